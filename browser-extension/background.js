@@ -0,0 +1,39 @@
+// Sends the URL of the active tab to the focusd native host whenever it may
+// have changed. The host keeps only the domain; nothing else leaves here.
+const api = globalThis.browser ?? globalThis.chrome;
+const BROWSER = globalThis.browser ? "firefox" : "chromium";
+
+let port = null;
+
+function connect() {
+  port = api.runtime.connectNative("focusd");
+  port.onDisconnect.addListener(() => {
+    port = null;
+  });
+}
+
+function send(tab) {
+  if (!port) connect();
+  port.postMessage({
+    url: tab ? tab.url : null,
+    incognito: tab ? tab.incognito : false,
+    browser: BROWSER,
+  });
+}
+
+async function reportActive() {
+  const win = await api.windows.getLastFocused({ populate: true }).catch(() => null);
+  if (!win || !win.focused) {
+    send(null);
+    return;
+  }
+  send(win.tabs.find((t) => t.active) ?? null);
+}
+
+api.tabs.onActivated.addListener(reportActive);
+api.tabs.onUpdated.addListener((_id, change, tab) => {
+  if (change.url && tab.active) reportActive();
+});
+api.windows.onFocusChanged.addListener(reportActive);
+api.runtime.onStartup.addListener(reportActive);
+reportActive();
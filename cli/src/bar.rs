@@ -0,0 +1,167 @@
+use clap::ValueEnum;
+use focusd_core::duration::format_duration;
+use focusd_core::ipc::{self, BarStatus, Request};
+use focusd_core::limits::LimitLevel;
+use focusd_core::db;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// How often today's totals are re-read from the database (syncs and edits)
+const REFRESH: Duration = Duration::from_secs(600);
+
+/// Apps listed in the tooltip
+const TOP: usize = 5;
+
+/// Today's usage by display name, kept by the daemon so bars don't hit SQLite
+#[derive(Default)]
+pub struct Today {
+    date: Option<NaiveDate>,
+    refreshed: Option<Instant>,
+    totals: HashMap<String, i64>,
+    // app id -> display name
+    names: HashMap<String, String>,
+}
+
+impl Today {
+    /// Adds `seconds` just logged for `app_id` and returns its display name
+    pub fn record(&mut self, db: &db::Db, app_id: &str, seconds: u64) -> anyhow::Result<String> {
+        // The database already holds what was just logged
+        if !self.refresh(db)? {
+            let name = self.name(db, app_id)?;
+            *self.totals.entry(name).or_default() += seconds as i64;
+        }
+        self.name(db, app_id)
+    }
+
+    /// Re-reads the totals on a new day or when they're old; true if it did
+    fn refresh(&mut self, db: &db::Db) -> anyhow::Result<bool> {
+        let date = db.today();
+        if self.date == Some(date) && self.refreshed.is_some_and(|t| t.elapsed() < REFRESH) {
+            return Ok(false);
+        }
+        self.totals = db.get_app_usage_range(date, date)?.into_iter().collect();
+        self.date = Some(date);
+        self.refreshed = Some(Instant::now());
+        Ok(true)
+    }
+
+    fn name(&mut self, db: &db::Db, app_id: &str) -> anyhow::Result<String> {
+        if let Some(name) = self.names.get(app_id) {
+            return Ok(name.clone());
+        }
+        let name = db.get_app(app_id)?.map_or_else(|| app_id.to_string(), |a| a.display_name);
+        self.names.insert(app_id.to_string(), name.clone());
+        Ok(name)
+    }
+
+    /// `app` is the focused app's display name, if any
+    pub fn status(&mut self, db: &db::Db, app: Option<String>, idle: bool) -> BarStatus {
+        if let Err(e) = self.refresh(db) {
+            eprintln!("Error reading today's totals: {}", e);
+        }
+        let mut top: Vec<(String, i64)> = self.totals.iter().map(|(name, s)| (name.clone(), *s)).collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let today = top.iter().map(|(_, s)| s).sum();
+        top.truncate(TOP);
+
+        BarStatus { app, today, top, idle, ..Default::default() }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    /// JSON lines for a Waybar custom module with `return-type = "json"`
+    Waybar,
+    /// Text with color tags for a Polybar `custom/script` with `tail = true`
+    Polybar,
+    /// Pango markup for an i3blocks block with `interval=persist`
+    I3blocks,
+}
+
+/// Prints a line per daemon tick until the daemon goes away
+pub fn run(format: Format) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout();
+    for response in ipc::subscribe(&Request::BarSubscribe)? {
+        let response = response?;
+        if !response.ok {
+            anyhow::bail!(response.error.unwrap_or_default());
+        }
+        let status: BarStatus = serde_json::from_value(response.data.unwrap_or_default())?;
+        let line = match format {
+            Format::Waybar => waybar(&status),
+            Format::Polybar => polybar(&status),
+            Format::I3blocks => i3blocks(&status),
+        };
+        // Bars read line by line, so every update has to go out right away
+        writeln!(stdout, "{}", line)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+fn text(status: &BarStatus) -> String {
+    match &status.app {
+        Some(app) => format!("{} · {}", format_duration(status.today), app),
+        None => format_duration(status.today),
+    }
+}
+
+fn tooltip(status: &BarStatus) -> String {
+    let mut lines = vec![format!("Today: {}", format_duration(status.today))];
+    lines.extend(status.top.iter().map(|(name, s)| format!("{}  {}", name, format_duration(*s))));
+    if status.limit != LimitLevel::Ok {
+        lines.push(format!("Limit: {}% used", status.limit_percent));
+    }
+    lines.join("\n")
+}
+
+fn level(limit: LimitLevel) -> &'static str {
+    match limit {
+        LimitLevel::Ok => "ok",
+        LimitLevel::Warning => "warning",
+        LimitLevel::Reached => "reached",
+    }
+}
+
+fn waybar(status: &BarStatus) -> String {
+    let mut class = vec![level(status.limit)];
+    if status.idle {
+        class.push("idle");
+    }
+    if status.focus {
+        class.push("focus");
+    }
+    serde_json::json!({
+        "text": text(status),
+        "tooltip": tooltip(status),
+        "class": class,
+        "percentage": status.limit_percent,
+    }).to_string()
+}
+
+fn color(limit: LimitLevel) -> Option<&'static str> {
+    match limit {
+        LimitLevel::Ok => None,
+        LimitLevel::Warning => Some("#e5c07b"),
+        LimitLevel::Reached => Some("#e06c75"),
+    }
+}
+
+fn polybar(status: &BarStatus) -> String {
+    // A literal `%{` in a window title would start a formatting tag
+    let text = text(status).replace("%{", "% {");
+    match color(status.limit) {
+        Some(color) => format!("%{{F{}}}{}%{{F-}}", color, text),
+        None => text,
+    }
+}
+
+fn i3blocks(status: &BarStatus) -> String {
+    let text = text(status).replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    match color(status.limit) {
+        Some(color) => format!("<span foreground=\"{}\">{}</span>", color, text),
+        None => text,
+    }
+}
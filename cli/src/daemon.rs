@@ -0,0 +1,441 @@
+use crate::bar::Today;
+use crate::digest;
+use crate::focus_mode::{Enforcer, FocusMode};
+use crate::terminal::{ShellReport, Terminals};
+use crate::{idle, window};
+use colored::*;
+use focusd_core::breaks::{BreakEvent, BreakTracker};
+use focusd_core::config::HookEvent;
+use focusd_core::hooks::HookRunner;
+use focusd_core::ipc::{self, BarStatus, Request, Response};
+use focusd_core::limits::LimitTracker;
+use focusd_core::notify::{Notifier, Urgency};
+use focusd_core::pomodoro::{Pomodoro, Recorder};
+use focusd_core::{browser, config, db, focus, process, project, sync};
+use std::collections::{hash_map::Entry, HashMap};
+use std::path::Path;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{mpsc, Arc, Mutex};
+use std::{fs, thread, time::{Duration, Instant}};
+
+/// What local helpers have told the daemon through the control socket
+struct State {
+    // Site of the active tab per browser family ("" when the extension
+    // didn't say), as last reported by the native host
+    browser_domains: HashMap<String, Option<String>>,
+    // Shells with the hook installed, by pid
+    shells: HashMap<u32, ShellReport>,
+    focus: FocusMode,
+    pomodoro: Pomodoro,
+    breaks: BreakTracker,
+    // Last status sent to `focusd bar`, and where to send the next one
+    bar: BarStatus,
+    bar_subscribers: Vec<mpsc::Sender<BarStatus>>,
+}
+
+type Shared = Arc<Mutex<State>>;
+
+pub fn run(db: &db::Db, config: &config::Config) -> anyhow::Result<()> {
+    let state = Shared::new(Mutex::new(State {
+        browser_domains: HashMap::new(),
+        shells: HashMap::new(),
+        focus: FocusMode::new(&config.focus.schedule),
+        pomodoro: Pomodoro::new(&config.pomodoro),
+        breaks: BreakTracker::new(&config.breaks),
+        bar: BarStatus::default(),
+        bar_subscribers: Vec::new(),
+    }));
+    spawn_control_server(state.clone())?;
+
+    let backend = window::Backend::detect();
+
+    println!("{}", "focusd daemon started...".green().bold());
+    println!("Backend: {}", backend.name());
+
+    spawn_compactor(config.retention.clone());
+
+    let mut last_sync: Option<Instant> = None;
+    let mut limits = LimitTracker::new(&config.limits);
+    let mut notifier: Option<Notifier> = None;
+    let mut enforcer = Enforcer::default();
+    focus::check_schedules(&config.focus.schedule);
+    db.close_stale_focus_sessions(chrono::Utc::now().timestamp())?;
+    let mut recorder = Recorder::default();
+    db.close_stale_pomodoros(chrono::Utc::now().timestamp())?;
+    // Break reminders shown and not yet taken, by name -> row id
+    let mut pending_breaks: HashMap<String, i64> = HashMap::new();
+    let hooks = HookRunner::new(&config.hooks);
+    let mut today = db.today();
+    write_digest(db, config);
+    let mut idle_since: Option<Instant> = None;
+    let mut last_app: Option<String> = None;
+    let mut totals = Today::default();
+    let mut terminals = Terminals::default();
+
+    loop {
+        thread::sleep(Duration::from_secs(config.interval));
+
+        if let Some(dir) = &config.sync_dir {
+            let due = last_sync.is_none_or(|t| t.elapsed() >= Duration::from_secs(config.sync_interval));
+            if due {
+                if let Err(e) = sync::sync_dir(db, dir) {
+                    eprintln!("Error syncing with {}: {}", dir.display(), e);
+                }
+                last_sync = Some(Instant::now());
+            }
+        }
+
+        let date = db.today();
+        if date != today {
+            if !hooks.is_empty() {
+                let total: i64 = db.get_app_usage_range(today, today).map(|apps| apps.iter().map(|(_, s)| s).sum()).unwrap_or(0);
+                hooks.fire(HookEvent::DayRollover, serde_json::json!({
+                    "date": date.to_string(),
+                    "previous_date": today.to_string(),
+                    "previous_total_seconds": total,
+                }));
+            }
+            today = date;
+            write_digest(db, config);
+        }
+
+        let focus_session = state.lock().unwrap().focus.current();
+        if let Err(e) = enforcer.sync(db, focus_session) {
+            eprintln!("Error recording focus session: {}", e);
+        }
+
+        // Phase changes are announced even while idle: that's when breaks end
+        let change = recorder.sync(db, &mut state.lock().unwrap().pomodoro);
+        match change {
+            Ok(Some(change)) => show(&mut notifier, &change.summary(), &change.body(), Urgency::Normal),
+            Ok(None) => {}
+            Err(e) => eprintln!("Error recording Pomodoro: {}", e),
+        }
+
+        let idle = idle::is_session_idle();
+        let input_idle = backend.input_idle();
+        let break_events = state.lock().unwrap().breaks.tick(idle, input_idle, config.interval);
+        for event in break_events {
+            if let Err(e) = record_break(db, &mut pending_breaks, &mut notifier, event) {
+                eprintln!("Error recording break: {}", e);
+            }
+        }
+
+        match (idle, idle_since) {
+            (true, None) => {
+                idle_since = Some(Instant::now());
+                hooks.fire(HookEvent::IdleStart, serde_json::json!({}));
+            }
+            (false, Some(since)) => {
+                idle_since = None;
+                hooks.fire(HookEvent::IdleEnd, serde_json::json!({ "idle_seconds": since.elapsed().as_secs() }));
+            }
+            _ => {}
+        }
+
+        if idle {
+            enforcer.reset();
+            publish(&state, db, totals.status(db, None, true), &mut limits);
+            continue;
+        }
+
+        let focused = backend.focused_window();
+        if focused.is_none() {
+            enforcer.reset();
+            publish(&state, db, totals.status(db, None, false), &mut limits);
+        }
+        if let Some(window) = focused {
+            // Skip logging if app_id is completely empty/whitespace (fixes blank line bug)
+            if window.app_id.trim().is_empty() {
+                continue;
+            }
+
+            // `java`, `electron` & co. get the name of the program they run
+            let process = window.pid.and_then(process::ProcessInfo::read);
+            let app_id = process::attribute_app_id(&window.app_id, process.as_ref());
+
+            let mut context = db::FocusContext::default();
+            if browser::is_browser(&app_id) {
+                let state = state.lock().unwrap();
+                let domains = &state.browser_domains;
+                context.domain = domains.get(browser::family(&app_id)).or_else(|| domains.get("")).cloned().flatten();
+            }
+            if let (true, Some(pid)) = (project::is_terminal(&app_id), window.pid) {
+                // Looking into the terminal can be slow; the control socket shouldn't wait on it
+                let shells = state.lock().unwrap().shells.clone();
+                (context.command, context.cwd) = terminals.context(pid, &shells);
+            }
+            context.process = process;
+
+            match enforcer.check(db, &config.focus, &backend, &window, &app_id) {
+                Ok(Some(v)) => {
+                    state.lock().unwrap().focus.add_violation();
+                    let name = db.get_app(&v.app_id).ok().flatten().map_or(v.app_id.clone(), |a| a.display_name);
+                    let body = match &v.result {
+                        Ok(()) => format!("Focus mode is on ({})", v.action.as_str()),
+                        Err(e) => format!("Focus mode is on, but {} failed: {}", v.action.as_str(), e),
+                    };
+                    show(&mut notifier, &format!("{} is blocked", name), &body, Urgency::Normal);
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Error checking focus mode: {}", e),
+            }
+
+            if let Err(e) = db.log_usage(&app_id, &window.title, config.interval, &context) {
+                eprintln!("Error writing to DB: {}", e);
+                continue;
+            }
+            if let Err(e) = recorder.record(db, &app_id, config.interval) {
+                eprintln!("Error recording Pomodoro: {}", e);
+            }
+            let name = totals.record(db, &app_id, config.interval).unwrap_or_else(|e| {
+                eprintln!("Error reading today's totals: {}", e);
+                app_id.clone()
+            });
+
+            if last_app.as_deref() != Some(app_id.as_str()) {
+                hooks.fire(HookEvent::FocusChanged, serde_json::json!({
+                    "app_id": app_id,
+                    "app": name,
+                    "title": window.title,
+                    "previous_app_id": last_app,
+                    "domain": context.domain,
+                    "command": context.command,
+                    "cwd": context.cwd,
+                }));
+                last_app = Some(app_id.clone());
+            }
+
+            if !limits.is_empty() {
+                match limits.record(db, &app_id, config.interval) {
+                    Ok(alerts) => alerts.iter().for_each(|a| {
+                        let urgency = if a.percent >= 100 { Urgency::Critical } else { Urgency::Normal };
+                        show(&mut notifier, &a.summary(), &a.body(), urgency);
+                        if a.percent >= 100 {
+                            hooks.fire(HookEvent::LimitReached, serde_json::json!({
+                                "target": a.target,
+                                "period": a.period,
+                                "used_seconds": a.used,
+                                "budget_seconds": a.budget,
+                            }));
+                        }
+                    }),
+                    Err(e) => eprintln!("Error checking limits: {}", e),
+                }
+            }
+            publish(&state, db, totals.status(db, Some(name), false), &mut limits);
+        }
+    }
+}
+
+/// Sends the tick's status to every `focusd bar`, forgetting those that quit
+fn publish(state: &Shared, db: &db::Db, mut status: BarStatus, limits: &mut LimitTracker) {
+    match limits.level(db) {
+        Ok(level) => (status.limit, status.limit_percent) = level,
+        Err(e) => eprintln!("Error checking limits: {}", e),
+    }
+    let mut state = state.lock().unwrap();
+    status.focus = state.focus.status().active;
+    state.bar_subscribers.retain(|s| s.send(status.clone()).is_ok());
+    state.bar = status;
+}
+
+/// Shows due reminders and logs what became of them. A reminder that comes
+/// back before its break was taken stays on the same row.
+fn record_break(
+    db: &db::Db,
+    pending: &mut HashMap<String, i64>,
+    notifier: &mut Option<Notifier>,
+    event: BreakEvent,
+) -> anyhow::Result<()> {
+    let now = chrono::Utc::now().timestamp();
+    match event {
+        BreakEvent::Due { name, message, active } => {
+            let summary = format!("{}: {} without a break", name, focusd_core::duration::format_duration(active as i64));
+            show(notifier, &summary, &message, Urgency::Normal);
+            if let Entry::Vacant(entry) = pending.entry(name) {
+                let id = db.log_break_reminder(entry.key(), now)?;
+                entry.insert(id);
+            }
+        }
+        BreakEvent::Taken { name } => {
+            if let Some(id) = pending.remove(&name) {
+                db.mark_break_taken(id, now)?;
+            }
+        }
+        BreakEvent::Snoozed { name } => {
+            if let Some(id) = pending.get(&name) {
+                db.add_break_snooze(*id)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Shows a desktop notification, connecting to the session bus on first use.
+/// Without a notification server the message at least ends up in the daemon's log.
+fn show(notifier: &mut Option<Notifier>, summary: &str, body: &str, urgency: Urgency) {
+    if notifier.is_none() {
+        match Notifier::session() {
+            Ok(n) => *notifier = Some(n),
+            Err(e) => eprintln!("Warning: no D-Bus session for notifications: {}", e),
+        }
+    }
+    let sent = notifier.as_ref().map(|n| n.notify(summary, body, urgency));
+    match sent {
+        Some(Ok(_)) => {}
+        Some(Err(e)) => {
+            eprintln!("Error sending notification: {}", e);
+            // Reconnect next time in case the bus went away
+            *notifier = None;
+        }
+        None => println!("{} — {}", summary, body),
+    }
+}
+
+/// Applies the retention policy every few hours on its own connection,
+/// so a slow prune never delays logging.
+fn spawn_compactor(policy: config::Retention) {
+    thread::spawn(move || {
+        let db = match db::Db::init() {
+            Ok(db) => db,
+            Err(e) => {
+                eprintln!("Warning: compactor could not open DB: {}", e);
+                return;
+            }
+        };
+        loop {
+            if let Err(e) = db.prune(&policy, false) {
+                eprintln!("Error pruning DB: {}", e);
+            }
+            thread::sleep(Duration::from_secs(policy.prune_every_hours.max(1) * 3600));
+        }
+    });
+}
+
+/// Listens on the control socket. A socket file left behind by a crashed
+/// daemon is replaced; one that still answers means we're already running.
+fn spawn_control_server(state: Shared) -> anyhow::Result<()> {
+    ipc::create_socket_dir()?;
+    let path = ipc::socket_path();
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            anyhow::bail!("another focusd daemon is already listening on {}", path.display());
+        }
+        fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let state = state.clone();
+                    thread::spawn(move || handle_client(stream, &state));
+                }
+                Err(e) => eprintln!("Error accepting control connection: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle_client(stream: UnixStream, state: &Shared) {
+    let reader = BufReader::new(&stream);
+    for line in reader.lines() {
+        let Ok(line) = line else { return };
+        if line.trim().is_empty() { continue; }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::BarSubscribe) => return stream_bar(&stream, state),
+            Ok(request) => handle_request(request, state),
+            Err(e) => Response::error(format!("invalid request: {}", e)),
+        };
+        let Ok(mut out) = serde_json::to_string(&response) else { return };
+        out.push('\n');
+        if (&stream).write_all(out.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+/// Writes the current status and then every update until the bar goes away
+fn stream_bar(stream: &UnixStream, state: &Shared) {
+    let (sender, receiver) = mpsc::channel();
+    let current = {
+        let mut state = state.lock().unwrap();
+        state.bar_subscribers.push(sender);
+        state.bar.clone()
+    };
+    for status in std::iter::once(current).chain(receiver) {
+        let Ok(mut out) = serde_json::to_string(&Response::with_data(serde_json::json!(status))) else { return };
+        out.push('\n');
+        if (&*stream).write_all(out.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+fn handle_request(request: Request, state: &Shared) -> Response {
+    match request {
+        Request::BrowserTab { browser, domain } => {
+            state.lock().unwrap().browser_domains.insert(browser.unwrap_or_default(), domain);
+            Response::ok()
+        }
+        Request::ShellState { pid, cwd, command } => {
+            let mut state = state.lock().unwrap();
+            // Forget shells that have exited
+            state.shells.retain(|pid, _| Path::new(&format!("/proc/{}", pid)).exists());
+            state.shells.insert(pid, ShellReport { cwd, command, at: Instant::now() });
+            Response::ok()
+        }
+        Request::FocusStart { seconds } => {
+            let status = state.lock().unwrap().focus.start(seconds);
+            Response::with_data(serde_json::json!(status))
+        }
+        Request::FocusStop => match state.lock().unwrap().focus.stop() {
+            Ok(()) => Response::ok(),
+            Err(e) => Response::error(e.to_string()),
+        },
+        Request::FocusStatus => {
+            let status = state.lock().unwrap().focus.status();
+            Response::with_data(serde_json::json!(status))
+        }
+        Request::PomodoroStart { work, short_break } => {
+            let status = state.lock().unwrap().pomodoro.start(work, short_break);
+            Response::with_data(serde_json::json!(status))
+        }
+        Request::PomodoroStop => match state.lock().unwrap().pomodoro.stop() {
+            Ok(()) => Response::ok(),
+            Err(e) => Response::error(e.to_string()),
+        },
+        Request::PomodoroStatus => {
+            let status = state.lock().unwrap().pomodoro.status();
+            Response::with_data(serde_json::json!(status))
+        }
+        Request::BreakSnooze { name, seconds } => match state.lock().unwrap().breaks.snooze(name.as_deref(), seconds) {
+            Ok(names) => Response::with_data(serde_json::json!(names)),
+            Err(e) => Response::error(e.to_string()),
+        },
+        // Handled by `stream_bar`
+        Request::BarSubscribe => Response::error("bar_subscribe needs its own connection"),
+        Request::BreakStatus => {
+            let status = state.lock().unwrap().breaks.status();
+            Response::with_data(serde_json::json!(status))
+        }
+    }
+}
+
+/// Writes last week's digest if [digest] dir is set and it isn't there yet.
+/// Runs at start and on each rollover: Monday's writes it, later ones catch
+/// up a missed Monday and otherwise find the file and do nothing
+fn write_digest(db: &db::Db, config: &config::Config) {
+    match digest::write_due(db, config) {
+        Ok(Some(path)) => println!("Wrote weekly digest to {}", path.display()),
+        Ok(None) => {}
+        Err(e) => eprintln!("Error writing weekly digest: {}", e),
+    }
+}
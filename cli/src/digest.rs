@@ -0,0 +1,334 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use focusd_core::compare::{self, Comparison, DateRange, Delta};
+use focusd_core::config::{self, DigestFormat, LimitPeriod};
+use focusd_core::db;
+use focusd_core::duration::format_duration;
+use focusd_core::goals::{Goal, GoalKind};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// `focusd digest`: a one-page summary of a week, as a self-contained HTML
+// file (inline CSS and SVG, nothing fetched) or Markdown. When [digest] dir
+// is set the daemon writes last week's on the first start or day rollover
+// that finds it missing, which is Monday unless the machine was off.
+
+/// Apps and categories listed in a digest
+const TOP: usize = 10;
+
+/// Everything a digest shows, read up front so both formats agree
+struct Digest {
+    title: String,
+    days: Vec<(NaiveDate, i64)>,
+    comparison: Comparison,
+    goals: Vec<GoalRow>,
+}
+
+struct GoalRow {
+    name: String,
+    target: String,
+    result: String,
+    /// None when the goal applied on none of the week's days
+    met: Option<bool>,
+    streak: u32,
+}
+
+/// The ISO week label for a Monday-to-Sunday range, e.g. `2026-W41`
+pub fn label(range: &DateRange) -> String {
+    let week = range.start.iso_week();
+    if range.days() == 7 && range.start.weekday() == chrono::Weekday::Mon {
+        format!("{}-W{:02}", week.year(), week.week())
+    } else {
+        format!("{}..{}", range.start, range.end)
+    }
+}
+
+fn build(db: &db::Db, config: &config::Config, range: DateRange) -> anyhow::Result<Digest> {
+    let totals = db.get_daily_totals(range.start, range.end)?;
+    let days = range.start.iter_days()
+        .take_while(|d| *d <= range.end)
+        .map(|d| (d, totals.get(&d.to_string()).copied().unwrap_or(0)))
+        .collect();
+
+    let shift = Duration::days(range.days());
+    let previous = DateRange { start: range.start - shift, end: range.end - shift };
+    let comparison = compare::compare(db, range, previous)?;
+
+    // Goals as they stood when the week ended, or today for this week
+    let until = range.end.min(db.today());
+    let mut goals = Vec::new();
+    for goal in Goal::from_rules(&config.goals) {
+        let status = goal.status_at(db, until)?;
+        let bound = match status.kind {
+            GoalKind::Min => "at least",
+            GoalKind::Max => "at most",
+        };
+        let per = match status.period {
+            LimitPeriod::Day => "a day",
+            LimitPeriod::Week => "a week",
+        };
+        let (result, met) = match goal.period() {
+            LimitPeriod::Day => {
+                match goal.days_met(db, range.start, until)? {
+                    (_, 0) => ("not scheduled".to_string(), None),
+                    (met, scheduled) => (format!("{} of {} days", met, scheduled), Some(met == scheduled)),
+                }
+            }
+            LimitPeriod::Week => (format_duration(status.used), Some(status.met())),
+        };
+        goals.push(GoalRow {
+            name: status.name.clone(),
+            target: format!("{} {} {}", bound, format_duration(status.target), per),
+            result,
+            met,
+            streak: status.current_streak,
+        });
+    }
+
+    Ok(Digest {
+        title: format!("focusd digest {}", label(&range)),
+        days,
+        comparison,
+        goals,
+    })
+}
+
+/// Renders the digest for `range`
+pub fn render(db: &db::Db, config: &config::Config, range: DateRange, format: DigestFormat) -> anyhow::Result<String> {
+    let digest = build(db, config, range)?;
+    Ok(match format {
+        DigestFormat::Html => html(&digest),
+        DigestFormat::Md => markdown(&digest),
+    })
+}
+
+/// Writes last week's digest into [digest] dir unless it's there already.
+/// Called on every start and rollover, so a Monday spent with the machine
+/// off is caught up later in the week; the check is one `exists` call.
+pub fn write_due(db: &db::Db, config: &config::Config) -> anyhow::Result<Option<PathBuf>> {
+    let Some(dir) = &config.digest.dir else {
+        return Ok(None);
+    };
+    let range = DateRange::parse("last-week", db.today())?;
+    let path = dir.join(format!("focusd-{}.{}", label(&range), config.digest.format.extension()));
+    if path.exists() {
+        return Ok(None);
+    }
+    fs::create_dir_all(dir)?;
+    write(&path, &render(db, config, range, config.digest.format)?)?;
+    Ok(Some(path))
+}
+
+/// Writes through a temporary file so a half-written digest is never left behind
+pub fn write(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn change(d: &Delta) -> String {
+    match (d.new, d.gone, d.percent) {
+        (true, _, _) => "new".to_string(),
+        (_, true, _) => "gone".to_string(),
+        (_, _, Some(p)) => format!("{}{} ({:+.0}%)", if d.change > 0 { "+" } else { "" }, format_duration(d.change), p),
+        _ => String::new(),
+    }
+}
+
+fn mark(met: Option<bool>) -> &'static str {
+    match met {
+        Some(true) => "✓",
+        Some(false) => "✗",
+        None => "–",
+    }
+}
+
+fn days_line(digest: &Digest) -> (i64, i64) {
+    let total: i64 = digest.days.iter().map(|(_, s)| s).sum();
+    let active = digest.days.iter().filter(|(_, s)| *s > 0).count() as i64;
+    (total, total / active.max(1))
+}
+
+// === MARKDOWN ===
+
+fn markdown(digest: &Digest) -> String {
+    let c = &digest.comparison;
+    let (total, average) = days_line(digest);
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", digest.title);
+    let _ = writeln!(out, "{} – {}\n", c.current.start, c.current.end);
+    let _ = writeln!(
+        out,
+        "**{}** in total, {} a day on average, {} the week before: {}.\n",
+        format_duration(total), format_duration(average), format_duration(c.total.previous), change(&c.total)
+    );
+
+    out.push_str("## Days\n\n| Day | Time |\n|---|---:|\n");
+    for (date, seconds) in &digest.days {
+        let _ = writeln!(out, "| {} | {} |", date.format("%a %Y-%m-%d"), format_duration(*seconds));
+    }
+
+    for (title, deltas) in [("Top apps", &c.apps), ("Categories", &c.categories)] {
+        let _ = writeln!(out, "\n## {}\n", title);
+        let top = top(deltas);
+        if top.is_empty() {
+            out.push_str("No data recorded.\n");
+            continue;
+        }
+        out.push_str("| Name | Time | Week before | Change |\n|---|---:|---:|---:|\n");
+        for d in top {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                d.name.replace('|', "\\|"), format_duration(d.current), format_duration(d.previous), change(d)
+            );
+        }
+    }
+
+    if !digest.goals.is_empty() {
+        out.push_str("\n## Goals\n\n| Goal | Target | Result | Streak |\n|---|---|---|---:|\n");
+        for g in &digest.goals {
+            let _ = writeln!(
+                out,
+                "| {} {} | {} | {} | {} |",
+                mark(g.met), g.name.replace('|', "\\|"), g.target, g.result, g.streak
+            );
+        }
+    }
+    out
+}
+
+/// Biggest first, leaving out what wasn't used this week
+fn top(deltas: &[Delta]) -> Vec<&Delta> {
+    let mut top: Vec<&Delta> = deltas.iter().filter(|d| d.current > 0).collect();
+    top.sort_by_key(|d| std::cmp::Reverse(d.current));
+    top.truncate(TOP);
+    top
+}
+
+// === HTML ===
+
+const STYLE: &str = "body{font:15px/1.5 system-ui,sans-serif;max-width:720px;margin:2em auto;padding:0 1em;color:#222}\
+h1{font-size:1.5em;margin-bottom:0}h2{font-size:1.1em;margin-top:2em}.dim{color:#777}\
+table{border-collapse:collapse;width:100%}td,th{padding:.25em .5em;text-align:left}td.n,th.n{text-align:right}\
+tr:nth-child(even){background:#f5f5f5}.up{color:#2a7d2a}.down{color:#b33}svg text{font:12px system-ui,sans-serif;fill:#444}";
+
+fn html(digest: &Digest) -> String {
+    let c = &digest.comparison;
+    let (total, average) = days_line(digest);
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>\n",
+        escape(&digest.title), STYLE
+    );
+    let _ = writeln!(out, "<h1>{}</h1><p class=\"dim\">{} – {}</p>", escape(&digest.title), c.current.start, c.current.end);
+    let _ = writeln!(
+        out,
+        "<p><b>{}</b> in total, {} a day on average, {} the week before: {}.</p>",
+        format_duration(total), format_duration(average), format_duration(c.total.previous), html_change(&c.total)
+    );
+
+    out.push_str("<h2>Days</h2>\n");
+    out.push_str(&day_chart(&digest.days));
+
+    for (title, deltas) in [("Top apps", &c.apps), ("Categories", &c.categories)] {
+        let _ = writeln!(out, "<h2>{}</h2>", title);
+        let top = top(deltas);
+        if top.is_empty() {
+            out.push_str("<p class=\"dim\">No data recorded.</p>\n");
+            continue;
+        }
+        out.push_str(&bar_chart(&top));
+    }
+
+    out.push_str("<h2>Compared with the week before</h2>\n<table><tr><th>App</th><th class=\"n\">This week</th><th class=\"n\">Week before</th><th class=\"n\">Change</th></tr>\n");
+    for d in c.apps.iter().take(TOP) {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td></tr>",
+            escape(&d.name), format_duration(d.current), format_duration(d.previous), html_change(d)
+        );
+    }
+    out.push_str("</table>\n");
+
+    if !digest.goals.is_empty() {
+        out.push_str("<h2>Goals</h2>\n<table><tr><th>Goal</th><th>Target</th><th>Result</th><th class=\"n\">Streak</th></tr>\n");
+        for g in &digest.goals {
+            let _ = writeln!(
+                out,
+                "<tr><td><span class=\"{}\">{}</span> {}</td><td>{}</td><td>{}</td><td class=\"n\">{}</td></tr>",
+                match g.met {
+                    Some(true) => "up",
+                    Some(false) => "down",
+                    None => "dim",
+                },
+                mark(g.met),
+                escape(&g.name), g.target, g.result, g.streak
+            );
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
+
+fn html_change(d: &Delta) -> String {
+    let class = match d.change {
+        c if c > 0 => "up",
+        c if c < 0 => "down",
+        _ => "dim",
+    };
+    format!("<span class=\"{}\">{}</span>", class, escape(&change(d)))
+}
+
+/// Vertical bars, one per day, with the hours on top
+fn day_chart(days: &[(NaiveDate, i64)]) -> String {
+    const HEIGHT: i64 = 160;
+    let width = 680;
+    let slot = width / days.len().max(1) as i64;
+    let max = days.iter().map(|(_, s)| *s).max().unwrap_or(0).max(1);
+
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\" width=\"100%\" role=\"img\">\n", width, HEIGHT + 40);
+    for (i, (date, seconds)) in days.iter().enumerate() {
+        let h = seconds * HEIGHT / max;
+        let x = i as i64 * slot;
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"3\" fill=\"#4a78c2\"><title>{}: {}</title></rect>",
+            x + slot / 8, 20 + HEIGHT - h, slot * 3 / 4, h, date, format_duration(*seconds)
+        );
+        let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{:.1}h</text>", x + slot / 2, 14 + HEIGHT - h, *seconds as f64 / 3600.0);
+        let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>", x + slot / 2, HEIGHT + 36, date.format("%a"));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Horizontal bars with the name on the left and the time on the right
+fn bar_chart(rows: &[&Delta]) -> String {
+    const ROW: i64 = 24;
+    let (width, name_width, time_width): (i64, i64, i64) = (680, 180, 80);
+    let bar_width = width - name_width - time_width;
+    let max = rows.first().map_or(1, |d| d.current.max(1));
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\" width=\"100%\" role=\"img\">\n",
+        width, rows.len() as i64 * ROW
+    );
+    for (i, d) in rows.iter().enumerate() {
+        let y = i as i64 * ROW;
+        let w = (d.current * bar_width / max).max(1);
+        let name: String = d.name.chars().take(24).collect();
+        let _ = writeln!(svg, "<text x=\"0\" y=\"{}\">{}</text>", y + 16, escape(&name));
+        let _ = writeln!(svg, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"16\" rx=\"3\" fill=\"#4a78c2\"/>", name_width, y + 4, w);
+        let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>", width, y + 16, format_duration(d.current));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
@@ -0,0 +1,170 @@
+use crate::window::{Backend, FocusedWindow};
+use focusd_core::ipc::FocusStatus;
+use focusd_core::{config, db, focus};
+use chrono::{Local, Utc};
+
+/// A focus session in progress
+struct Active {
+    source: &'static str,
+    started_at: i64,
+    until: i64,
+    violations: u32,
+}
+
+/// Whether focus mode is on. Lives in the daemon's shared state so the
+/// control socket can start and stop it.
+#[derive(Default)]
+pub struct FocusMode {
+    active: Option<Active>,
+    schedule: Vec<config::FocusSchedule>,
+    // A scheduled window stopped by hand stays off until it would have ended
+    paused_until: Option<i64>,
+}
+
+impl FocusMode {
+    pub fn new(schedule: &[config::FocusSchedule]) -> Self {
+        FocusMode { schedule: schedule.to_vec(), ..Default::default() }
+    }
+
+    pub fn start(&mut self, seconds: u64) -> FocusStatus {
+        let now = Utc::now().timestamp();
+        self.active = Some(Active { source: "manual", started_at: now, until: now + seconds as i64, violations: 0 });
+        self.status()
+    }
+
+    pub fn stop(&mut self) -> anyhow::Result<()> {
+        let stopped = self.active.take().ok_or_else(|| anyhow::anyhow!("focus mode is not running"))?;
+        // Otherwise the schedule would turn it straight back on. A scheduled
+        // session knows when its window ends; a manual one may sit in a window.
+        self.paused_until = match stopped.source {
+            "schedule" => Some(stopped.until),
+            _ => focus::scheduled_until(&self.schedule, Local::now()),
+        };
+        Ok(())
+    }
+
+    /// (started_at, source, until) of the session that should be running now
+    pub fn current(&mut self) -> Option<(i64, &'static str, i64)> {
+        self.refresh();
+        self.active.as_ref().map(|a| (a.started_at, a.source, a.until))
+    }
+
+    pub fn add_violation(&mut self) {
+        if let Some(active) = self.active.as_mut() {
+            active.violations += 1;
+        }
+    }
+
+    pub fn status(&self) -> FocusStatus {
+        match &self.active {
+            Some(a) => FocusStatus {
+                active: true,
+                source: Some(a.source.to_string()),
+                started_at: Some(a.started_at),
+                until: Some(a.until),
+                violations: a.violations,
+            },
+            None => FocusStatus::default(),
+        }
+    }
+
+    /// Ends an expired session and starts one when the schedule says so
+    fn refresh(&mut self) {
+        let now = Utc::now().timestamp();
+        if self.active.as_ref().is_some_and(|a| a.until <= now) {
+            self.active = None;
+        }
+        if self.active.is_some() || self.paused_until.is_some_and(|p| p > now) {
+            return;
+        }
+        if let Some(until) = focus::scheduled_until(&self.schedule, Local::now()) {
+            self.active = Some(Active { source: "schedule", started_at: now, until, violations: 0 });
+        }
+    }
+}
+
+/// What happened when a blocked app was focused
+pub struct Violation {
+    pub app_id: String,
+    pub action: config::FocusAction,
+    pub result: anyhow::Result<()>,
+}
+
+/// The daemon-side half of focus mode: mirrors sessions into the database and
+/// acts on blocked windows. Holds no lock on the shared state, so the control
+/// socket isn't kept waiting while it runs external tools.
+#[derive(Default)]
+pub struct Enforcer {
+    // (row id, started_at) of the session recorded in the database
+    recorded: Option<(i64, i64)>,
+    // Blocked app focused right now; one violation per stretch of focus
+    blocked: Option<String>,
+    // (window id, pid) last acted on; acted on again only after focus moves
+    acted: Option<(Option<u64>, Option<u32>)>,
+    // Last allowed window, for backends that switch back to it
+    last_allowed: Option<u64>,
+}
+
+impl Enforcer {
+    /// Brings the database in line with `current` (from `FocusMode::current`).
+    /// Call once per tick.
+    pub fn sync(&mut self, db: &db::Db, current: Option<(i64, &'static str, i64)>) -> anyhow::Result<()> {
+        let now = Utc::now().timestamp();
+
+        if let Some((id, started)) = self.recorded {
+            if current.map(|c| c.0) != Some(started) {
+                db.end_focus_session(id, now)?;
+                self.recorded = None;
+                self.reset();
+            }
+        }
+        if let (None, Some((started, source, until))) = (self.recorded, current) {
+            let id = db.start_focus_session(source, started, until)?;
+            self.recorded = Some((id, started));
+        }
+        Ok(())
+    }
+
+    /// Nothing focused (or idle): the next blocked window is a new violation
+    pub fn reset(&mut self) {
+        self.blocked = None;
+        self.acted = None;
+    }
+
+    /// Checks the focused window against the blocklist. Returns a violation the
+    /// first time a blocked app gets focus, which the caller adds to the
+    /// session. The action runs once per blocked window (or process) until
+    /// something else is focused.
+    pub fn check(
+        &mut self,
+        db: &db::Db,
+        config: &config::Focus,
+        backend: &Backend,
+        window: &FocusedWindow,
+        app_id: &str,
+    ) -> anyhow::Result<Option<Violation>> {
+        let Some((session_id, _)) = self.recorded else { return Ok(None) };
+
+        let app = db.get_app(app_id)?;
+        if !focus::is_blocked(&config.block, app_id, app.as_ref()) {
+            self.reset();
+            self.last_allowed = window.id;
+            return Ok(None);
+        }
+
+        let target = (window.id, window.pid);
+        let result = if self.acted != Some(target) {
+            self.acted = Some(target);
+            backend.enforce(config.action, window, &config.workspace, self.last_allowed)
+        } else {
+            Ok(())
+        };
+        if self.blocked.as_deref() == Some(app_id) {
+            return Ok(None);
+        }
+
+        self.blocked = Some(app_id.to_string());
+        db.log_focus_violation(session_id, app_id, config.action.as_str())?;
+        Ok(Some(Violation { app_id: app_id.to_string(), action: config.action, result }))
+    }
+}
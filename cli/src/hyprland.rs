@@ -1,13 +1,19 @@
 use std::process::Command;
 use serde::Deserialize;
+use crate::window::FocusedWindow;
 
 #[derive(Deserialize)]
 struct HyprWindow {
     class: String,
     title: String,
+    #[serde(default)]
+    pid: i64,
+    // e.g. "0x55d1c2a3b4c0"
+    #[serde(default)]
+    address: String,
 }
 
-pub fn get_focused_window() -> Option<(String, String)> {
+pub fn get_focused_window() -> Option<FocusedWindow> {
     // 1. Run 'hyprctl activewindow -j'
     let output = Command::new("hyprctl")
         .arg("activewindow")
@@ -33,8 +39,31 @@ pub fn get_focused_window() -> Option<(String, String)> {
                 return None;
             }
             // Hyprland 'class' is the stable App ID.
-            Some((window.class, window.title))
+            // pid is -1 for windows without a process (e.g. some layers)
+            Some(FocusedWindow {
+                app_id: window.class,
+                title: window.title,
+                pid: u32::try_from(window.pid).ok().filter(|p| *p > 0),
+                id: u64::from_str_radix(window.address.trim_start_matches("0x"), 16).ok(),
+            })
         }
         Err(_) => None,
     }
-}
\ No newline at end of file
+}
+
+/// Moves a window to another workspace without following it
+pub fn move_to_workspace(address: u64, workspace: &str) -> anyhow::Result<()> {
+    // Bare words would be read as workspace ids; names need the prefix
+    let workspace = if workspace.contains(':') || workspace.parse::<i64>().is_ok() {
+        workspace.to_string()
+    } else {
+        format!("name:{}", workspace)
+    };
+    let status = Command::new("hyprctl")
+        .args(["dispatch", "movetoworkspacesilent", &format!("{},address:0x{:x}", workspace, address)])
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("hyprctl dispatch failed");
+    }
+    Ok(())
+}
@@ -2,14 +2,23 @@
 mod x11;
 mod hyprland;
 mod idle;
+mod window;
+mod daemon;
+mod native_host;
+mod terminal;
+mod focus_mode;
+mod bar;
+mod top;
+mod digest;
 
 // External Modules (From Core)
-use focusd_core::{db, config}; // <--- CHANGED THIS
+use focusd_core::{db, config, sync, duration, desktop, process, project, ipc, limits, goals, compare, timezone, timeline}; // <--- CHANGED THIS
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
 use std::{thread, time::Duration};
-use std::env;
+use std::path::PathBuf;
+use chrono::NaiveDate;
 
 /// focusd - Privacy respecting screen time tracker
 #[derive(Parser)]
@@ -18,6 +27,15 @@ use std::env;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Only report usage recorded on this device (hostname or id)
+    #[arg(long, global = true)]
+    device: Option<String>,
+
+    /// Compute days and hours in this time zone (e.g. Europe/Berlin, +05:30,
+    /// UTC, local) instead of the one each session was recorded in
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    tz: Option<timezone::Zone>,
 }
 
 #[derive(Subcommand)]
@@ -27,34 +45,320 @@ enum Commands {
     Week,
     Export,
     Listen, 
+    /// Live full-screen dashboard of today, this week or this month
+    Top,
+    /// Usage over the last few days, grouped by app, project or device
+    Report {
+        #[arg(long, value_enum, default_value_t = ReportBy::App)]
+        by: ReportBy,
+        /// How many days back to include (0 = today only)
+        #[arg(long, default_value_t = 7)]
+        days: i64,
+    },
+    /// Weekday × hour heatmap of when the time goes
+    Heatmap {
+        /// How many days back to include
+        #[arg(long, default_value_t = 28)]
+        days: i64,
+    },
+    /// Per-app and per-category changes between two date ranges
+    Compare {
+        /// today, yesterday, this-week, last-week, this-month, last-month,
+        /// 7d, YYYY-MM-DD, YYYY-Www, YYYY-MM or FROM..TO
+        #[arg(default_value = "this-week")]
+        range: String,
+        /// The range to compare against
+        #[arg(default_value = "last-week")]
+        against: String,
+        /// Print the comparison as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Weekly summary as a self-contained HTML page or Markdown
+    Digest {
+        /// An ISO week like 2026-W41, or any range `compare` takes
+        #[arg(long, default_value = "last-week")]
+        week: String,
+        /// html or md
+        #[arg(long, default_value = "html")]
+        format: config::DigestFormat,
+        /// Write to this file instead of printing
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Gantt-style bars of one day's apps (default: today)
+    Timeline {
+        date: Option<NaiveDate>,
+        /// Also list every stretch in order
+        #[arg(long)]
+        list: bool,
+    },
+    /// Native messaging host for the browser extension (started by the browser)
+    NativeHost {
+        /// Register the host with Firefox (and Chrome with --chrome-extension-id)
+        #[arg(long)]
+        install: bool,
+        /// Id of the unpacked extension in chrome://extensions
+        #[arg(long, requires = "install")]
+        chrome_extension_id: Option<String>,
+    },
+    /// Print the shell integration to eval in your rc file (zsh, bash or fish)
+    ShellHook { shell: String },
+    /// Sent by the shell hook; reports a shell's directory and running command
+    #[command(hide = true)]
+    ShellReport {
+        #[arg(long)]
+        pid: u32,
+        #[arg(long)]
+        cwd: String,
+        #[arg(long)]
+        command: Option<String>,
+    },
+    /// Stream today's total and the current app to a status bar
+    Bar {
+        #[arg(long, value_enum, default_value = "waybar")]
+        format: bar::Format,
+    },
+    /// Show how much of each [[limit]] budget is used
+    Limits,
+    /// Progress and streaks for the [[goal]] entries in config.toml
+    Goals,
+    /// Block distracting apps for a while ([focus] in config.toml)
+    Focus {
+        #[command(subcommand)]
+        action: FocusCommand,
+    },
+    /// Work/break timer run by the daemon ([pomodoro] in config.toml)
+    Pomodoro {
+        #[command(subcommand)]
+        action: Option<PomodoroCommand>,
+    },
+    /// Break reminders ([breaks] in config.toml)
+    Breaks {
+        #[command(subcommand)]
+        action: BreaksCommand,
+    },
+    /// List devices and their usage over the last 7 days
+    Devices,
+    /// Exchange usage with other devices through `sync_dir`
+    Sync,
+    /// Delete recorded history for an app, a date range, or both
+    Forget {
+        /// Raw app id or display name
+        #[arg(long)]
+        app: Option<String>,
+        /// First day to delete (YYYY-MM-DD)
+        #[arg(long, conflicts_with = "date")]
+        from: Option<NaiveDate>,
+        /// Last day to delete (YYYY-MM-DD)
+        #[arg(long, conflicts_with = "date")]
+        to: Option<NaiveDate>,
+        /// A single day to delete
+        #[arg(long)]
+        date: Option<NaiveDate>,
+    },
+    /// Correct the recorded time of an app on one day
+    Edit {
+        /// Raw app id or display name
+        #[arg(long)]
+        app: String,
+        #[arg(long)]
+        date: NaiveDate,
+        /// New total, e.g. 1h20m
+        #[arg(long)]
+        set: String,
+    },
+    /// Manage display names for raw app ids
+    Alias {
+        #[command(subcommand)]
+        action: AliasCommand,
+    },
+    /// Unify app ids that are the same program
+    Apps {
+        #[command(subcommand)]
+        action: AppsCommand,
+    },
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        action: DbCommand,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportBy {
+    App,
+    /// Repository / project named in editor and terminal titles
+    Project,
+    /// Website of the active browser tab (needs the browser extension)
+    Site,
+    /// Program running in a focused terminal (needs `shell-hook` or tmux)
+    Command,
+    /// Working directory of a focused terminal
+    Directory,
+    Device,
+}
+
+#[derive(Subcommand)]
+enum FocusCommand {
+    /// Start focus mode, e.g. `focusd focus start 50m`
+    Start { duration: String },
+    /// End focus mode early (a scheduled window stays off until it ends)
+    Stop,
+    /// Show whether focus mode is on and for how long
+    Status,
+    /// Past focus sessions and the blocked apps opened during them
+    Log {
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum PomodoroCommand {
+    /// Start a cycle (the default), e.g. `focusd pomodoro start --work 50m --break 10m`
+    Start {
+        /// Length of a work block
+        #[arg(long)]
+        work: Option<String>,
+        /// Length of a short break
+        #[arg(long = "break")]
+        short_break: Option<String>,
+    },
+    /// Stop the timer; an unfinished work block is recorded as interrupted
+    Stop,
+    /// Show the current phase and time left
+    Status,
+    /// Past work blocks and the apps used during them
+    Log {
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum BreaksCommand {
+    /// Active time per reminder and how long until the next one
+    Status,
+    /// Postpone the reminders showing now
+    Snooze {
+        /// Only this reminder
+        name: Option<String>,
+        /// How long, e.g. 10m (default: `snooze` in config.toml)
+        #[arg(long = "for")]
+        duration: Option<String>,
+    },
+    /// How often reminded breaks were taken
+    Report {
+        #[arg(long, default_value_t = 7)]
+        days: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum AliasCommand {
+    /// Give an app a display name
+    Set { app: String, name: String },
+    /// Remove a display name set with `alias set`
+    Unset { app: String },
+    /// Show every recorded app and its display name
+    List,
+    /// Propose names from installed .desktop files
+    Suggest {
+        /// Store the suggestions as aliases
+        #[arg(long)]
+        apply: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AppsCommand {
+    /// Move all history of <FROM> onto <INTO> and log <FROM> as <INTO> from now on
+    Merge { from: String, into: String },
+    /// Show app ids that have been merged
+    Merges,
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Write a consistent snapshot of the database (safe while the daemon runs)
+    Backup { path: PathBuf },
+    /// Replace the database with a snapshot made by `backup`
+    Restore { path: PathBuf },
+    /// Add usage from another machine's database (repeatable without double-counting)
+    Merge { other: PathBuf },
+    /// Apply the [retention] policy now
+    Prune {
+        /// Only report what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Reclaim disk space and refresh statistics (VACUUM + ANALYZE)
+    Vacuum,
+    /// Show recent manual changes made with `forget` and `edit`
+    Audit {
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
 }
 
 fn main() -> anyhow::Result<()> {
+    // Browsers start native hosts with their own arguments, not ours
+    let args: Vec<String> = std::env::args().collect();
+    if native_host::launched_by_browser(&args) {
+        return native_host::run(&config::Config::load().browser);
+    }
+
     let cli = Cli::parse();
     let config = config::Config::load();
-    let db = db::Db::init()?;
+    let Some(command) = run_without_db(cli.command, &config)? else {
+        return Ok(());
+    };
+
+    let mut db = db::Db::init()?;
+    db.set_config_aliases(&config.alias)?;
+    db.set_merge_rules(&config.merge)?;
+    // Scanning .desktop files is slow; the daemon stores what it finds for everyone else
+    if matches!(command, Commands::Daemon) {
+        db.set_app_resolver(desktop::AppResolver::load())?;
+    }
+    db.set_project_extractor(project::ProjectExtractor::new(&config.projects));
+    db.set_day_start(config.day_start_time());
+    // The daemon keeps days in this machine's zone for limits and the bar
+    if let (Some(zone), false) = (cli.tz, matches!(command, Commands::Daemon)) {
+        db.set_zone(zone);
+    }
+    if let Some(device) = &cli.device {
+        db.set_device_filter(device)?;
+    }
 
-    match cli.command {
+    match command {
         Commands::Daemon => {
-            run_daemon(&db, &config)?;
+            daemon::run(&db, &config)?;
+        }
+        Commands::Top => {
+            top::run(&db, config.interval)?;
         }
         Commands::Listen => {
             // Debug Loop
-            let is_hyprland = is_hyprland();
-            let env_name = if is_hyprland { "Hyprland" } else { "X11/Other" };
-            println!("Environment: {}", env_name.yellow());
-
-            let x11_backend = if !is_hyprland { x11::X11Backend::new().ok() } else { None };
+            let backend = window::Backend::detect();
+            println!("Environment: {}", backend.name().yellow());
 
             loop {
-                let window_opt = if is_hyprland {
-                    hyprland::get_focused_window()
-                } else {
-                     x11_backend.as_ref().and_then(|b| b.get_focused_window())
-                };
-
-                match window_opt {
-                    Some((app, title)) => println!("Focused: [{}] {}", app.blue(), title),
+                match backend.focused_window() {
+                    Some(w) => {
+                        println!("Focused: [{}] {}", w.app_id.blue(), w.title);
+                        if let Some(p) = w.pid.and_then(process::ProcessInfo::read) {
+                            let attributed = process::attribute_app_id(&w.app_id, Some(&p));
+                            println!(
+                                "         pid {} exe {} cgroup {}{}",
+                                p.pid,
+                                p.exe.as_deref().unwrap_or("?"),
+                                p.cgroup.as_deref().unwrap_or("?"),
+                                if attributed != w.app_id { format!(" -> {}", attributed.green()) } else { String::new() }
+                            );
+                        }
+                    }
                     None => println!("Focused: None/Idle (or unknown)"),
                 }
 
@@ -66,74 +370,390 @@ fn main() -> anyhow::Result<()> {
             }
         }
         Commands::Today => {
-            // Pass Config to the print function now
-            print_report(&db, &config, "Today", 0)?;
+            print_report(&db, "Today", 0)?;
+            print_focus_metrics(&db, db.today(), config.deep_work_seconds(), config.idle_gap_seconds())?;
         }
         Commands::Week => {
-            // Pass Config to the print function now
-            print_report(&db, &config, "Last 7 Days", 7)?;
+            print_report(&db, "Last 7 Days", 7)?;
+        }
+        Commands::Report { by, days } => {
+            let end = db.today();
+            let start = end - chrono::Duration::days(days);
+            let (title, data) = match by {
+                ReportBy::App => ("Apps", db.get_app_usage_range(start, end)?),
+                ReportBy::Project => ("Projects", db.get_project_usage_range(start, end)?),
+                ReportBy::Site => ("Sites", db.get_domain_usage_range(start, end)?),
+                ReportBy::Command => ("Commands", db.get_command_usage_range(start, end)?),
+                ReportBy::Directory => ("Directories", db.get_directory_usage_range(start, end)?),
+                ReportBy::Device => {
+                    let usage = db.get_device_usage_range(start, end)?;
+                    ("Devices", usage.into_iter().map(|(d, s)| (d.hostname, s)).collect())
+                }
+            };
+            let range = if days == 0 { "Today".to_string() } else { format!("Last {} Days", days) };
+            print_bars(&format!("{} — {}", title, range), &data);
+        }
+        Commands::Heatmap { days } => {
+            print_heatmap(&db, days)?;
+        }
+        Commands::Compare { range, against, json } => {
+            let today = db.today();
+            let current = compare::DateRange::parse(&range, today)?;
+            let previous = compare::DateRange::parse(&against, today)?.same_days_as(&current, today);
+            let comparison = compare::compare(&db, current, previous)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&comparison)?);
+            } else {
+                print_comparison(&comparison, &range, &against);
+            }
+        }
+        Commands::Digest { week, format, output } => {
+            let range = compare::DateRange::parse(&week, db.today())?;
+            let contents = digest::render(&db, &config, range, format)?;
+            match output {
+                Some(path) => {
+                    digest::write(&path, &contents)?;
+                    println!("Wrote the {} digest to {}", digest::label(&range), path.display());
+                }
+                None => print!("{}", contents),
+            }
+        }
+        Commands::Timeline { date, list } => {
+            print_timeline(&db, date.unwrap_or_else(|| db.today()), list)?;
         }
         Commands::Export => {
             let data = db.export_json()?;
             let json = serde_json::to_string_pretty(&data)?;
             println!("{}", json);
         }
+        Commands::Focus { action: FocusCommand::Log { limit } } => {
+            print_focus_log(&db, limit)?;
+        }
+        Commands::Pomodoro { action: Some(PomodoroCommand::Log { limit }) } => {
+            print_pomodoro_log(&db, limit)?;
+        }
+        Commands::Breaks { action: BreaksCommand::Report { days } } => {
+            print_break_report(&db, days)?;
+        }
+        Commands::Limits => {
+            print_limits(&db, &config)?;
+        }
+        Commands::Goals => {
+            print_goals(&db, &config)?;
+        }
+        Commands::Devices => {
+            print_devices(&db)?;
+        }
+        Commands::Sync => {
+            let Some(dir) = &config.sync_dir else {
+                anyhow::bail!("No sync_dir set in config.toml");
+            };
+            let report = sync::sync_dir(&db, dir)?;
+            println!(
+                "Synced with {}: {} rows exported, {} rows imported from {} device(s)",
+                dir.display(), report.exported, report.imported, report.devices
+            );
+        }
+        Commands::Forget { app, from, to, date } => {
+            let (from, to) = match date {
+                Some(d) => (Some(d), Some(d)),
+                None => (from, to),
+            };
+            let report = db.forget(app.as_deref(), from, to)?;
+            println!(
+                "Forgot {} ({} daily totals, {} sessions)",
+                duration::format_duration(report.seconds).red(), report.rows, report.sessions
+            );
+        }
+        Commands::Edit { app, date, set } => {
+            let seconds = duration::parse_duration(&set)? as i64;
+            let old = db.set_usage(&app, date, seconds)?;
+            println!(
+                "{} on {}: {} -> {}",
+                app.bold(), date, duration::format_duration(old), duration::format_duration(seconds).green()
+            );
+        }
+        Commands::Alias { action } => match action {
+            AliasCommand::Set { app, name } => {
+                db.set_alias(&app, &name)?;
+                if let Some(c) = config.alias.get(&app) {
+                    println!("{} config.toml still names it '{}', which takes precedence", "Note:".yellow(), c);
+                }
+                println!("{} -> {}", app, name.green());
+            }
+            AliasCommand::Unset { app } => {
+                db.unset_alias(&app)?;
+                if let Some(c) = config.alias.get(&app) {
+                    println!("{} config.toml still names it '{}'", "Note:".yellow(), c);
+                }
+                println!("Removed alias for {}", app);
+            }
+            AliasCommand::List => {
+                for app in db.list_apps()? {
+                    let source = if app.config_alias.is_some() {
+                        "config"
+                    } else if app.alias.is_some() {
+                        "alias"
+                    } else if app.desktop_name.is_some() {
+                        "desktop"
+                    } else {
+                        ""
+                    };
+                    println!("{:<35} {:<20} {}", app.app_id, app.display_name.bold(), source.dimmed());
+                }
+            }
+            AliasCommand::Suggest { apply } => {
+                let mut found = 0;
+                for app in db.list_apps()? {
+                    // Apps that are only named by their .desktop file (or not at all)
+                    if app.alias.is_some() || app.config_alias.is_some() { continue; }
+                    let Some(name) = app.desktop_name else { continue };
+                    if name == app.app_id { continue; }
+
+                    found += 1;
+                    println!("{:<35} -> {}", app.app_id, name.green());
+                    if apply {
+                        db.set_alias(&app.app_id, &name)?;
+                    }
+                }
+                if found == 0 {
+                    println!("No suggestions.");
+                } else if !apply {
+                    println!("\nRun `focusd alias suggest --apply` to keep them as aliases.");
+                }
+            }
+        },
+        Commands::Apps { action } => match action {
+            AppsCommand::Merge { from, into } => {
+                let report = db.merge_apps(&from, &into)?;
+                println!(
+                    "Merged {} into {}: {} moved ({} daily totals, {} sessions)",
+                    from.bold(), into.bold(), duration::format_duration(report.seconds).green(),
+                    report.rows, report.sessions
+                );
+            }
+            AppsCommand::Merges => {
+                let merges = db.list_app_merges()?;
+                if merges.is_empty() {
+                    println!("No merged apps.");
+                }
+                for (from, into) in merges {
+                    let source = if config.merge.contains_key(&from) { "config" } else { "" };
+                    println!("{:<35} -> {}  {}", from, into.bold(), source.dimmed());
+                }
+            }
+        },
+        Commands::Db { action } => match action {
+            DbCommand::Backup { path } => {
+                db.backup_to(&path)?;
+                println!("Backup written to {}", path.display().to_string().green());
+            }
+            DbCommand::Restore { path } => {
+                db.restore_from(&path)?;
+                println!("Restored database from {}", path.display().to_string().green());
+            }
+            DbCommand::Merge { other } => {
+                let added = db.merge_from(&other)?;
+                println!("Merged {} ({}h {}m new)", other.display(), added / 3600, (added % 3600) / 60);
+            }
+            DbCommand::Prune { dry_run } => {
+                let report = db.prune(&config.retention, dry_run)?;
+                let verb = if dry_run { "Would delete" } else { "Deleted" };
+                println!("{} {} sessions and strip titles from {} more", verb, report.sessions, report.titles);
+            }
+            DbCommand::Vacuum => {
+                db.vacuum()?;
+                println!("{}", "Database vacuumed and analyzed".green());
+            }
+            DbCommand::Audit { limit } => {
+                for e in db.get_audit_log(limit)? {
+                    let at = db.wall_clock(e.at).format("%Y-%m-%d %H:%M").to_string();
+                    println!(
+                        "{}  {:<7} {:<15} {}  {} -> {}  [{}]",
+                        at.dimmed(), e.action, e.app_id.truncate_pad(15), e.date,
+                        duration::format_duration(e.old_seconds), duration::format_duration(e.new_seconds), e.device
+                    );
+                }
+            }
+        },
+        Commands::NativeHost { .. } | Commands::ShellHook { .. } | Commands::ShellReport { .. }
+        | Commands::Focus { .. } | Commands::Pomodoro { .. } | Commands::Breaks { .. } | Commands::Bar { .. } => {
+            unreachable!("run before the database is opened")
+        }
     }
     Ok(())
 }
 
-fn is_hyprland() -> bool {
-    env::var("HYPRLAND_INSTANCE_SIGNATURE").is_ok()
-}
-
-fn run_daemon(db: &db::Db, config: &config::Config) -> anyhow::Result<()> {
-    let is_hyprland = is_hyprland();
-    
-    let x11_backend = if !is_hyprland {
-        match x11::X11Backend::new() {
-            Ok(b) => Some(b),
-            Err(e) => {
-                eprintln!("Warning: Failed to init X11: {}", e);
-                None
+/// Runs the commands that only talk to the daemon or print a script, before
+/// any database or app lookup is set up: `shell-report` runs on every shell
+/// prompt. Gives the command back when it needs the database.
+fn run_without_db(command: Commands, config: &config::Config) -> anyhow::Result<Option<Commands>> {
+    match command {
+        Commands::NativeHost { install, chrome_extension_id } => {
+            if !install {
+                native_host::run(&config.browser)?;
+                return Ok(None);
+            }
+            for path in native_host::install(chrome_extension_id.as_deref())? {
+                println!("Wrote {}", path.display());
             }
         }
-    } else {
-        None
-    };
-
-    println!("{}", "focusd daemon started...".green().bold());
-    println!("Backend: {}", if is_hyprland { "Hyprland" } else { "X11" });
-
-    loop {
-        thread::sleep(Duration::from_secs(config.interval));
-
-        if idle::is_session_idle() { continue; }
-
-        let window_opt = if is_hyprland {
-            hyprland::get_focused_window()
-        } else {
-            match &x11_backend {
-                Some(b) => b.get_focused_window(),
-                None => None,
+        Commands::ShellHook { shell } => {
+            let exe = std::env::current_exe()?;
+            match terminal::hook_script(&shell, &exe) {
+                Some(script) => print!("{}", script),
+                None => anyhow::bail!("unsupported shell '{}' (use zsh, bash or fish)", shell),
             }
-        };
-
-        if let Some((app_id, title)) = window_opt {
-            // Skip logging if app_id is completely empty/whitespace (fixes blank line bug)
-            if app_id.trim().is_empty() {
-                continue;
+        }
+        Commands::ShellReport { pid, cwd, command } => {
+            // The shell never waits on us, so a missing daemon is not worth an error
+            let command = command.as_deref().and_then(terminal::command_name);
+            let _ = ipc::send(&ipc::Request::ShellState { pid, cwd, command });
+        }
+        Commands::Focus { action: FocusCommand::Start { duration } } => {
+            let seconds = duration::parse_duration(&duration)?;
+            let response = ipc::send(&ipc::Request::FocusStart { seconds })?;
+            if !response.ok {
+                anyhow::bail!(response.error.unwrap_or_default());
             }
-
-            if let Err(e) = db.log_usage(&app_id, &title, config.interval) {
-                eprintln!("Error writing to DB: {}", e);
+            println!("Focus mode on for {}.", duration::format_duration(seconds as i64));
+        }
+        Commands::Focus { action: FocusCommand::Stop } => {
+            let response = ipc::send(&ipc::Request::FocusStop)?;
+            if !response.ok {
+                anyhow::bail!(response.error.unwrap_or_default());
             }
+            println!("Focus mode off.");
         }
+        Commands::Focus { action: FocusCommand::Status } => {
+            let response = ipc::send(&ipc::Request::FocusStatus)?;
+            let status: ipc::FocusStatus = serde_json::from_value(response.data.unwrap_or_default())?;
+            match status.until {
+                Some(until) if status.active => {
+                    let left = until - chrono::Utc::now().timestamp();
+                    println!(
+                        "Focus mode {} ({}), {} left, {} blocked app(s) opened",
+                        "on".green().bold(),
+                        status.source.unwrap_or_default(),
+                        duration::format_duration(left.max(0)),
+                        status.violations
+                    );
+                }
+                _ => println!("Focus mode off."),
+            }
+        }
+        Commands::Pomodoro { action: None } => pomodoro_start(None, None)?,
+        Commands::Pomodoro { action: Some(PomodoroCommand::Start { work, short_break }) } => pomodoro_start(work, short_break)?,
+        Commands::Pomodoro { action: Some(PomodoroCommand::Stop) } => {
+            let response = ipc::send(&ipc::Request::PomodoroStop)?;
+            if !response.ok {
+                anyhow::bail!(response.error.unwrap_or_default());
+            }
+            println!("Pomodoro stopped.");
+        }
+        Commands::Pomodoro { action: Some(PomodoroCommand::Status) } => {
+            let response = ipc::send(&ipc::Request::PomodoroStatus)?;
+            let status: ipc::PomodoroStatus = serde_json::from_value(response.data.unwrap_or_default())?;
+            match (status.phase, status.phase_ends) {
+                (Some(phase), Some(ends)) if status.active => {
+                    let left = ends - chrono::Utc::now().timestamp();
+                    println!(
+                        "Pomodoro: {}, {} left, {} done",
+                        phase.replace('_', " ").green().bold(),
+                        duration::format_duration(left.max(0)),
+                        status.completed
+                    );
+                }
+                _ => println!("No Pomodoro running."),
+            }
+        }
+        Commands::Breaks { action: BreaksCommand::Status } => {
+            let response = ipc::send(&ipc::Request::BreakStatus)?;
+            let reminders: Vec<ipc::BreakStatus> = serde_json::from_value(response.data.unwrap_or_default())?;
+            println!("\n{}\n", "Breaks".bold());
+            if reminders.is_empty() {
+                println!("No [[breaks.reminder]] entries in config.toml.");
+            }
+            for r in reminders {
+                let next = if r.pending {
+                    "due now".yellow().to_string()
+                } else {
+                    format!("next in {}", duration::format_duration(r.due_in as i64))
+                };
+                println!("{:<15} {} active, {}", r.name.truncate_pad(15), duration::format_duration(r.active as i64), next);
+            }
+            println!();
+        }
+        Commands::Breaks { action: BreaksCommand::Snooze { name, duration: length } } => {
+            let seconds = length.as_deref().map(duration::parse_duration).transpose()?;
+            let response = ipc::send(&ipc::Request::BreakSnooze { name, seconds })?;
+            if !response.ok {
+                anyhow::bail!(response.error.unwrap_or_default());
+            }
+            let names: Vec<String> = serde_json::from_value(response.data.unwrap_or_default())?;
+            println!("Snoozed {}.", names.join(", "));
+        }
+        Commands::Bar { format } => {
+            bar::run(format)?;
+        }
+        command => return Ok(Some(command)),
     }
+    Ok(None)
+}
+
+fn pomodoro_start(work: Option<String>, short_break: Option<String>) -> anyhow::Result<()> {
+        let work = work.as_deref().map(duration::parse_duration).transpose()?;
+        let short_break = short_break.as_deref().map(duration::parse_duration).transpose()?;
+        let response = ipc::send(&ipc::Request::PomodoroStart { work, short_break })?;
+        if !response.ok {
+            anyhow::bail!(response.error.unwrap_or_default());
+        }
+        let status: ipc::PomodoroStatus = serde_json::from_value(response.data.unwrap_or_default())?;
+        let left = status.phase_ends.unwrap_or_default() - chrono::Utc::now().timestamp();
+        println!("Pomodoro started: {} of work.", duration::format_duration(left.max(0)));
+    Ok(())
 }
 
 /// Generic report printer
-fn print_report(db: &db::Db, config: &config::Config, title: &str, days_lookback: i64) -> anyhow::Result<()> {
+fn print_report(db: &db::Db, title: &str, days_lookback: i64) -> anyhow::Result<()> {
     let data = db.get_usage_since(days_lookback)?;
+    print_bars(title, &data);
+    Ok(())
+}
+
+/// Switches, stretch lengths and deep work for one day, overall and per app
+fn print_focus_metrics(db: &db::Db, date: NaiveDate, deep_work: i64, idle_gap: i64) -> anyhow::Result<()> {
+    let overall = db.get_focus_metrics(date, date, deep_work, idle_gap)?;
+    if overall.stretches == 0 {
+        return Ok(());
+    }
+    let fmt = |s: i64| duration::format_duration(s);
+    println!(
+        "{} — {} switches, median stretch {}, longest {}, deep work {} (≥ {})\n",
+        "Focus".bold(),
+        overall.switches,
+        fmt(overall.median),
+        fmt(overall.longest),
+        fmt(overall.deep_work).green(),
+        fmt(deep_work)
+    );
+    println!("{:<15} {:>9} {:>8} {:>8} {:>9}", "", "switches", "median", "longest", "deep work");
+    for (app, m) in db.get_app_focus_metrics(date, date, deep_work, idle_gap)?.iter().take(8) {
+        println!(
+            "{:<15} {:>9} {:>8} {:>8} {:>9}",
+            app.truncate_pad(15),
+            m.switches,
+            fmt(m.median),
+            fmt(m.longest),
+            fmt(m.deep_work)
+        );
+    }
+    println!();
+    Ok(())
+}
+
+/// Prints (name, seconds) rows as a bar chart under a title with the total
+fn print_bars(title: &str, data: &[(String, i64)]) {
     let total_seconds: i64 = data.iter().map(|(_, s)| s).sum();
     
     let t_h = total_seconds / 3600;
@@ -143,17 +763,16 @@ fn print_report(db: &db::Db, config: &config::Config, title: &str, days_lookback
 
     if data.is_empty() {
         println!("No data found.");
-        return Ok(());
+        return;
     }
 
     let max_val = data.iter().map(|(_, s)| *s).max().unwrap_or(1);
 
-    for (raw_name, seconds) in data {
+    // Names are already resolved (config alias > `focusd alias` > raw id) in the DB
+    for (display_name, seconds) in data {
+        let seconds = *seconds;
         // Fix blank names in report immediately
-        if raw_name.trim().is_empty() { continue; }
-
-        // 1. LOOKUP ALIAS: Check if user defined a name in config.toml
-        let display_name = config.alias.get(&raw_name).unwrap_or(&raw_name);
+        if display_name.trim().is_empty() { continue; }
 
         let h = seconds / 3600;
         let m = (seconds % 3600) / 60;
@@ -163,8 +782,8 @@ fn print_report(db: &db::Db, config: &config::Config, title: &str, days_lookback
         let filled_len = (seconds as f64 / max_val as f64 * bar_width as f64) as usize;
         let empty_len = bar_width.saturating_sub(filled_len);
         
-        let bar_filled: String = std::iter::repeat("█").take(filled_len).collect();
-        let bar_empty: String = std::iter::repeat("░").take(empty_len).collect();
+        let bar_filled = "█".repeat(filled_len);
+        let bar_empty = "░".repeat(empty_len);
 
         println!(
             "{:<15} {}{} {}h {:02}m {:02}s", 
@@ -174,7 +793,372 @@ fn print_report(db: &db::Db, config: &config::Config, title: &str, days_lookback
             h, m, s
         );
     }
-    println!("");
+    println!();
+}
+
+fn print_heatmap(db: &db::Db, days: i64) -> anyhow::Result<()> {
+    let end = db.today();
+    let start = end - chrono::Duration::days(days.max(1) - 1);
+    let matrix = db.get_weekday_hour_totals(start, end)?;
+    println!("\n{} — {} to {}\n", "When the time goes".bold(), start, end);
+
+    let max = matrix.iter().flatten().copied().max().unwrap_or(0);
+    if max == 0 {
+        println!("No data found.\n");
+        return Ok(());
+    }
+    let header: String = (0..24).map(|h| if h % 3 == 0 { format!("{:<3}", h) } else { "   ".to_string() }).collect();
+    println!("     {}", header.dimmed());
+
+    const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    for (weekday, hours) in matrix.iter().enumerate() {
+        let cells: String = hours.iter().map(|&seconds| {
+            // Quarters of the busiest hour
+            let cell = match seconds * 4 / max {
+                _ if seconds == 0 => return "·  ".dimmed().to_string(),
+                0 => "░░ ",
+                1 => "▒▒ ",
+                2 => "▓▓ ",
+                _ => "██ ",
+            };
+            cell.cyan().to_string()
+        }).collect();
+        println!("{}  {}", WEEKDAYS[weekday], cells);
+    }
+
+    let (weekday, hour) = (0..7).flat_map(|d| (0..24).map(move |h| (d, h)))
+        .max_by_key(|&(d, h)| matrix[d][h])
+        .unwrap_or((0, 0));
+    println!(
+        "\nBusiest hour: {} {:02}:00–{:02}:00 ({} in total). ░ ▒ ▓ █ are quarters of that.\n",
+        WEEKDAYS[weekday], hour, (hour + 1) % 24, duration::format_duration(max)
+    );
+    Ok(())
+}
+
+fn print_comparison(c: &compare::Comparison, range: &str, against: &str) {
+    let span = |r: &compare::DateRange| match r.days() {
+        1 => r.start.to_string(),
+        _ => format!("{} – {}", r.start, r.end),
+    };
+    println!(
+        "\n{} ({}) vs {} ({})\n",
+        range.bold(), span(&c.current), against.bold(), span(&c.previous)
+    );
+    if c.current.days() != c.previous.days() {
+        println!("{}\n", format!("Note: {} days vs {} days", c.current.days(), c.previous.days()).dimmed());
+    }
+
+    let row = |d: &compare::Delta| {
+        let change = match (d.new, d.gone, d.percent) {
+            (true, _, _) => "new".green().to_string(),
+            (_, true, _) => "gone".red().to_string(),
+            (_, _, Some(p)) => {
+                let text = format!("{:>9} {:>+5.0}%", signed(d.change), p);
+                match d.change {
+                    c if c > 0 => text.green().to_string(),
+                    c if c < 0 => text.red().to_string(),
+                    _ => text.dimmed().to_string(),
+                }
+            }
+            _ => String::new(),
+        };
+        println!(
+            "{:<15} {:>8} {:>8}  {}",
+            d.name.truncate_pad(15),
+            duration::format_duration(d.current),
+            duration::format_duration(d.previous),
+            change
+        );
+    };
+
+    row(&c.total);
+    for (title, deltas) in [("Apps", &c.apps), ("Categories", &c.categories)] {
+        println!("\n{}", title.bold());
+        if deltas.is_empty() {
+            println!("No data found.");
+        }
+        for d in deltas.iter().take(15) {
+            row(d);
+        }
+        if deltas.len() > 15 {
+            println!("{}", format!("… and {} more", deltas.len() - 15).dimmed());
+        }
+    }
+
+    let movers = |names: &[String]| c.apps.iter()
+        .filter(|d| names.contains(&d.name))
+        .map(|d| format!("{} {}", d.name, signed(d.change)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if !c.risers.is_empty() {
+        println!("\n{} {}", "Up:".green().bold(), movers(&c.risers));
+    }
+    if !c.fallers.is_empty() {
+        println!("{} {}", "Down:".red().bold(), movers(&c.fallers));
+    }
+    println!();
+}
+
+/// A duration with an explicit sign, e.g. `+1h 5m`
+fn signed(seconds: i64) -> String {
+    let sign = if seconds > 0 { "+" } else { "" };
+    format!("{}{}", sign, duration::format_duration(seconds))
+}
+
+fn print_timeline(db: &db::Db, date: NaiveDate, list: bool) -> anyhow::Result<()> {
+    // Entries carry the offset they're shown in
+    let clock = |ts: i64, offset: i32| chrono::DateTime::from_timestamp(ts + offset as i64, 0)
+        .map(|t| t.format("%H:%M").to_string())
+        .unwrap_or_default();
+
+    let timeline = db.get_timeline(date)?;
+    println!("\n{} — {}\n", "Timeline".bold(), date.format("%a %Y-%m-%d"));
+    // 72 divides an hour, so every span splits into whole-second slots
+    const WIDTH: usize = 72;
+    let Some(bins) = timeline::bin(&timeline, WIDTH, 10) else {
+        println!("No data found.\n");
+        return Ok(());
+    };
+    let offset = timeline[0].offset;
+    let (span_start, span_end, slot) = (bins.start, bins.end, bins.slot);
+
+    for lane in &bins.lanes {
+        let bar: String = lane.cells.iter().map(|&s| match s {
+            0 => ' ',
+            s if s * 2 >= slot => '█',
+            _ => '▌',
+        }).collect();
+        println!("{:<15} {} {}", lane.name.truncate_pad(15), bar.cyan(), duration::format_duration(lane.total).dimmed());
+    }
+
+    // Hour marks under the bars
+    let mut axis = vec![' '; WIDTH + 5];
+    let mut hour = span_start;
+    while hour < span_end {
+        let pos = ((hour - span_start) / slot) as usize;
+        for (i, c) in clock(hour, offset)[..2].chars().enumerate() {
+            if let Some(cell) = axis.get_mut(pos + i) {
+                *cell = c;
+            }
+        }
+        // Keep labels apart on long days
+        hour += 3600 * (1 + (span_end - span_start) / 3600 / 12);
+    }
+    println!("{:<15} {}", "", axis.into_iter().collect::<String>().dimmed());
+
+    if list {
+        println!();
+        for e in &timeline {
+            println!(
+                "{}–{} {:>8}  {:<15} {}",
+                clock(e.started_at, e.offset),
+                clock(e.ended_at, e.offset),
+                duration::format_duration(e.ended_at - e.started_at),
+                e.app.truncate_pad(15),
+                e.title.as_deref().unwrap_or("").dimmed()
+            );
+        }
+    }
+    println!();
+    Ok(())
+}
+
+fn print_focus_log(db: &db::Db, limit: usize) -> anyhow::Result<()> {
+    let local = |ts: i64| db.wall_clock(ts).format("%Y-%m-%d %H:%M").to_string();
+
+    println!("\n{}\n", "Focus sessions".bold());
+    let sessions = db.list_focus_sessions(limit)?;
+    if sessions.is_empty() {
+        println!("No focus sessions yet.\n");
+        return Ok(());
+    }
+    for session in sessions {
+        let length = match session.ended_at {
+            Some(end) => duration::format_duration(end - session.started_at),
+            None => "running".to_string(),
+        };
+        println!(
+            "{}  {:<9} {:<8}  {} blocked app(s)",
+            local(session.started_at),
+            length,
+            session.source.dimmed(),
+            session.violations
+        );
+        for (at, app_id, action) in db.get_focus_violations(session.id)? {
+            println!("    {} {} ({})", db.wall_clock(at).format("%H:%M"), app_id.red(), action);
+        }
+    }
+    println!();
+    Ok(())
+}
+
+fn print_pomodoro_log(db: &db::Db, limit: usize) -> anyhow::Result<()> {
+    let local = |ts: i64| db.wall_clock(ts).format("%Y-%m-%d %H:%M").to_string();
+
+    println!("\n{}\n", "Pomodoros".bold());
+    let pomodoros = db.list_pomodoros(limit)?;
+    if pomodoros.is_empty() {
+        println!("No Pomodoros yet.\n");
+        return Ok(());
+    }
+    for p in pomodoros {
+        let (length, state) = match p.ended_at {
+            Some(end) if p.completed => (end - p.started_at, "done".green()),
+            Some(end) => (end - p.started_at, "stopped".yellow()),
+            None => (chrono::Utc::now().timestamp() - p.started_at, "running".normal()),
+        };
+        // How much of the block went to its most used app
+        let tracked: i64 = p.apps.iter().map(|(_, s)| s).sum();
+        let focus = match p.apps.first() {
+            Some((_, top)) => format!("{}% in one app", top * 100 / tracked.max(1)),
+            None => "no activity".to_string(),
+        };
+        println!(
+            "{}  {:<9} {:<8} {}",
+            local(p.started_at),
+            duration::format_duration(length.max(0)),
+            state,
+            focus
+        );
+        for (name, seconds) in p.apps.iter().take(5) {
+            println!("    {:<24} {}", name.truncate_pad(24), duration::format_duration(*seconds).dimmed());
+        }
+    }
+    println!();
+    Ok(())
+}
+
+fn print_break_report(db: &db::Db, days: i64) -> anyhow::Result<()> {
+    let end = db.today();
+    let start = end - chrono::Duration::days(days.max(1) - 1);
+    println!("\n{} ({} to {})\n", "Breaks taken".bold(), start, end);
+
+    let rows = db.get_break_compliance(start, end)?;
+    if rows.is_empty() {
+        println!("No break reminders in this period.\n");
+        return Ok(());
+    }
+    for (name, reminded, taken, snoozes) in rows {
+        let percent = taken * 100 / reminded.max(1);
+        let color = match percent {
+            p if p >= 75 => "green",
+            p if p >= 40 => "yellow",
+            _ => "red",
+        };
+        println!(
+            "{:<15} {:>3}% ({} of {} taken, {} snoozes)",
+            name.truncate_pad(15),
+            percent.to_string().color(color),
+            taken,
+            reminded,
+            snoozes
+        );
+    }
+    println!();
+    Ok(())
+}
+
+fn print_limits(db: &db::Db, config: &config::Config) -> anyhow::Result<()> {
+    let mut tracker = limits::LimitTracker::new(&config.limits);
+    println!("\n{}\n", "Limits".bold());
+    if tracker.is_empty() {
+        println!("No [[limit]] entries in config.toml.\n");
+        return Ok(());
+    }
+    for status in tracker.status(db)? {
+        let percent = status.used * 100 / status.budget.max(1);
+        let period = match status.period {
+            config::LimitPeriod::Day => "today",
+            config::LimitPeriod::Week => "this week",
+        };
+        let line = format!(
+            "{:<15} {:>8} / {:<8} {} ({}%)",
+            status.target.truncate_pad(15),
+            duration::format_duration(status.used),
+            duration::format_duration(status.budget),
+            period,
+            percent
+        );
+        if percent >= 100 {
+            println!("{}", line.red());
+        } else {
+            println!("{}", line);
+        }
+    }
+    println!();
+    Ok(())
+}
+
+fn print_goals(db: &db::Db, config: &config::Config) -> anyhow::Result<()> {
+    let goals = goals::Goal::from_rules(&config.goals);
+    println!("\n{}\n", "Goals".bold());
+    if goals.is_empty() {
+        println!("No [[goal]] entries in config.toml.\n");
+        return Ok(());
+    }
+    for goal in goals {
+        let status = goal.status(db)?;
+        let sign = match status.kind {
+            goals::GoalKind::Min => "at least",
+            goals::GoalKind::Max => "at most",
+        };
+        let period = match status.period {
+            config::LimitPeriod::Day => "today",
+            config::LimitPeriod::Week => "this week",
+        };
+        let line = format!(
+            "{:<15} {:>8} of {:<8} {:<8} {:<9}",
+            status.name.truncate_pad(15),
+            duration::format_duration(status.used),
+            sign,
+            duration::format_duration(status.target),
+            period,
+        );
+        let line = if !status.scheduled {
+            line.dimmed()
+        } else if status.met() {
+            line.green()
+        } else if status.kind == goals::GoalKind::Max {
+            line.red()
+        } else {
+            line.normal()
+        };
+        let unit = match status.period {
+            config::LimitPeriod::Day => "day",
+            config::LimitPeriod::Week => "week",
+        };
+        println!(
+            "{}  streak {} {}{} (best {})",
+            line, status.current_streak, unit, if status.current_streak == 1 { "" } else { "s" }, status.longest_streak
+        );
+    }
+    println!();
+    Ok(())
+}
+
+fn print_devices(db: &db::Db) -> anyhow::Result<()> {
+    let end = db.today();
+    let start = end - chrono::Duration::days(7);
+    let usage = db.get_device_usage_range(start, end)?;
+
+    println!("\n{}\n", "Devices".bold());
+    for device in db.list_devices()? {
+        let seconds = usage.iter()
+            .find(|(d, _)| d.id == device.id)
+            .map(|(_, s)| *s)
+            .unwrap_or(0);
+        let marker = if device.id == db.device_id() { " (this device)" } else { "" };
+        println!(
+            "{:<15} {}  {}h {:02}m{}",
+            device.hostname.truncate_pad(15),
+            device.id[..8.min(device.id.len())].dimmed(),
+            seconds / 3600,
+            (seconds % 3600) / 60,
+            marker.green()
+        );
+    }
+    println!();
     Ok(())
 }
 
@@ -184,10 +1168,9 @@ trait StringExt {
 
 impl StringExt for String {
     fn truncate_pad(&self, len: usize) -> String {
-        // Updated formatting to be stricter
-        if self.len() > len {
-            let mut s = self.clone();
-            s.truncate(len - 1);
+        // By chars: display names from .desktop files are often not ASCII
+        if self.chars().count() > len {
+            let s: String = self.chars().take(len.saturating_sub(1)).collect();
             format!("{}…", s)
         } else {
             format!("{:<width$}", self, width = len)
@@ -0,0 +1,127 @@
+use focusd_core::{browser, config, ipc};
+use serde::Deserialize;
+use serde_json::json;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+// WebExtension native messaging: every message is a u32 length in native
+// byte order followed by that many bytes of UTF-8 JSON, both ways.
+
+/// Name the extension connects to (`browser.runtime.connectNative("focusd")`)
+const HOST_NAME: &str = "focusd";
+/// Gecko id of the bundled extension in `browser-extension/`
+const FIREFOX_EXTENSION_ID: &str = "focusd@focusd.app";
+/// Browsers won't send more than this to a host; anything bigger is garbage
+const MAX_MESSAGE: u32 = 1024 * 1024;
+
+/// What the extension sends whenever the active tab, its URL or the focused
+/// browser window changes
+#[derive(Deserialize)]
+struct TabMessage {
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    incognito: bool,
+    #[serde(default)]
+    browser: Option<String>,
+}
+
+/// True when the browser started us: Firefox passes the manifest path and
+/// the extension id, Chrome the extension origin.
+pub fn launched_by_browser(args: &[String]) -> bool {
+    args.get(1).is_some_and(|a| a.starts_with("chrome-extension://") || a.ends_with(".json"))
+}
+
+/// Serves the extension until the browser closes stdin
+pub fn run(rules: &config::Browser) -> anyhow::Result<()> {
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+
+    while let Some(raw) = read_message(&mut stdin)? {
+        let reply = match serde_json::from_slice::<TabMessage>(&raw) {
+            Ok(msg) => forward(msg, rules),
+            Err(e) => json!({ "ok": false, "error": format!("invalid message: {}", e) }),
+        };
+        write_message(&mut stdout, &reply)?;
+    }
+    Ok(())
+}
+
+/// Reduces the tab to its domain and hands it to the daemon. The reply tells
+/// the extension what was recorded; the URL itself goes no further.
+fn forward(msg: TabMessage, rules: &config::Browser) -> serde_json::Value {
+    // No URL means no tab is focused (browser window lost focus, or devtools)
+    let domain = msg.url.as_deref().and_then(|url| browser::site_for(url, msg.incognito, rules));
+    let request = ipc::Request::BrowserTab { browser: msg.browser, domain: domain.clone() };
+    match ipc::send(&request) {
+        Ok(r) if r.ok => json!({ "ok": true, "domain": domain }),
+        Ok(r) => json!({ "ok": false, "error": r.error }),
+        Err(e) => json!({ "ok": false, "error": e.to_string() }),
+    }
+}
+
+fn read_message(input: &mut impl Read) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match input.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_ne_bytes(len);
+    if len > MAX_MESSAGE {
+        anyhow::bail!("message of {} bytes is too large", len);
+    }
+    let mut buf = vec![0u8; len as usize];
+    input.read_exact(&mut buf)?;
+    Ok(Some(buf))
+}
+
+fn write_message(output: &mut impl Write, value: &serde_json::Value) -> anyhow::Result<()> {
+    let body = serde_json::to_vec(value)?;
+    output.write_all(&(body.len() as u32).to_ne_bytes())?;
+    output.write_all(&body)?;
+    output.flush()?;
+    Ok(())
+}
+
+/// Registers this binary as a native messaging host with Firefox and, when
+/// the id of the unpacked Chrome extension is given, Chromium-based browsers.
+pub fn install(chrome_extension_id: Option<&str>) -> anyhow::Result<Vec<PathBuf>> {
+    let exe = std::env::current_exe()?;
+    let home = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("no home directory"))?;
+    let config = dirs::config_dir().unwrap_or_else(|| home.join(".config"));
+    let mut written = Vec::new();
+
+    let firefox = json!({
+        "name": HOST_NAME,
+        "description": "focusd screen time tracker",
+        "path": exe,
+        "type": "stdio",
+        "allowed_extensions": [FIREFOX_EXTENSION_ID],
+    });
+    let dir = home.join(".mozilla/native-messaging-hosts");
+    written.push(write_manifest(&dir, &firefox)?);
+
+    if let Some(id) = chrome_extension_id {
+        let chrome = json!({
+            "name": HOST_NAME,
+            "description": "focusd screen time tracker",
+            "path": exe,
+            "type": "stdio",
+            "allowed_origins": [format!("chrome-extension://{}/", id)],
+        });
+        for browser_dir in ["google-chrome", "chromium", "BraveSoftware/Brave-Browser", "vivaldi", "microsoft-edge"] {
+            let dir = config.join(browser_dir).join("NativeMessagingHosts");
+            written.push(write_manifest(&dir, &chrome)?);
+        }
+    }
+    Ok(written)
+}
+
+fn write_manifest(dir: &std::path::Path, manifest: &serde_json::Value) -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.json", HOST_NAME));
+    fs::write(&path, serde_json::to_string_pretty(manifest)?)?;
+    Ok(path)
+}
@@ -0,0 +1,176 @@
+use focusd_core::process;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+// What runs inside a focused terminal: shells report themselves through
+// `focusd shell-hook`, tmux panes are asked for directly.
+
+/// Shells a tmux pane shows as its command while sitting at the prompt
+const SHELLS: [&str; 8] = ["bash", "zsh", "fish", "sh", "dash", "ksh", "nu", "xonsh"];
+
+/// Words in front of the real program that say nothing about it
+const WRAPPERS: [&str; 8] = ["sudo", "doas", "env", "time", "nohup", "exec", "command", "nice"];
+
+/// How long a terminal is taken to still run (or not run) a tmux client
+const TMUX_CHECK: Duration = Duration::from_secs(60);
+
+/// How long the active tmux pane is reused before asking tmux again
+const PANE_CHECK: Duration = Duration::from_secs(5);
+
+/// Last thing a shell told us
+#[derive(Clone)]
+pub struct ShellReport {
+    pub cwd: String,
+    pub command: Option<String>,
+    pub at: Instant,
+}
+
+/// The script `focusd shell-hook <shell>` prints for the user's rc file.
+/// Reports run in the background so the prompt never waits on the daemon.
+pub fn hook_script(shell: &str, exe: &Path) -> Option<String> {
+    let exe = format!("'{}'", exe.display().to_string().replace('\'', r"'\''"));
+    let script = match shell {
+        "zsh" => format!(
+            r#"_focusd_preexec() {{ {exe} shell-report --pid $$ --cwd "$PWD" --command "$1" >/dev/null 2>&1 &! }}
+_focusd_precmd() {{ {exe} shell-report --pid $$ --cwd "$PWD" >/dev/null 2>&1 &! }}
+autoload -Uz add-zsh-hook
+add-zsh-hook preexec _focusd_preexec
+add-zsh-hook precmd _focusd_precmd
+"#
+        ),
+        // bash has no preexec; a DEBUG trap guarded until the next prompt stands in.
+        // The hook goes last in PROMPT_COMMAND so the entries before it still
+        // run under the guard and aren't taken for the user's command.
+        "bash" => format!(
+            r#"_focusd_running=1
+_focusd_preexec() {{
+    [ -n "$_focusd_running" ] || [ -n "$COMP_LINE" ] && return
+    # Inside the subshell BASH_COMMAND is already the report itself
+    local command="$BASH_COMMAND"
+    case "$command" in _focusd_*) return ;; esac
+    _focusd_running=1
+    ({exe} shell-report --pid $$ --cwd "$PWD" --command "$command" >/dev/null 2>&1 &)
+}}
+_focusd_precmd() {{
+    ({exe} shell-report --pid $$ --cwd "$PWD" >/dev/null 2>&1 &)
+    _focusd_running=
+}}
+trap '_focusd_preexec' DEBUG
+if [[ "$(declare -p PROMPT_COMMAND 2>/dev/null)" == "declare -a"* ]]; then
+    PROMPT_COMMAND+=(_focusd_precmd)
+else
+    _focusd_pc="${{PROMPT_COMMAND%"${{PROMPT_COMMAND##*[![:space:];]}}"}}"
+    PROMPT_COMMAND="${{_focusd_pc:+$_focusd_pc;}}_focusd_precmd"
+    unset _focusd_pc
+fi
+"#
+        ),
+        "fish" => format!(
+            r#"function __focusd_preexec --on-event fish_preexec
+    {exe} shell-report --pid $fish_pid --cwd "$PWD" --command "$argv[1]" >/dev/null 2>&1 &
+    disown 2>/dev/null
+end
+function __focusd_prompt --on-event fish_prompt
+    {exe} shell-report --pid $fish_pid --cwd "$PWD" >/dev/null 2>&1 &
+    disown 2>/dev/null
+end
+"#
+        ),
+        _ => return None,
+    };
+    Some(script)
+}
+
+/// Only the program of a command line is ever sent: `sudo pacman -Syu` gives
+/// `pacman`, `FOO=1 ./build.sh --release` gives `build.sh`.
+pub fn command_name(command_line: &str) -> Option<String> {
+    let word = command_line
+        .split_whitespace()
+        .find(|w| !w.contains('=') && !WRAPPERS.contains(w) && !w.starts_with('-'))?;
+    let name = Path::new(word).file_name()?.to_string_lossy().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Remembers what's slow to find out about a terminal, so it isn't redone
+/// every tick: whether a tmux client runs in it takes a walk over /proc, the
+/// active pane a `tmux` call.
+#[derive(Default)]
+pub struct Terminals {
+    // Terminal pid -> whether it runs tmux, and when that was checked
+    tmux: HashMap<u32, (bool, Instant)>,
+    pane: Option<(Option<TmuxPane>, Instant)>,
+}
+
+impl Terminals {
+    /// Foreground command and working directory of the terminal owned by
+    /// `pid`, preferring tmux when a tmux client runs in it.
+    pub fn context(&mut self, pid: u32, shells: &HashMap<u32, ShellReport>) -> (Option<String>, Option<String>) {
+        let in_tmux = match self.tmux.get(&pid) {
+            Some((in_tmux, checked)) if checked.elapsed() < TMUX_CHECK => *in_tmux,
+            _ => {
+                let in_tmux = !process::find_descendants(pid, |comm| comm.starts_with("tmux") && comm != "tmux: server").is_empty();
+                // Closed terminals drop out once their check is stale
+                self.tmux.retain(|_, (_, checked)| checked.elapsed() < TMUX_CHECK);
+                self.tmux.insert(pid, (in_tmux, Instant::now()));
+                in_tmux
+            }
+        };
+        let pane = match (in_tmux, &self.pane) {
+            (false, _) => None,
+            (true, Some((pane, checked))) if checked.elapsed() < PANE_CHECK => pane.clone(),
+            (true, _) => {
+                let pane = tmux_active_pane();
+                self.pane = Some((pane.clone(), Instant::now()));
+                pane
+            }
+        };
+        context(pid, pane, shells)
+    }
+}
+
+/// The tmux pane when there is one, else the shell below `pid` that reported last
+fn context(pid: u32, pane: Option<TmuxPane>, shells: &HashMap<u32, ShellReport>) -> (Option<String>, Option<String>) {
+    if let Some(pane) = pane {
+        // The hook knows the real command when the pane's shell has it installed
+        let command = match shells.get(&pane.pid) {
+            Some(report) => report.command.clone(),
+            None => (!SHELLS.contains(&pane.command.as_str())).then_some(pane.command),
+        };
+        return (command, Some(pane.cwd));
+    }
+
+    // Terminals with several windows share one process; the shell that
+    // reported last is the one being typed in.
+    shells.iter()
+        .filter(|(shell_pid, _)| process::is_descendant(**shell_pid, pid))
+        .max_by_key(|(_, report)| report.at)
+        .map(|(_, report)| (report.command.clone(), Some(report.cwd.clone())))
+        .unwrap_or((None, None))
+}
+
+#[derive(Clone)]
+struct TmuxPane {
+    pid: u32,
+    command: String,
+    cwd: String,
+}
+
+/// Active pane of the most recently used tmux client
+fn tmux_active_pane() -> Option<TmuxPane> {
+    let output = Command::new("tmux")
+        .args(["display-message", "-p", "#{pane_pid}\t#{pane_current_command}\t#{pane_current_path}"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut fields = text.trim_end().splitn(3, '\t');
+    Some(TmuxPane {
+        pid: fields.next()?.parse().ok()?,
+        command: fields.next()?.to_string(),
+        cwd: fields.next()?.to_string(),
+    })
+}
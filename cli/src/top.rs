@@ -0,0 +1,320 @@
+use focusd_core::duration::format_duration;
+use focusd_core::{db, ipc};
+use chrono::{Datelike, Duration as Days, Months, NaiveDate, Utc};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Bar, BarChart, BarGroup, Block, List, ListItem, ListState, Paragraph, Sparkline, Tabs};
+use ratatui::Frame;
+use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
+
+// `focusd top`: a live dashboard that only reads the database, so it works
+// the same with or without a daemon running.
+
+/// How often the database is read again
+const REFRESH: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    Today,
+    Week,
+    Month,
+    Category,
+}
+
+const VIEWS: [View; 4] = [View::Today, View::Week, View::Month, View::Category];
+
+impl View {
+    fn title(&self) -> &'static str {
+        match self {
+            View::Today => "Day",
+            View::Week => "Week",
+            View::Month => "Month",
+            View::Category => "Categories",
+        }
+    }
+}
+
+struct App {
+    view: View,
+    date: NaiveDate,
+    list: ListState,
+    // Everything below is re-read every REFRESH
+    rows: Vec<(String, i64)>,
+    hours: [i64; 24],
+    days: Vec<(NaiveDate, i64)>,
+    current: Option<db::CurrentSession>,
+    daemon: bool,
+}
+
+impl App {
+    /// First and last day shown by the current view
+    fn range(&self) -> (NaiveDate, NaiveDate) {
+        match self.view {
+            View::Today | View::Category => (self.date, self.date),
+            View::Week => {
+                let monday = self.date - Days::days(self.date.weekday().num_days_from_monday() as i64);
+                (monday, monday + Days::days(6))
+            }
+            View::Month => {
+                let first = self.date.with_day(1).unwrap_or(self.date);
+                let last = first + Months::new(1) - Days::days(1);
+                (first, last)
+            }
+        }
+    }
+
+    /// Moves a day, week or month back (`-1`) or forward (`1`), never past today
+    fn step(&mut self, db: &db::Db, direction: i32) {
+        let date = match (self.view, direction < 0) {
+            (View::Today | View::Category, true) => self.date - Days::days(1),
+            (View::Today | View::Category, false) => self.date + Days::days(1),
+            (View::Week, true) => self.date - Days::days(7),
+            (View::Week, false) => self.date + Days::days(7),
+            (View::Month, true) => self.date - Months::new(1),
+            (View::Month, false) => self.date + Months::new(1),
+        };
+        self.date = date.min(db.today());
+        self.list.select(Some(0));
+    }
+
+    fn load(&mut self, db: &db::Db, interval: u64) -> anyhow::Result<()> {
+        let (start, end) = self.range();
+        self.rows = match self.view {
+            View::Category => db.get_category_usage_range(start, end)?,
+            _ => db.get_app_usage_range(start, end)?,
+        };
+        self.hours = db.get_hourly_totals(self.date, self.date)?;
+
+        // The week around the selected day, or the whole month
+        let (first, last) = match self.view {
+            View::Month => (start, end),
+            _ => {
+                let monday = self.date - Days::days(self.date.weekday().num_days_from_monday() as i64);
+                (monday, monday + Days::days(6))
+            }
+        };
+        let totals = db.get_daily_totals(first, last)?;
+        self.days = first.iter_days()
+            .take_while(|d| *d <= last)
+            .map(|d| (d, totals.get(&d.to_string()).copied().unwrap_or(0)))
+            .collect();
+
+        // Anything older than a couple of ticks isn't focused anymore
+        let since = Utc::now().timestamp() - (interval as i64 * 2).max(10);
+        self.current = db.get_current_session(since)?;
+        self.daemon = UnixStream::connect(ipc::socket_path()).is_ok();
+        Ok(())
+    }
+}
+
+pub fn run(db: &db::Db, interval: u64) -> anyhow::Result<()> {
+    let mut app = App {
+        view: View::Today,
+        date: db.today(),
+        list: ListState::default().with_selected(Some(0)),
+        rows: Vec::new(),
+        hours: [0; 24],
+        days: Vec::new(),
+        current: None,
+        daemon: false,
+    };
+    app.load(db, interval)?;
+
+    let mut terminal = ratatui::init();
+    let result = (|| -> anyhow::Result<()> {
+        let mut loaded = Instant::now();
+        loop {
+            terminal.draw(|frame| draw(frame, &mut app))?;
+
+            // Redraw every second so the session timer keeps ticking
+            if event::poll(Duration::from_secs(1))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    let view = app.view;
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Left | KeyCode::Char('h') => app.step(db, -1),
+                        KeyCode::Right | KeyCode::Char('l') => app.step(db, 1),
+                        KeyCode::Up | KeyCode::Char('k') => app.list.select_previous(),
+                        KeyCode::Down | KeyCode::Char('j') => app.list.select_next(),
+                        KeyCode::Char('.') | KeyCode::Home => app.date = db.today(),
+                        KeyCode::Char('t') | KeyCode::Char('d') => app.view = View::Today,
+                        KeyCode::Char('w') => app.view = View::Week,
+                        KeyCode::Char('m') => app.view = View::Month,
+                        KeyCode::Char('c') => app.view = View::Category,
+                        KeyCode::Tab => {
+                            let i = VIEWS.iter().position(|v| *v == app.view).unwrap_or(0);
+                            app.view = VIEWS[(i + 1) % VIEWS.len()];
+                        }
+                        KeyCode::BackTab => {
+                            let i = VIEWS.iter().position(|v| *v == app.view).unwrap_or(0);
+                            app.view = VIEWS[(i + VIEWS.len() - 1) % VIEWS.len()];
+                        }
+                        _ => continue,
+                    }
+                    if app.view != view {
+                        app.list.select(Some(0));
+                    }
+                    app.load(db, interval)?;
+                    loaded = Instant::now();
+                    continue;
+                }
+            }
+            if loaded.elapsed() >= REFRESH {
+                app.load(db, interval)?;
+                loaded = Instant::now();
+            }
+        }
+    })();
+    ratatui::restore();
+    result
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [tabs, now, body, help] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(3),
+        Constraint::Min(8),
+        Constraint::Length(1),
+    ]).areas(frame.area());
+
+    let selected = VIEWS.iter().position(|v| *v == app.view).unwrap_or(0);
+    let (start, end) = app.range();
+    let range = if start == end { start.format("%a %Y-%m-%d").to_string() } else { format!("{} – {}", start, end) };
+    let [tabs, title] = Layout::horizontal([Constraint::Min(40), Constraint::Length(36)]).areas(tabs);
+    frame.render_widget(
+        Tabs::new(VIEWS.iter().map(|v| v.title()))
+            .select(selected)
+            .highlight_style(Style::new().bold().reversed()),
+        tabs,
+    );
+    frame.render_widget(
+        Paragraph::new(Line::from(vec!["focusd ".bold(), range.into(), " ".into()]).right_aligned()),
+        title,
+    );
+
+    draw_now(frame, app, now);
+
+    let [list, charts] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(body);
+    draw_list(frame, app, list);
+
+    let [hours, days] = Layout::vertical([Constraint::Length(6), Constraint::Min(6)]).areas(charts);
+    let peak = app.hours.iter().max().copied().unwrap_or(0);
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::bordered().title(format!(" {} by hour, peak {} ", app.date.format("%a"), format_duration(peak))))
+            .data(app.hours.iter().map(|s| *s as u64))
+            .style(Style::new().fg(Color::Cyan)),
+        hours,
+    );
+    draw_days(frame, app, days);
+
+    frame.render_widget(
+        Paragraph::new(" ←/→ day/week/month  ↑/↓ scroll  t w m c or Tab views  . today  q quit").dim(),
+        help,
+    );
+}
+
+fn draw_now(frame: &mut Frame, app: &App, area: Rect) {
+    let daemon = if app.daemon { " daemon running ".green() } else { " daemon not running, showing recorded data ".yellow() };
+    let line = match &app.current {
+        Some(session) => {
+            let elapsed = Utc::now().timestamp() - session.started_at;
+            let mut spans = vec![
+                "● ".green(),
+                Span::styled(session.app.clone(), Style::new().bold()),
+                format!("  for {}", format_clock(elapsed)).into(),
+            ];
+            if let Some(title) = session.title.as_deref().filter(|t| !t.is_empty()) {
+                spans.push(format!("  {}", title).dim());
+            }
+            Line::from(spans)
+        }
+        None => Line::from("○ nothing focused (idle or away)".dim()),
+    };
+    frame.render_widget(
+        Paragraph::new(line).block(Block::bordered().title(" Now ").title(Line::from(daemon).right_aligned())),
+        area,
+    );
+}
+
+fn draw_list(frame: &mut Frame, app: &mut App, area: Rect) {
+    let total: i64 = app.rows.iter().map(|(_, s)| s).sum();
+    let max = app.rows.first().map_or(1, |(_, s)| (*s).max(1));
+    // Room for the name, a bar and the duration inside the border
+    let width = area.width.saturating_sub(2) as usize;
+    let name_width = (width / 2).clamp(8, 28);
+    let bar_width = width.saturating_sub(name_width + 12);
+
+    let items: Vec<ListItem> = app.rows.iter().enumerate().map(|(i, (name, seconds))| {
+        let name: String = name.chars().take(name_width).collect();
+        let bar = "█".repeat((*seconds * bar_width as i64 / max) as usize);
+        ListItem::new(Line::from(vec![
+            format!("{:>2} {:<w$} ", i + 1, name, w = name_width.saturating_sub(3)).into(),
+            Span::styled(format!("{:<w$}", bar, w = bar_width), Style::new().fg(Color::Blue)),
+            format!(" {:>8}", format_duration(*seconds)).into(),
+        ]))
+    }).collect();
+
+    let title = match app.view {
+        View::Category => " Categories ",
+        _ => " Apps ",
+    };
+    let list = List::new(items)
+        .block(Block::bordered().title(title).title(Line::from(format!(" {} ", format_duration(total))).right_aligned()))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    if app.rows.is_empty() {
+        frame.render_widget(Paragraph::new("No data recorded.").dim().block(Block::bordered().title(title)), area);
+    } else {
+        frame.render_stateful_widget(list, area, &mut app.list);
+    }
+}
+
+fn draw_days(frame: &mut Frame, app: &App, area: Rect) {
+    let month = app.view == View::Month;
+    // Narrow bars for a month, wider ones for a week
+    let count = app.days.len().max(1) as u16;
+    let inner = area.width.saturating_sub(2);
+    let bar_width = (inner / count).saturating_sub(1).clamp(1, 8);
+
+    let bars: Vec<Bar> = app.days.iter().map(|(date, seconds)| {
+        let label = match (month, bar_width) {
+            (false, _) => date.format("%a").to_string(),
+            // Day numbers only fit once the bars are two wide
+            (true, 2..) => date.format("%d").to_string(),
+            (true, _) => String::new(),
+        };
+        let style = if *date == app.date { Style::new().fg(Color::Yellow) } else { Style::new().fg(Color::Blue) };
+        Bar::default()
+            .value(*seconds as u64)
+            .label(Line::from(label))
+            // Hours with one decimal fit in the narrow bars
+            .text_value(if month { String::new() } else { format!("{:.1}h", *seconds as f64 / 3600.0) })
+            .style(style)
+    }).collect();
+
+    let title = match app.days.first() {
+        Some((first, _)) if month => first.format(" %B %Y ").to_string(),
+        _ => " Week ".to_string(),
+    };
+    frame.render_widget(
+        BarChart::default()
+            .block(Block::bordered().title(title))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(bar_width)
+            .bar_gap(1),
+        area,
+    );
+}
+
+/// `1:02:03` or `2:03`, for a timer that visibly ticks
+fn format_clock(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if h > 0 { format!("{}:{:02}:{:02}", h, m, s) } else { format!("{}:{:02}", m, s) }
+}
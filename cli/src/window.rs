@@ -0,0 +1,126 @@
+use crate::{hyprland, x11};
+use focusd_core::config::FocusAction;
+use std::env;
+use std::process::Command;
+
+/// The window that currently has focus, as reported by the active backend
+#[derive(Debug, Clone)]
+pub struct FocusedWindow {
+    /// Stable app id (Hyprland class / X11 WM_CLASS)
+    pub app_id: String,
+    pub title: String,
+    /// Owning process, when the backend knows it
+    pub pid: Option<u32>,
+    /// X11 window id or Hyprland window address
+    pub id: Option<u64>,
+}
+
+/// Whichever compositor / display server we're running under
+pub enum Backend {
+    Hyprland,
+    X11(Box<x11::X11Backend>),
+    Unavailable,
+}
+
+impl Backend {
+    pub fn detect() -> Self {
+        if env::var("HYPRLAND_INSTANCE_SIGNATURE").is_ok() {
+            return Backend::Hyprland;
+        }
+        match x11::X11Backend::new() {
+            Ok(b) => Backend::X11(Box::new(b)),
+            Err(e) => {
+                eprintln!("Warning: Failed to init X11: {}", e);
+                Backend::Unavailable
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Hyprland => "Hyprland",
+            Backend::X11(_) => "X11",
+            Backend::Unavailable => "None",
+        }
+    }
+
+    /// Seconds since the last input, where the backend can tell. Much finer
+    /// than logind's IdleHint, which only flips after the idle manager's timeout.
+    pub fn input_idle(&self) -> Option<u64> {
+        match self {
+            Backend::X11(b) => b.input_idle(),
+            Backend::Hyprland | Backend::Unavailable => None,
+        }
+    }
+
+    pub fn focused_window(&self) -> Option<FocusedWindow> {
+        match self {
+            Backend::Hyprland => hyprland::get_focused_window(),
+            Backend::X11(b) => b.get_focused_window(),
+            Backend::Unavailable => None,
+        }
+    }
+
+    /// Carries out a focus-mode action on the focused `window`. `previous` is
+    /// the last allowed window, which plain X11 focuses after moving the
+    /// blocked one to another desktop.
+    pub fn enforce(&self, action: FocusAction, window: &FocusedWindow, workspace: &str, previous: Option<u64>) -> anyhow::Result<()> {
+        match action {
+            FocusAction::Notify => return Ok(()),
+            FocusAction::Kill => {
+                let pid = window.pid.ok_or_else(|| anyhow::anyhow!("window has no known process"))?;
+                let status = Command::new("kill").args(["-TERM", &pid.to_string()]).status()?;
+                if !status.success() {
+                    anyhow::bail!("could not kill pid {}", pid);
+                }
+                return Ok(());
+            }
+            FocusAction::Minimize | FocusAction::Workspace => {}
+        }
+
+        // i3 and sway act on the focused window, which is the blocked one
+        if let Some(msg) = tiling_wm_msg() {
+            let command = match action {
+                FocusAction::Minimize => "move scratchpad".to_string(),
+                _ => format!("move container to workspace {}", workspace),
+            };
+            let status = Command::new(msg).arg(command).status()?;
+            if !status.success() {
+                anyhow::bail!("{} failed", msg);
+            }
+            return Ok(());
+        }
+
+        let id = window.id.ok_or_else(|| anyhow::anyhow!("backend gave no window id"))?;
+        match self {
+            Backend::Hyprland => {
+                // Hyprland can't minimise; a special workspace is the closest thing
+                let target = if action == FocusAction::Minimize { "special:minimized" } else { workspace };
+                hyprland::move_to_workspace(id, target)
+            }
+            Backend::X11(b) => match action {
+                FocusAction::Workspace => {
+                    b.move_to_desktop(id as u32, workspace)?;
+                    // The moved window usually keeps focus; hand it back
+                    match previous {
+                        Some(previous) => b.activate(previous as u32),
+                        None => Ok(()),
+                    }
+                }
+                _ => b.minimize(id as u32),
+            },
+            Backend::Unavailable => anyhow::bail!("no window backend"),
+        }
+    }
+}
+
+/// `swaymsg` or `i3-msg` when running under sway or i3
+fn tiling_wm_msg() -> Option<&'static str> {
+    if env::var_os("SWAYSOCK").is_some() {
+        Some("swaymsg")
+    } else if env::var_os("I3SOCK").is_some() {
+        Some("i3-msg")
+    } else {
+        None
+    }
+}
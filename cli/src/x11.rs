@@ -1,5 +1,7 @@
 use x11rb::connection::Connection;
-use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, GetPropertyReply};
+use x11rb::protocol::screensaver::ConnectionExt as _;
+use x11rb::protocol::xproto::{AtomEnum, ClientMessageEvent, ConnectionExt, EventMask, GetPropertyReply};
+use crate::window::FocusedWindow;
 
 pub struct X11Backend {
     conn: x11rb::rust_connection::RustConnection,
@@ -7,6 +9,10 @@ pub struct X11Backend {
     atom_wm_class: u32,
     atom_utf8_string: u32,
     atom_net_wm_name: u32,
+    atom_net_wm_pid: u32,
+    atom_wm_change_state: u32,
+    atom_net_wm_desktop: u32,
+    atom_net_number_of_desktops: u32,
 }
 
 impl X11Backend {
@@ -18,6 +24,10 @@ impl X11Backend {
         let atom_wm_class = conn.intern_atom(false, b"WM_CLASS")?.reply()?.atom;
         let atom_utf8_string = conn.intern_atom(false, b"UTF8_STRING")?.reply()?.atom;
         let atom_net_wm_name = conn.intern_atom(false, b"_NET_WM_NAME")?.reply()?.atom;
+        let atom_net_wm_pid = conn.intern_atom(false, b"_NET_WM_PID")?.reply()?.atom;
+        let atom_wm_change_state = conn.intern_atom(false, b"WM_CHANGE_STATE")?.reply()?.atom;
+        let atom_net_wm_desktop = conn.intern_atom(false, b"_NET_WM_DESKTOP")?.reply()?.atom;
+        let atom_net_number_of_desktops = conn.intern_atom(false, b"_NET_NUMBER_OF_DESKTOPS")?.reply()?.atom;
 
         Ok(Self {
             conn,
@@ -25,10 +35,14 @@ impl X11Backend {
             atom_wm_class,
             atom_utf8_string,
             atom_net_wm_name,
+            atom_net_wm_pid,
+            atom_wm_change_state,
+            atom_net_wm_desktop,
+            atom_net_number_of_desktops,
         })
     }
 
-    pub fn get_focused_window(&self) -> Option<(String, String)> {
+    pub fn get_focused_window(&self) -> Option<FocusedWindow> {
         let root = self.conn.setup().roots[0].root;
 
         // 1. Ask Root window for the Active Window ID
@@ -62,9 +76,72 @@ impl X11Backend {
         
         // Normalize App ID (WM_CLASS often comes as "gnome-terminal\0Gnome-terminal")
         // We usually want the capitalized or second part
-        let stable_id = app_id.split('\0').last().unwrap_or(&app_id).to_string();
+        let stable_id = app_id.split('\0').next_back().unwrap_or(&app_id).to_string();
 
-        Some((stable_id, title))
+        // 4. Get _NET_WM_PID (Owning process, not every client sets it)
+        let pid = self.conn.get_property(
+            false, window_id, self.atom_net_wm_pid,
+            AtomEnum::CARDINAL, 0, 1
+        ).ok()
+            .and_then(|c| c.reply().ok())
+            .and_then(|r| r.value32().and_then(|mut v| v.next()))
+            .filter(|pid| *pid > 0);
+
+        Some(FocusedWindow { app_id: stable_id, title, pid, id: Some(window_id as u64) })
+    }
+
+    /// Seconds since the last keyboard or mouse input (MIT-SCREEN-SAVER), if
+    /// the server has the extension
+    pub fn input_idle(&self) -> Option<u64> {
+        let root = self.conn.setup().roots[0].root;
+        let info = self.conn.screensaver_query_info(root).ok()?.reply().ok()?;
+        Some(info.ms_since_user_input as u64 / 1000)
+    }
+
+    /// Asks the window manager to iconify a window (ICCCM WM_CHANGE_STATE)
+    pub fn minimize(&self, window: u32) -> anyhow::Result<()> {
+        const ICONIC_STATE: u32 = 3;
+        self.send_root_message(window, self.atom_wm_change_state, [ICONIC_STATE, 0, 0, 0, 0])
+    }
+
+    /// Asks the window manager to focus another window (EWMH _NET_ACTIVE_WINDOW)
+    pub fn activate(&self, window: u32) -> anyhow::Result<()> {
+        // Source 2 = pager, which WMs obey without focus-stealing checks
+        self.send_root_message(window, self.atom_net_active_window, [2, 0, 0, 0, 0])
+    }
+
+    /// Asks the window manager to move a window to another desktop (EWMH
+    /// _NET_WM_DESKTOP). `workspace` is a desktop number counted from 1 as
+    /// pagers show them; anything else means the last desktop.
+    pub fn move_to_desktop(&self, window: u32, workspace: &str) -> anyhow::Result<()> {
+        let desktop = match workspace.parse::<u32>() {
+            Ok(n) if n > 0 => n - 1,
+            _ => {
+                let root = self.conn.setup().roots[0].root;
+                let count = self.conn.get_property(
+                    false, root, self.atom_net_number_of_desktops,
+                    AtomEnum::CARDINAL, 0, 1
+                )?.reply()?.value32().and_then(|mut v| v.next()).unwrap_or(0);
+                if count < 2 {
+                    anyhow::bail!("window manager has no other desktop");
+                }
+                count - 1
+            }
+        };
+        self.send_root_message(window, self.atom_net_wm_desktop, [desktop, 2, 0, 0, 0])
+    }
+
+    fn send_root_message(&self, window: u32, atom: u32, data: [u32; 5]) -> anyhow::Result<()> {
+        let root = self.conn.setup().roots[0].root;
+        let event = ClientMessageEvent::new(32, window, atom, data);
+        self.conn.send_event(
+            false,
+            root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        )?;
+        self.conn.flush()?;
+        Ok(())
     }
 
     fn parse_string_property(&self, reply: &GetPropertyReply) -> String {
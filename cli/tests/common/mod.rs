@@ -0,0 +1,34 @@
+// Fixtures shared by the integration tests. Each test binary uses a
+// different subset, hence the allow.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A fresh directory under the system temp dir. `name` has to be unique
+/// across all test files, so it starts with the file's own name.
+pub fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("focusd-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// `focusd` with its home, config, data and socket all inside `dir`
+pub fn focusd(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_focusd"));
+    command
+        .env("HOME", dir)
+        .env("XDG_DATA_HOME", dir.join("data"))
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("XDG_RUNTIME_DIR", dir)
+        .env("NO_COLOR", "1");
+    command
+}
+
+/// Writes `contents` as `dir`'s config.toml
+pub fn config(dir: &Path, contents: &str) {
+    fs::create_dir_all(dir.join("config/focusd")).unwrap();
+    fs::write(dir.join("config/focusd/config.toml"), contents).unwrap();
+}
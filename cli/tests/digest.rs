@@ -0,0 +1,26 @@
+mod common;
+
+use common::{config, focusd, scratch};
+use std::fs;
+
+#[test]
+fn daily_goal_with_no_scheduled_days_is_not_counted() {
+    let dir = scratch("digest-goals");
+    config(
+        &dir,
+        "[[goal]]\nname = \"Weekend reading\"\napp = \"foliate\"\nmin = \"1h\"\ndays = [\"sat\", \"sun\"]\n\n\
+         [[goal]]\nname = \"Less chat\"\napp = \"slack\"\nmax = \"1h\"\n",
+    );
+
+    let output = focusd(&dir)
+        .args(["digest", "--week", "2026-10-05..2026-10-09", "--format", "md"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let digest = String::from_utf8(output.stdout).unwrap();
+
+    let row = |name: &str| digest.lines().find(|l| l.contains(name)).unwrap_or_else(|| panic!("no {} in:\n{}", name, digest)).to_string();
+    assert!(row("Weekend reading").starts_with("| – ") && row("Weekend reading").contains("| not scheduled |"), "{}", digest);
+    assert!(row("Less chat").starts_with("| ✓ ") && row("Less chat").contains("| 5 of 5 days |"), "{}", digest);
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::{config, focusd, scratch};
+use focusd_core::db::{Db, FocusContext};
+use std::fs;
+
+#[test]
+fn long_non_ascii_names_are_cut_by_chars() {
+    let dir = scratch("names-cut");
+    config(
+        &dir,
+        "[alias]\n\"org.gnome.Nautilus\" = \"Файлы и папки пользователя\"\n\"jp.example.Editor\" = \"ファイルマネージャーとエディター\"\n",
+    );
+    fs::create_dir_all(dir.join("data/focusd")).unwrap();
+    let db = Db::open(dir.join("data/focusd/focusd.db")).unwrap();
+    for app in ["org.gnome.Nautilus", "jp.example.Editor"] {
+        db.log_usage(app, "", 600, &FocusContext::default()).unwrap();
+    }
+    drop(db);
+
+    for args in [&["today"][..], &["week"], &["timeline", "--list"]] {
+        let output = focusd(&dir).args(args).output().unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+        assert!(stdout.contains("Файлы и папки …") && stdout.contains("ファイルマネージャーとエディ…"), "{:?}:\n{}", args, stdout);
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
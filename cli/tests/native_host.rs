@@ -0,0 +1,71 @@
+mod common;
+
+use common::{focusd, scratch};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixListener;
+use std::process::Stdio;
+use std::thread;
+
+fn frame(message: &[u8]) -> Vec<u8> {
+    let mut framed = (message.len() as u32).to_ne_bytes().to_vec();
+    framed.extend_from_slice(message);
+    framed
+}
+
+fn unframe(mut output: &[u8]) -> Vec<Value> {
+    let mut messages = Vec::new();
+    while !output.is_empty() {
+        let mut len = [0u8; 4];
+        output.read_exact(&mut len).unwrap();
+        let mut body = vec![0u8; u32::from_ne_bytes(len) as usize];
+        output.read_exact(&mut body).unwrap();
+        messages.push(serde_json::from_slice(&body).unwrap());
+    }
+    messages
+}
+
+#[test]
+fn host_forwards_only_the_domain_to_the_daemon() {
+    let dir = scratch("native-host-forward");
+    // Stands in for the daemon's control socket and keeps every request
+    let listener = UnixListener::bind(dir.join("focusd.sock")).unwrap();
+    let daemon = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for _ in 0..2 {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            requests.push(serde_json::from_str::<Value>(&line).unwrap());
+            (&stream).write_all(b"{\"ok\":true}\n").unwrap();
+        }
+        requests
+    });
+
+    let mut input = Vec::new();
+    input.extend(frame(br#"{"url":"https://docs.rs/serde/latest/serde/?search=x","browser":"firefox"}"#));
+    input.extend(frame(br#"{"url":"https://mail.example.com/inbox","incognito":true,"browser":"chromium"}"#));
+    input.extend(frame(b"not json"));
+
+    let mut host = focusd(&dir)
+        .args(["/usr/lib/mozilla/native-messaging-hosts/focusd.json", "focusd@focusd.app"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    host.stdin.take().unwrap().write_all(&input).unwrap();
+    let output = host.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    assert_eq!(unframe(&output.stdout), [
+        json!({ "ok": true, "domain": "docs.rs" }),
+        json!({ "ok": true, "domain": null }),
+        json!({ "ok": false, "error": "invalid message: expected ident at line 1 column 2" }),
+    ]);
+    assert_eq!(daemon.join().unwrap(), [
+        json!({ "cmd": "browser_tab", "browser": "firefox", "domain": "docs.rs" }),
+        json!({ "cmd": "browser_tab", "browser": "chromium", "domain": null }),
+    ]);
+    let _ = fs::remove_dir_all(&dir);
+}
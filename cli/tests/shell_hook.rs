@@ -0,0 +1,100 @@
+mod common;
+
+use common::{focusd, scratch};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// A scratch directory with a stand-in for `focusd shell-report` that
+/// appends its arguments to `reports`
+fn setup(name: &str) -> (PathBuf, PathBuf) {
+    let dir = scratch(&format!("shell-hook-{}", name));
+    let stub = dir.join("focusd");
+    fs::write(&stub, format!("#!/bin/sh\nprintf '%s\\n' \"$*\" >> '{}'\n", dir.join("reports").display())).unwrap();
+    fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+    (dir, stub)
+}
+
+/// The bash hook as `focusd shell-hook bash` prints it, pointed at `stub`
+fn bash_hook(dir: &Path, stub: &Path) -> String {
+    let output = focusd(dir)
+        .args(["shell-hook", "bash"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let script = String::from_utf8(output.stdout).unwrap();
+    let exe = fs::canonicalize(env!("CARGO_BIN_EXE_focusd")).unwrap();
+    let quoted = |p: &Path| format!("'{}'", p.display());
+    let replaced = script.replace(&quoted(&exe), &quoted(stub)).replace(&quoted(Path::new(env!("CARGO_BIN_EXE_focusd"))), &quoted(stub));
+    assert!(replaced.contains(&quoted(stub)), "exe not found in:\n{}", script);
+    replaced
+}
+
+/// Runs `commands` in an interactive bash with `rc` as its rc file and
+/// returns the reports once `expected` of them arrived
+fn run_bash(dir: &Path, rc: &str, commands: &str, expected: usize) -> Vec<String> {
+    let rcfile = dir.join("bashrc");
+    fs::write(&rcfile, rc).unwrap();
+    let mut bash = Command::new("bash")
+        .arg("--rcfile")
+        .arg(&rcfile)
+        .arg("-i")
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    bash.stdin.take().unwrap().write_all(commands.as_bytes()).unwrap();
+    bash.wait().unwrap();
+
+    // Reports are sent in the background
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let reports: Vec<String> = fs::read_to_string(dir.join("reports"))
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        if reports.len() >= expected || Instant::now() > deadline {
+            return reports;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Commands the hook reported. Reports run in the background and may land out
+/// of order, so they come back sorted.
+fn commands(reports: &[String]) -> Vec<&str> {
+    let mut commands: Vec<&str> = reports.iter().filter_map(|r| r.split_once(" --command ").map(|(_, c)| c)).collect();
+    commands.sort();
+    commands
+}
+
+#[test]
+fn bash_hook_runs_after_an_existing_prompt_command() {
+    let (dir, stub) = setup("existing");
+    let rc = format!(
+        "__vte_prompt_command() {{ :; }}\nPROMPT_COMMAND='__vte_prompt_command; '\n{}",
+        bash_hook(&dir, &stub)
+    );
+    // One report per prompt and one per command, `exit` included
+    let reports = run_bash(&dir, &rc, "sleep 0\ntrue\nexit\n", 6);
+    assert_eq!(commands(&reports), ["exit", "sleep 0", "true"], "reports: {:?}", reports);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn bash_hook_appends_to_a_prompt_command_array() {
+    let (dir, stub) = setup("array");
+    let rc = format!(
+        "__first() {{ :; }}\n__second() {{ :; }}\nPROMPT_COMMAND=(__first __second)\n{}",
+        bash_hook(&dir, &stub)
+    );
+    let reports = run_bash(&dir, &rc, "sleep 0\nexit\n", 4);
+    assert_eq!(commands(&reports), ["exit", "sleep 0"], "reports: {:?}", reports);
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,168 @@
+use crate::config::Breaks;
+use crate::duration::{format_duration, parse_duration};
+use crate::ipc::BreakStatus;
+
+// Break reminders based on continuous active time.
+//
+// Each reminder has its own clock of active seconds. An idle stretch as long as
+// the reminder's break restarts that clock (and counts as the break being
+// taken); one of `reset_after` restarts all of them.
+//
+// Where the window system reports the time since the last input (X11), breaks
+// are measured with that. Otherwise only logind's IdleHint is known, which the
+// idle manager sets after its own timeout, typically minutes: a break then only
+// counts once IdleHint has been on for min(length, reset_after), and breaks
+// shorter than that timeout are never seen as taken.
+
+struct Reminder {
+    name: String,
+    message: Option<String>,
+    every: u64,
+    length: u64,
+    // Active seconds since the last break
+    active: u64,
+    // Active seconds at which to remind next
+    due_at: u64,
+    // Reminded, break not taken yet
+    pending: bool,
+}
+
+/// Something the daemon should show or record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakEvent {
+    /// Time for a break; `active` is how long the user has been at it
+    Due { name: String, message: String, active: u64 },
+    /// A reminded break was taken
+    Taken { name: String },
+    Snoozed { name: String },
+}
+
+pub struct BreakTracker {
+    reset_after: u64,
+    snooze: u64,
+    reminders: Vec<Reminder>,
+    // Length of the current idle stretch
+    idle_for: u64,
+    // Snoozes asked for over the control socket, reported on the next tick
+    snoozed: Vec<String>,
+}
+
+impl BreakTracker {
+    /// Reminders whose durations don't parse are skipped with a warning
+    pub fn new(config: &Breaks) -> Self {
+        let duration = |what: &str, value: &str, default: u64| match parse_duration(value) {
+            Ok(seconds) if seconds > 0 => seconds,
+            _ => {
+                eprintln!("Warning: invalid [breaks] {} '{}'", what, value);
+                default
+            }
+        };
+        let reminders = config.reminders.iter().filter_map(|r| {
+            match (parse_duration(&r.every), parse_duration(&r.length)) {
+                (Ok(every), Ok(length)) if every > 0 && length > 0 => Some(Reminder {
+                    name: r.name.clone(),
+                    message: r.message.clone(),
+                    every,
+                    length,
+                    active: 0,
+                    due_at: every,
+                    pending: false,
+                }),
+                _ => {
+                    eprintln!("Warning: invalid [[breaks.reminder]] '{}': every = '{}', length = '{}'", r.name, r.every, r.length);
+                    None
+                }
+            }
+        }).collect();
+
+        BreakTracker {
+            reset_after: duration("reset_after", &config.reset_after, 300),
+            snooze: duration("snooze", &config.snooze, 300),
+            reminders,
+            idle_for: 0,
+            snoozed: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reminders.is_empty()
+    }
+
+    /// Advances the clocks by one daemon tick of `seconds`. `idle` is logind's
+    /// IdleHint, `input_idle` the seconds since the last input when known.
+    pub fn tick(&mut self, idle: bool, input_idle: Option<u64>, seconds: u64) -> Vec<BreakEvent> {
+        let mut events: Vec<BreakEvent> = self.snoozed.drain(..).map(|name| BreakEvent::Snoozed { name }).collect();
+
+        // No input during the whole tick is idle, however short
+        let idle = match input_idle {
+            Some(since) => since >= seconds,
+            None => idle,
+        };
+        if idle {
+            self.idle_for = match input_idle {
+                Some(since) => since,
+                None => self.idle_for + seconds,
+            };
+            for r in &mut self.reminders {
+                if self.idle_for < r.length.min(self.reset_after) || r.active == 0 {
+                    continue;
+                }
+                r.active = 0;
+                r.due_at = r.every;
+                if r.pending {
+                    r.pending = false;
+                    events.push(BreakEvent::Taken { name: r.name.clone() });
+                }
+            }
+            return events;
+        }
+
+        self.idle_for = 0;
+        for r in &mut self.reminders {
+            r.active += seconds;
+            if r.active >= r.due_at {
+                // Ignored reminders come back after another `every`
+                r.due_at = r.active + r.every;
+                r.pending = true;
+                events.push(BreakEvent::Due {
+                    name: r.name.clone(),
+                    message: r.message.clone().unwrap_or_else(|| default_message(r.length)),
+                    active: r.active,
+                });
+            }
+        }
+        events
+    }
+
+    /// Postpones pending reminders (all, or the one called `name`) by
+    /// `seconds` of activity, or the configured snooze. Returns their names.
+    pub fn snooze(&mut self, name: Option<&str>, seconds: Option<u64>) -> anyhow::Result<Vec<String>> {
+        let seconds = seconds.unwrap_or(self.snooze);
+        let mut snoozed = Vec::new();
+        for r in &mut self.reminders {
+            if !r.pending || name.is_some_and(|n| !n.eq_ignore_ascii_case(&r.name)) {
+                continue;
+            }
+            r.due_at = r.active + seconds;
+            snoozed.push(r.name.clone());
+        }
+        if snoozed.is_empty() {
+            anyhow::bail!("no break reminder to snooze");
+        }
+        self.snoozed.extend(snoozed.iter().cloned());
+        Ok(snoozed)
+    }
+
+    pub fn status(&self) -> Vec<BreakStatus> {
+        self.reminders.iter().map(|r| BreakStatus {
+            name: r.name.clone(),
+            active: r.active,
+            due_in: r.due_at.saturating_sub(r.active),
+            pending: r.pending,
+        }).collect()
+    }
+}
+
+fn default_message(length: u64) -> String {
+    format!("Take a {} break", format_duration(length as i64))
+}
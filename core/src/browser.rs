@@ -0,0 +1,81 @@
+use crate::config;
+use std::net::IpAddr;
+
+// Active browser tab -> the site it is on, as allowed by the [browser] rules.
+//
+// The native-messaging host runs this before anything leaves the browser's
+// process tree, so full URLs and tab titles are never sent to the daemon.
+
+/// App ids of browsers whose time can be split by site
+const BROWSERS: [&str; 16] = [
+    "firefox", "firefox-esr", "org.mozilla.firefox", "librewolf", "io.gitlab.librewolf-community",
+    "chromium", "chromium-browser", "org.chromium.chromium", "google-chrome", "com.google.chrome",
+    "brave-browser", "com.brave.browser", "vivaldi-stable", "microsoft-edge", "zen", "floorp",
+];
+
+/// Second-level labels under country TLDs that aren't registrable on their own (`co.uk`)
+const SECOND_LEVEL: [&str; 9] = ["co", "com", "net", "org", "gov", "ac", "edu", "ne", "or"];
+
+/// Browsers built on Firefox; the rest of `BROWSERS` are Chromium
+const GECKO: [&str; 7] = [
+    "firefox", "firefox-esr", "org.mozilla.firefox", "librewolf", "io.gitlab.librewolf-community", "zen", "floorp",
+];
+
+pub fn is_browser(app_id: &str) -> bool {
+    BROWSERS.contains(&app_id.to_lowercase().as_str())
+}
+
+/// `firefox` or `chromium`, as the extension names the browser it runs in
+pub fn family(app_id: &str) -> &'static str {
+    if GECKO.contains(&app_id.to_lowercase().as_str()) { "firefox" } else { "chromium" }
+}
+
+/// The domain to record for a tab, or `None` if the tab must not be recorded
+/// (private window, excluded site, or not a web page at all).
+pub fn site_for(url: &str, private: bool, rules: &config::Browser) -> Option<String> {
+    if private && !rules.track_private {
+        return None;
+    }
+    let host = host_of(url)?;
+    let excluded = rules.exclude.iter().any(|e| {
+        let e = e.trim().trim_start_matches("*.").to_lowercase();
+        host == e || host.ends_with(&format!(".{}", e))
+    });
+    if excluded {
+        return None;
+    }
+    Some(if rules.keep_subdomains { host } else { registrable_domain(&host) })
+}
+
+/// Lower-cased host of an http(s) URL without user info, port or `www.`
+fn host_of(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return None;
+    }
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = match host_port.strip_prefix('[') {
+        // IPv6 literal
+        Some(v6) => v6.split(']').next()?,
+        None => host_port.split(':').next()?,
+    };
+    let host = host.trim_end_matches('.').to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    (!host.is_empty()).then_some(host)
+}
+
+/// `mail.google.com` -> `google.com`, `news.bbc.co.uk` -> `bbc.co.uk`.
+/// A heuristic, not the public suffix list; IPs and single labels pass through.
+fn registrable_domain(host: &str) -> String {
+    if host.parse::<IpAddr>().is_ok() {
+        return host.to_string();
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    let n = labels.len();
+    if n <= 2 {
+        return host.to_string();
+    }
+    let keep = if labels[n - 1].len() == 2 && SECOND_LEVEL.contains(&labels[n - 2]) { 3 } else { 2 };
+    labels[n.saturating_sub(keep)..].join(".")
+}
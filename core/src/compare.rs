@@ -0,0 +1,167 @@
+use crate::db::Db;
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use std::collections::HashMap;
+
+// "This week vs last week": per-app and per-category totals of two date
+// ranges side by side, for `focusd compare` and the GUI.
+
+/// Apps listed as biggest movers in each direction
+const MOVERS: usize = 3;
+
+/// A range of days, both ends included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct DateRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    /// Parses `today`, `yesterday`, `this-week`, `last-week`, `this-month`,
+    /// `last-month`, `7d` (the last 7 days up to today), `2026-10-12`,
+    /// `2026-W41`, `2026-10` or `2026-10-01..2026-10-14`. Weeks start on Monday.
+    pub fn parse(input: &str, today: NaiveDate) -> anyhow::Result<Self> {
+        let range = |start, end| Ok(DateRange { start, end });
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let first_of_month = today.with_day(1).expect("day 1 exists");
+
+        match input.trim().to_lowercase().replace([' ', '_'], "-").as_str() {
+            "today" => return range(today, today),
+            "yesterday" => return range(today - Duration::days(1), today - Duration::days(1)),
+            "this-week" => return range(monday, today),
+            "last-week" => return range(monday - Duration::days(7), monday - Duration::days(1)),
+            "this-month" => return range(first_of_month, today),
+            "last-month" => return range(first_of_month - Months::new(1), first_of_month - Duration::days(1)),
+            _ => {}
+        }
+        if let Some(days) = input.strip_suffix('d').and_then(|n| n.parse::<i64>().ok()).filter(|&n| n > 0) {
+            let start = Duration::try_days(days - 1)
+                .and_then(|back| today.checked_sub_signed(back))
+                .ok_or_else(|| anyhow::anyhow!("Range '{}' goes back too far", input))?;
+            return range(start, today);
+        }
+        if let Some((from, to)) = input.split_once("..") {
+            let (start, end): (NaiveDate, NaiveDate) = (parse_date(from)?, parse_date(to)?);
+            if end < start {
+                anyhow::bail!("Range '{}' ends before it starts", input);
+            }
+            return range(start, end);
+        }
+        if let Ok(date) = input.parse::<NaiveDate>() {
+            return range(date, date);
+        }
+        // ISO week
+        if let Ok(start) = NaiveDate::parse_from_str(&format!("{}-1", input.to_uppercase()), "%G-W%V-%u") {
+            return range(start, start + Duration::days(6));
+        }
+        if let Ok(start) = NaiveDate::parse_from_str(&format!("{}-01", input), "%Y-%m-%d") {
+            if let Some(next) = start.checked_add_months(Months::new(1)) {
+                return range(start, next - Duration::days(1));
+            }
+        }
+        anyhow::bail!(
+            "Unknown range '{}' (use today, yesterday, this-week, last-week, this-month, last-month, 7d, YYYY-MM-DD, YYYY-Www, YYYY-MM or FROM..TO)",
+            input
+        )
+    }
+
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days() + 1
+    }
+
+    /// The first days of `self`, as many as `current` has, when `current` is
+    /// still running (ends today) and comes after `self`. This week so far is
+    /// then compared with the same weekdays of last week rather than all of it.
+    pub fn same_days_as(self, current: &DateRange, today: NaiveDate) -> DateRange {
+        if current.end != today || self.end >= current.start || self.days() <= current.days() {
+            return self;
+        }
+        DateRange { start: self.start, end: self.start + Duration::days(current.days() - 1) }
+    }
+}
+
+fn parse_date(s: &str) -> anyhow::Result<NaiveDate> {
+    s.trim().parse().map_err(|_| anyhow::anyhow!("Invalid date '{}' (use YYYY-MM-DD)", s))
+}
+
+/// How one app or category moved between the two ranges
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Delta {
+    pub name: String,
+    /// Seconds in the range being looked at
+    pub current: i64,
+    /// Seconds in the range it's compared against
+    pub previous: i64,
+    /// `current - previous`
+    pub change: i64,
+    /// Change relative to `previous`; none when there was nothing before
+    pub percent: Option<f64>,
+    /// Only used in the current range
+    pub new: bool,
+    /// Only used in the previous range
+    pub gone: bool,
+}
+
+impl Delta {
+    fn new(name: String, current: i64, previous: i64) -> Self {
+        Delta {
+            name,
+            current,
+            previous,
+            change: current - previous,
+            percent: (previous > 0).then(|| (current - previous) as f64 * 100.0 / previous as f64),
+            new: previous == 0 && current > 0,
+            gone: current == 0 && previous > 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Comparison {
+    pub current: DateRange,
+    pub previous: DateRange,
+    pub total: Delta,
+    /// Biggest change first
+    pub apps: Vec<Delta>,
+    pub categories: Vec<Delta>,
+    /// Names of the apps that grew the most, then those that shrank the most
+    pub risers: Vec<String>,
+    pub fallers: Vec<String>,
+}
+
+/// Compares `current` against `previous`
+pub fn compare(db: &Db, current: DateRange, previous: DateRange) -> anyhow::Result<Comparison> {
+    let apps = deltas(
+        db.get_app_usage_range(current.start, current.end)?,
+        db.get_app_usage_range(previous.start, previous.end)?,
+    );
+    let categories = deltas(
+        db.get_category_usage_range(current.start, current.end)?,
+        db.get_category_usage_range(previous.start, previous.end)?,
+    );
+    let total = Delta::new(
+        "Total".to_string(),
+        apps.iter().map(|d| d.current).sum(),
+        apps.iter().map(|d| d.previous).sum(),
+    );
+
+    let mut by_change: Vec<&Delta> = apps.iter().filter(|d| d.change != 0).collect();
+    by_change.sort_by_key(|d| std::cmp::Reverse(d.change));
+    let risers = by_change.iter().filter(|d| d.change > 0).take(MOVERS).map(|d| d.name.clone()).collect();
+    let fallers = by_change.iter().rev().filter(|d| d.change < 0).take(MOVERS).map(|d| d.name.clone()).collect();
+
+    Ok(Comparison { current, previous, total, apps, categories, risers, fallers })
+}
+
+/// Per-name changes from `previous` to `current`, biggest change first
+pub fn deltas(current: Vec<(String, i64)>, previous: Vec<(String, i64)>) -> Vec<Delta> {
+    let mut previous: HashMap<String, i64> = previous.into_iter().collect();
+    let mut result: Vec<Delta> = current.into_iter()
+        .map(|(name, seconds)| {
+            let before = previous.remove(&name).unwrap_or(0);
+            Delta::new(name, seconds, before)
+        })
+        .collect();
+    result.extend(previous.into_iter().map(|(name, seconds)| Delta::new(name, 0, seconds)));
+    result.sort_by(|a, b| b.change.abs().cmp(&a.change.abs()).then_with(|| a.name.cmp(&b.name)));
+    result
+}
@@ -14,39 +14,474 @@ pub struct Config {
 
     // NEW: Map raw AppIDs to pretty names
     #[serde(default)]
-    pub alias: HashMap<String, String>, 
+    pub alias: HashMap<String, String>,
+
+    // Shared folder (Syncthing etc.) used to exchange usage with other devices
+    #[serde(default)]
+    pub sync_dir: Option<PathBuf>,
+
+    // How often the daemon syncs with `sync_dir`, in seconds
+    #[serde(default = "default_sync_interval")]
+    pub sync_interval: u64,
+
+    #[serde(default)]
+    pub retention: Retention,
+
+    // Uninterrupted time in one app at least this long counts as deep work
+    #[serde(default = "default_deep_work")]
+    pub deep_work: String,
+
+    // Coming back to a different app after this long without focus is a
+    // fresh start rather than a context switch
+    #[serde(default = "default_idle_gap")]
+    pub idle_gap: String,
+
+    // Local time the day rolls over, "HH:MM". Usage before it counts towards
+    // the previous day, for people who work past midnight.
+    #[serde(default = "default_day_start")]
+    pub day_start: String,
+
+    // Raw AppIDs that are the same program as another one (Flatpak vs native,
+    // X11 vs Hyprland class). Left side gets folded into the right side.
+    #[serde(default)]
+    pub merge: HashMap<String, String>,
+
+    // [[project]] rules: extra title regexes for `focusd report --by project`
+    #[serde(default, rename = "project")]
+    pub projects: Vec<ProjectRule>,
+
+    #[serde(default)]
+    pub browser: Browser,
+
+    // [[limit]] budgets the daemon warns about with desktop notifications
+    #[serde(default, rename = "limit")]
+    pub limits: Vec<LimitRule>,
+
+    // [[goal]] targets shown with their streaks by `focusd goals`
+    #[serde(default, rename = "goal")]
+    pub goals: Vec<GoalRule>,
+
+    #[serde(default)]
+    pub focus: Focus,
+
+    #[serde(default)]
+    pub pomodoro: Pomodoro,
+
+    #[serde(default)]
+    pub breaks: Breaks,
+
+    #[serde(default, rename = "hook")]
+    pub hooks: Vec<HookRule>,
+
+    #[serde(default)]
+    pub digest: Digest,
+}
+
+/// A command run when something happens, e.g.
+/// `{ event = "focus_changed", command = "~/bin/slack-status" }`
+#[derive(Deserialize, Debug, Clone)]
+pub struct HookRule {
+    pub event: HookEvent,
+
+    // Run with `sh -c`; event data comes as FOCUSD_* variables and JSON on stdin
+    pub command: String,
+
+    // A hook still running after this long is killed
+    #[serde(default = "default_hook_timeout")]
+    pub timeout: String,
+
+    // Events this close together only run the hook once, for the latest
+    #[serde(default)]
+    pub debounce: Option<String>,
+}
+
+#[derive(Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    FocusChanged,
+    IdleStart,
+    IdleEnd,
+    LimitReached,
+    DayRollover,
+}
+
+/// The weekly digest the daemon writes every Monday
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Digest {
+    // Where to write last week's digest; none means the daemon doesn't
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+
+    #[serde(default)]
+    pub format: DigestFormat,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestFormat {
+    /// One self-contained file with inline SVG charts
+    #[default]
+    Html,
+    /// Markdown tables
+    Md,
+}
+
+impl DigestFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DigestFormat::Html => "html",
+            DigestFormat::Md => "md",
+        }
+    }
+}
+
+impl std::str::FromStr for DigestFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "html" => Ok(DigestFormat::Html),
+            "md" | "markdown" => Ok(DigestFormat::Md),
+            _ => anyhow::bail!("Unknown digest format '{}' (use html or md)", s),
+        }
+    }
+}
+
+/// Break reminders, driven by continuous active time
+#[derive(Deserialize, Debug, Clone)]
+pub struct Breaks {
+    // An idle stretch this long restarts every reminder's clock
+    #[serde(default = "default_breaks_reset_after")]
+    pub reset_after: String,
+
+    // Snooze length when `focusd breaks snooze` is given none
+    #[serde(default = "default_breaks_snooze")]
+    pub snooze: String,
+
+    #[serde(default, rename = "reminder")]
+    pub reminders: Vec<BreakReminder>,
+}
+
+/// e.g. `{ name = "Eyes", every = "20m", length = "20s" }`
+#[derive(Deserialize, Debug, Clone)]
+pub struct BreakReminder {
+    pub name: String,
+
+    // Active time before the reminder
+    pub every: String,
+
+    // How long the break should be; an idle stretch this long counts as taken
+    #[serde(default = "default_break_length")]
+    pub length: String,
+
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Lengths for `focusd pomodoro`, as durations like "25m"
+#[derive(Deserialize, Debug, Clone)]
+pub struct Pomodoro {
+    #[serde(default = "default_pomodoro_work")]
+    pub work: String,
+
+    #[serde(default = "default_pomodoro_short_break")]
+    pub short_break: String,
+
+    #[serde(default = "default_pomodoro_long_break")]
+    pub long_break: String,
+
+    // Every Nth break is a long one
+    #[serde(default = "default_pomodoro_long_break_every")]
+    pub long_break_every: u32,
+}
+
+/// Focus mode: what counts as a distraction and what happens when one is focused
+#[derive(Deserialize, Debug, Clone)]
+pub struct Focus {
+    // App ids, display names or .desktop categories
+    #[serde(default)]
+    pub block: Vec<String>,
+
+    #[serde(default)]
+    pub action: FocusAction,
+
+    // Workspace blocked windows are moved to with action = "workspace"
+    #[serde(default = "default_focus_workspace")]
+    pub workspace: String,
+
+    // [[focus.schedule]] windows in which focus mode starts by itself
+    #[serde(default)]
+    pub schedule: Vec<FocusSchedule>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FocusAction {
+    #[default]
+    Notify,
+    Minimize,
+    Workspace,
+    Kill,
+}
+
+/// e.g. `{ days = ["mon", "tue"], start = "09:00", end = "12:00" }`.
+/// No days means every day; an end before the start runs past midnight.
+#[derive(Deserialize, Debug, Clone)]
+pub struct FocusSchedule {
+    #[serde(default)]
+    pub days: Vec<String>,
+    pub start: String,
+    pub end: String,
+}
+
+/// A daily or weekly time budget for an app or a whole category
+#[derive(Deserialize, Debug, Clone)]
+pub struct LimitRule {
+    // Raw app id or display name
+    #[serde(default)]
+    pub app: Option<String>,
+
+    // Main category from the app's .desktop file, e.g. "Game"
+    #[serde(default)]
+    pub category: Option<String>,
+
+    // e.g. "1h" or "45m"
+    pub budget: String,
+
+    #[serde(default)]
+    pub period: LimitPeriod,
+
+    // Percentage of the budget that triggers the first warning
+    #[serde(default = "default_warn_at")]
+    pub warn_at: u8,
+}
+
+#[derive(Deserialize, serde::Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitPeriod {
+    #[default]
+    Day,
+    Week,
+}
+
+/// A daily or weekly target: at least `min` or at most `max` of an app, a
+/// category, a project, or of everything when none is given
+#[derive(Deserialize, Debug, Clone)]
+pub struct GoalRule {
+    // Shown instead of the target
+    #[serde(default)]
+    pub name: Option<String>,
+
+    // Raw app id or display name
+    #[serde(default)]
+    pub app: Option<String>,
+
+    #[serde(default)]
+    pub category: Option<String>,
+
+    // As found by [[project]] rules or a terminal's directory
+    #[serde(default)]
+    pub project: Option<String>,
+
+    // e.g. "3h"; exactly one of min and max
+    #[serde(default)]
+    pub min: Option<String>,
+
+    #[serde(default)]
+    pub max: Option<String>,
+
+    #[serde(default)]
+    pub period: LimitPeriod,
+
+    // Days a daily goal applies on, e.g. ["mon", "tue"]; no days means every
+    // day. Other days don't break a streak.
+    #[serde(default)]
+    pub days: Vec<String>,
+}
+
+/// Pulls a project name out of window titles. `title` must have a
+/// `(?P<project>...)` group; `app` optionally limits the rule to matching app ids.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ProjectRule {
+    #[serde(default)]
+    pub app: Option<String>,
+    pub title: String,
+}
+
+/// What the browser native-messaging host may pass on to the daemon.
+/// Only ever the domain of the active tab, never paths or titles.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Browser {
+    // Sites (and their subdomains) that are never recorded, e.g. "mybank.com"
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
+    // Record private / incognito windows too
+    #[serde(default)]
+    pub track_private: bool,
+
+    // Keep the full host name instead of the registrable domain (mail.google.com vs google.com)
+    #[serde(default)]
+    pub keep_subdomains: bool,
+}
+
+/// How long detailed history is kept. Daily totals are always kept.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Retention {
+    // Raw focus sessions, in days. 0 keeps them forever.
+    #[serde(default = "default_sessions_days")]
+    pub sessions_days: u64,
+
+    // Window titles attached to sessions, in days. 0 keeps them forever.
+    #[serde(default = "default_titles_days")]
+    pub titles_days: u64,
+
+    // How often the daemon compacts, in hours
+    #[serde(default = "default_prune_every_hours")]
+    pub prune_every_hours: u64,
 }
 
 fn default_interval() -> u64 { 1 }
 fn default_idle_timeout() -> u64 { 300 }
+fn default_sync_interval() -> u64 { 300 }
+fn default_sessions_days() -> u64 { 180 }
+fn default_titles_days() -> u64 { 30 }
+fn default_prune_every_hours() -> u64 { 6 }
+fn default_warn_at() -> u8 { 80 }
+fn default_focus_workspace() -> String { "focusd".to_string() }
+fn default_pomodoro_work() -> String { "25m".to_string() }
+fn default_pomodoro_short_break() -> String { "5m".to_string() }
+fn default_pomodoro_long_break() -> String { "15m".to_string() }
+fn default_pomodoro_long_break_every() -> u32 { 4 }
+fn default_breaks_reset_after() -> String { "5m".to_string() }
+fn default_breaks_snooze() -> String { "5m".to_string() }
+fn default_break_length() -> String { "5m".to_string() }
+fn default_hook_timeout() -> String { "10s".to_string() }
+fn default_deep_work() -> String { "25m".to_string() }
+fn default_idle_gap() -> String { "5m".to_string() }
+fn default_day_start() -> String { "00:00".to_string() }
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention {
+            sessions_days: default_sessions_days(),
+            titles_days: default_titles_days(),
+            prune_every_hours: default_prune_every_hours(),
+        }
+    }
+}
+
+impl Default for Focus {
+    fn default() -> Self {
+        Focus {
+            block: Vec::new(),
+            action: FocusAction::default(),
+            workspace: default_focus_workspace(),
+            schedule: Vec::new(),
+        }
+    }
+}
+
+impl Default for Pomodoro {
+    fn default() -> Self {
+        Pomodoro {
+            work: default_pomodoro_work(),
+            short_break: default_pomodoro_short_break(),
+            long_break: default_pomodoro_long_break(),
+            long_break_every: default_pomodoro_long_break_every(),
+        }
+    }
+}
+
+impl Default for Breaks {
+    fn default() -> Self {
+        Breaks {
+            reset_after: default_breaks_reset_after(),
+            snooze: default_breaks_snooze(),
+            reminders: Vec::new(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            interval: default_interval(),
+            idle_timeout: default_idle_timeout(),
+            alias: HashMap::new(),
+            sync_dir: None,
+            sync_interval: default_sync_interval(),
+            retention: Retention::default(),
+            deep_work: default_deep_work(),
+            idle_gap: default_idle_gap(),
+            day_start: default_day_start(),
+            merge: HashMap::new(),
+            projects: Vec::new(),
+            browser: Browser::default(),
+            limits: Vec::new(),
+            goals: Vec::new(),
+            focus: Focus::default(),
+            pomodoro: Pomodoro::default(),
+            breaks: Breaks::default(),
+            hooks: Vec::new(),
+            digest: Digest::default(),
+        }
+    }
+}
 
 impl Config {
     pub fn load() -> Self {
         let config_path = Self::get_path();
-        
+
         if !config_path.exists() {
-            return Config { 
-                interval: default_interval(),
-                idle_timeout: default_idle_timeout(),
-                alias: HashMap::new(),
-            };
+            return Config::default();
         }
 
         let contents = fs::read_to_string(config_path).unwrap_or_default();
-        match toml::from_str(&contents) {
-            Ok(c) => c,
+        match toml::from_str::<Config>(&contents) {
+            Ok(mut c) => {
+                c.sync_dir = c.sync_dir.map(expand_home);
+                c.digest.dir = c.digest.dir.map(expand_home);
+                c
+            }
             Err(e) => {
                 eprintln!("Warning: Failed to parse config.toml: {}", e);
                 // Return default on error so app doesn't crash
-                Config {
-                    interval: default_interval(),
-                    idle_timeout: default_idle_timeout(),
-                    alias: HashMap::new(),
-                }
+                Config::default()
+            }
+        }
+    }
+
+    /// `deep_work` in seconds; 25 minutes if it doesn't parse
+    pub fn deep_work_seconds(&self) -> i64 {
+        match crate::duration::parse_duration(&self.deep_work) {
+            Ok(seconds) => seconds as i64,
+            Err(_) => {
+                eprintln!("Warning: invalid deep_work '{}', using 25m", self.deep_work);
+                25 * 60
             }
         }
     }
 
+    /// `idle_gap` in seconds; 5 minutes if it doesn't parse
+    pub fn idle_gap_seconds(&self) -> i64 {
+        match crate::duration::parse_duration(&self.idle_gap) {
+            Ok(seconds) => seconds as i64,
+            Err(_) => {
+                eprintln!("Warning: invalid idle_gap '{}', using 5m", self.idle_gap);
+                5 * 60
+            }
+        }
+    }
+
+    /// `day_start` as a time of day; midnight if it doesn't parse
+    pub fn day_start_time(&self) -> chrono::NaiveTime {
+        chrono::NaiveTime::parse_from_str(&self.day_start, "%H:%M").unwrap_or_else(|_| {
+            eprintln!("Warning: invalid day_start '{}', using 00:00", self.day_start);
+            chrono::NaiveTime::MIN
+        })
+    }
+
     fn get_path() -> PathBuf {
         let mut path = dirs::config_dir().expect("Could not determine config dir");
         path.push("focusd");
@@ -56,4 +491,12 @@ impl Config {
         path.push("config.toml");
         path
     }
-}
\ No newline at end of file
+}
+
+/// Expands a leading `~/` so paths in config.toml can be written the usual way
+pub fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path,
+    }
+}
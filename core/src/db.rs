@@ -1,12 +1,46 @@
-use rusqlite::{params, Connection, Result};
-use chrono::{Local, NaiveDate}; 
+use rusqlite::{params, Connection, DatabaseName, OpenFlags, OptionalExtension, Result};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime}; 
 use std::fs;
-use std::collections::HashMap; // New import
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap}; // New import
+use crate::sync::SyncEntry;
+use crate::config::Retention;
+use crate::desktop::{AppResolver, ResolvedApp};
+use crate::process::ProcessInfo;
+use crate::project::{project_from_path, ProjectExtractor};
+use crate::timezone::{self, Zone};
 
 // ... [Existing imports and structs remain the same] ...
 
 pub struct Db {
     conn: Connection,
+    device_id: String,
+    // When set, report queries only look at rows recorded on this device
+    device_filter: Option<String>,
+    // [alias] from config.toml. Wins over aliases stored in the database.
+    config_aliases: HashMap<String, String>,
+    // Looks up names, icons and categories of newly seen apps
+    resolver: Option<AppResolver>,
+    // [merge] from config.toml: raw app id -> the id it is logged as
+    merge_rules: HashMap<String, String>,
+    // Derives a project name from each new session's title
+    project_extractor: Option<ProjectExtractor>,
+    // `day_start` from config.toml: local time at which one day ends and the next begins
+    day_start: NaiveTime,
+    // Time zone reports are computed in (`--tz`)
+    zone: Zone,
+}
+
+/// What the daemon knows about the focused window besides its class and title
+#[derive(Default, Debug, Clone)]
+pub struct FocusContext {
+    pub process: Option<ProcessInfo>,
+    /// Site of the active tab when the window is a browser
+    pub domain: Option<String>,
+    /// Program running in the foreground of a focused terminal (`None` at the prompt)
+    pub command: Option<String>,
+    /// Working directory of that terminal's shell or tmux pane
+    pub cwd: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -14,6 +48,161 @@ pub struct ExportEntry {
     pub date: String,
     pub app: String,
     pub seconds: i64,
+    pub device: String,
+}
+
+#[derive(Default, Debug)]
+pub struct PruneReport {
+    pub sessions: i64,
+    pub titles: i64,
+}
+
+#[derive(Default, Debug)]
+pub struct ForgetReport {
+    pub rows: usize,
+    pub seconds: i64,
+    pub sessions: usize,
+}
+
+#[derive(Default, Debug)]
+pub struct AppMergeReport {
+    pub rows: usize,
+    pub seconds: i64,
+    pub sessions: usize,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct AuditEntry {
+    pub at: i64,
+    pub action: String,
+    pub app_id: String,
+    pub date: String,
+    pub device: String,
+    pub old_seconds: i64,
+    pub new_seconds: i64,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct AppInfo {
+    pub app_id: String,
+    pub display_name: String,
+    /// Alias set with `focusd alias set`
+    pub alias: Option<String>,
+    /// Alias from config.toml, which overrides `alias`
+    pub config_alias: Option<String>,
+    /// Name from the matching .desktop file, used when there is no alias
+    pub desktop_name: Option<String>,
+    pub icon: Option<String>,
+    pub category: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct FocusSessionEntry {
+    pub id: i64,
+    /// `manual` or `schedule`
+    pub source: String,
+    pub started_at: i64,
+    pub planned_end: i64,
+    /// `None` while the session is running
+    pub ended_at: Option<i64>,
+    pub violations: i64,
+}
+
+/// A stretch of time in one app, for timelines
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct TimelineEntry {
+    pub app: String,
+    /// Title at the start of the stretch
+    pub title: Option<String>,
+    pub started_at: i64,
+    pub ended_at: i64,
+    /// Seconds east of UTC the times are shown in
+    pub offset: i32,
+}
+
+struct Stretch {
+    device: String,
+    entry: TimelineEntry,
+    // Focus came here from another app, this many seconds after leaving it
+    switched_after: Option<i64>,
+}
+
+/// How fragmented focused time was
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FocusMetrics {
+    pub total: i64,
+    /// Times focus moved from one app to another
+    pub switches: i64,
+    /// Uninterrupted stretches in one app
+    pub stretches: i64,
+    pub median: i64,
+    pub longest: i64,
+    /// Time in stretches of at least the deep-work threshold
+    pub deep_work: i64,
+}
+
+impl FocusMetrics {
+    /// Coming back to another app after more than `idle_gap` seconds away is
+    /// starting afresh rather than a switch
+    fn from_stretches<'a>(stretches: impl Iterator<Item = &'a Stretch>, deep_work: i64, idle_gap: i64) -> Self {
+        let mut metrics = FocusMetrics::default();
+        let mut lengths = Vec::new();
+        for s in stretches {
+            let length = s.entry.ended_at - s.entry.started_at;
+            metrics.total += length;
+            metrics.switches += s.switched_after.is_some_and(|gap| gap <= idle_gap) as i64;
+            if length >= deep_work {
+                metrics.deep_work += length;
+            }
+            lengths.push(length);
+        }
+        lengths.sort_unstable();
+        metrics.stretches = lengths.len() as i64;
+        metrics.longest = lengths.last().copied().unwrap_or(0);
+        metrics.median = match lengths.len() {
+            0 => 0,
+            n if n % 2 == 1 => lengths[n / 2],
+            n => (lengths[n / 2 - 1] + lengths[n / 2]) / 2,
+        };
+        metrics
+    }
+}
+
+/// What the daemon last recorded as focused
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct CurrentSession {
+    pub app: String,
+    pub title: Option<String>,
+    pub started_at: i64,
+    pub ended_at: i64,
+}
+
+/// Narrows usage down to an app (id or display name), a category and/or a
+/// project. All `None` means everything.
+#[derive(Debug, Clone, Default)]
+pub struct UsageFilter {
+    pub app: Option<String>,
+    pub category: Option<String>,
+    pub project: Option<String>,
+}
+
+/// A Pomodoro work block and where its time went
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct PomodoroEntry {
+    pub id: i64,
+    pub started_at: i64,
+    pub planned_end: i64,
+    pub ended_at: Option<i64>,
+    /// Ran its full length rather than being stopped
+    pub completed: bool,
+    /// Display name and focused seconds, most used first
+    pub apps: Vec<(String, i64)>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct Device {
+    pub id: String,
+    pub hostname: String,
 }
 
 impl Db {
@@ -23,19 +212,38 @@ impl Db {
     // ADD: The new functions below.
     
     pub fn init() -> anyhow::Result<Self> {
+        Self::open(Self::default_path())
+    }
+
+    /// Opens (and creates if needed) a focusd database at an explicit path.
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
+        
+        let mut db = Db {
+            conn,
+            device_id: String::new(),
+            device_filter: None,
+            config_aliases: HashMap::new(),
+            resolver: None,
+            merge_rules: HashMap::new(),
+            project_extractor: None,
+            day_start: NaiveTime::MIN,
+            zone: Zone::Recorded,
+        };
+        db.create_tables()?;
+        db.register_device()?;
+        Ok(db)
+    }
+
+    pub fn default_path() -> PathBuf {
         let mut db_path = dirs::data_local_dir().expect("Could not find data dir");
         db_path.push("focusd");
         if !db_path.exists() {
             let _ = fs::create_dir_all(&db_path);
         }
         db_path.push("focusd.db");
-
-        let conn = Connection::open(db_path)?;
-        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
-        
-        let db = Db { conn };
-        db.create_tables()?;
-        Ok(db)
+        db_path
     }
 
     fn create_tables(&self) -> Result<()> {
@@ -43,29 +251,343 @@ impl Db {
             "CREATE TABLE IF NOT EXISTS apps (
                 id INTEGER PRIMARY KEY,
                 app_id TEXT UNIQUE NOT NULL,
-                display_name TEXT
+                display_name TEXT,
+                alias TEXT,
+                desktop_name TEXT,
+                icon TEXT,
+                category TEXT,
+                categories TEXT
+            )", []
+        )?;
+        for column in ["alias", "desktop_name", "icon", "category", "categories"] {
+            if !self.has_column("apps", column)? {
+                self.conn.execute(&format!("ALTER TABLE apps ADD COLUMN {} TEXT", column), [])?;
+            }
+        }
+
+        // Small key/value store. Holds a random id that identifies this install
+        // (and this database when it is merged into another one).
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS meta (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )", []
+        )?;
+        self.conn.execute(
+            "INSERT OR IGNORE INTO meta (key, value) VALUES ('instance_id', lower(hex(randomblob(16))))",
+            [],
+        )?;
+
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS devices (
+                id TEXT PRIMARY KEY,
+                hostname TEXT NOT NULL
             )", []
         )?;
 
+        // Databases from before multi-device support have no device_id column.
+        // Everything in them was recorded on this install, except what the
+        // merge ledger says came from other databases: that goes to a device
+        // named after the source, so merging the same file again adds nothing.
+        if self.table_exists("usage_daily")? && !self.has_column("usage_daily", "device_id")? {
+            let merged = if self.table_exists("merge_ledger")? {
+                "INSERT OR IGNORE INTO devices (id, hostname)
+                     SELECT DISTINCT source_id, 'merged database' FROM merge_ledger;
+                 INSERT INTO usage_daily (app_ref_id, date, seconds_focused, device_id, updated_at)
+                     SELECT o.app_ref_id, o.date, MIN(l.seconds, o.seconds_focused), l.source_id,
+                            CAST(strftime('%s', 'now') AS INTEGER)
+                     FROM merge_ledger l
+                     JOIN apps a ON a.app_id = l.app_id
+                     JOIN usage_daily_old o ON o.app_ref_id = a.id AND o.date = l.date
+                     WHERE l.seconds > 0;
+                 UPDATE usage_daily_old SET seconds_focused = MAX(seconds_focused - COALESCE((
+                     SELECT SUM(u.seconds_focused) FROM usage_daily u
+                     WHERE u.app_ref_id = usage_daily_old.app_ref_id AND u.date = usage_daily_old.date
+                 ), 0), 0);
+                 DELETE FROM usage_daily_old WHERE seconds_focused = 0;
+                 DROP TABLE merge_ledger;"
+            } else {
+                ""
+            };
+            self.conn.execute_batch(&format!(
+                "BEGIN;
+                 ALTER TABLE usage_daily RENAME TO usage_daily_old;
+                 CREATE TABLE usage_daily (
+                     id INTEGER PRIMARY KEY,
+                     app_ref_id INTEGER NOT NULL,
+                     date TEXT NOT NULL,
+                     seconds_focused INTEGER DEFAULT 0,
+                     device_id TEXT NOT NULL,
+                     updated_at INTEGER NOT NULL DEFAULT 0,
+                     FOREIGN KEY(app_ref_id) REFERENCES apps(id),
+                     UNIQUE(app_ref_id, date, device_id)
+                 );
+                 {}
+                 INSERT INTO usage_daily (app_ref_id, date, seconds_focused, device_id, updated_at)
+                     SELECT app_ref_id, date, seconds_focused,
+                            (SELECT value FROM meta WHERE key = 'instance_id'),
+                            CAST(strftime('%s', 'now') AS INTEGER)
+                     FROM usage_daily_old;
+                 DROP TABLE usage_daily_old;
+                 COMMIT;",
+                merged
+            ))?;
+        }
+
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS usage_daily (
                 id INTEGER PRIMARY KEY,
                 app_ref_id INTEGER NOT NULL,
                 date TEXT NOT NULL,
                 seconds_focused INTEGER DEFAULT 0,
+                device_id TEXT NOT NULL,
+                updated_at INTEGER NOT NULL DEFAULT 0,
                 FOREIGN KEY(app_ref_id) REFERENCES apps(id),
-                UNIQUE(app_ref_id, date)
+                UNIQUE(app_ref_id, date, device_id)
+            )", []
+        )?;
+
+        // Raw focus sessions: one row per uninterrupted stretch in the same window.
+        // Pruned by the retention policy, unlike usage_daily which is kept forever.
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                id INTEGER PRIMARY KEY,
+                app_ref_id INTEGER NOT NULL,
+                device_id TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                ended_at INTEGER NOT NULL,
+                title TEXT,
+                pid INTEGER,
+                exe TEXT,
+                cmdline TEXT,
+                cgroup TEXT,
+                project TEXT,
+                domain TEXT,
+                command TEXT,
+                cwd TEXT,
+                utc_offset INTEGER NOT NULL DEFAULT 0,
+                FOREIGN KEY(app_ref_id) REFERENCES apps(id)
+            )", []
+        )?;
+        // Sessions from before offsets were stored get this machine's offset
+        // at the time, the best guess there is
+        if !self.has_column("sessions", "utc_offset")? {
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute("ALTER TABLE sessions ADD COLUMN utc_offset INTEGER NOT NULL DEFAULT 0", [])?;
+            let rows: Vec<(i64, i64)> = {
+                let mut stmt = tx.prepare("SELECT id, started_at FROM sessions")?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<Result<_>>()?
+            };
+            for (id, started_at) in rows {
+                tx.execute("UPDATE sessions SET utc_offset = ?2 WHERE id = ?1", params![id, timezone::local_offset(started_at)])?;
+            }
+            tx.commit()?;
+        }
+        for (column, kind) in [
+            ("pid", "INTEGER"), ("exe", "TEXT"), ("cmdline", "TEXT"), ("cgroup", "TEXT"), ("project", "TEXT"),
+            ("domain", "TEXT"), ("command", "TEXT"), ("cwd", "TEXT"),
+        ] {
+            if !self.has_column("sessions", column)? {
+                self.conn.execute(&format!("ALTER TABLE sessions ADD COLUMN {} {}", column, kind), [])?;
+            }
+        }
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS sessions_started_at ON sessions(started_at)", []
+        )?;
+
+        // Manual changes to history (forget / edit), newest last
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY,
+                at INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                app_id TEXT NOT NULL,
+                date TEXT NOT NULL,
+                device_id TEXT NOT NULL,
+                old_seconds INTEGER NOT NULL,
+                new_seconds INTEGER NOT NULL
+            )", []
+        )?;
+
+        // App ids folded into another one by `focusd apps merge`.
+        // Anything logged or imported under `from_app_id` goes to `into_app_id`.
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS app_merges (
+                from_app_id TEXT PRIMARY KEY,
+                into_app_id TEXT NOT NULL
+            )", []
+        )?;
+
+        // Last total imported per (device, raw app id, date). Lets sync add the
+        // difference to whatever row the app id maps to now.
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_rows (
+                device_id TEXT NOT NULL,
+                app_id TEXT NOT NULL,
+                date TEXT NOT NULL,
+                seconds INTEGER NOT NULL,
+                PRIMARY KEY(device_id, app_id, date)
+            )", []
+        )?;
+
+        // Last change-log entry imported from each other device, and where in
+        // which version of its log reading stopped (see sync.rs)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_state (
+                device_id TEXT PRIMARY KEY,
+                last_seq INTEGER NOT NULL,
+                log_generation INTEGER NOT NULL DEFAULT 0,
+                log_offset INTEGER NOT NULL DEFAULT 0
+            )", []
+        )?;
+        for column in ["log_generation", "log_offset"] {
+            if !self.has_column("sync_state", column)? {
+                self.conn.execute(&format!("ALTER TABLE sync_state ADD COLUMN {} INTEGER NOT NULL DEFAULT 0", column), [])?;
+            }
+        }
+
+        // Rows of this device removed by `forget` or `apps merge`. Sync sends
+        // them as a total of zero so other devices drop them too.
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_tombstones (
+                app_id TEXT NOT NULL,
+                date TEXT NOT NULL,
+                deleted_at INTEGER NOT NULL,
+                PRIMARY KEY(app_id, date)
+            )", []
+        )?;
+
+        // Focus mode runs (`focusd focus start` or [[focus.schedule]]) and the
+        // blocked apps focused during them
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS focus_sessions (
+                id INTEGER PRIMARY KEY,
+                device_id TEXT NOT NULL,
+                source TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                planned_end INTEGER NOT NULL,
+                ended_at INTEGER
+            )", []
+        )?;
+        // One row per break reminder until it's taken (or given up on)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS breaks (
+                id INTEGER PRIMARY KEY,
+                device_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                reminded_at INTEGER NOT NULL,
+                taken_at INTEGER,
+                snoozes INTEGER NOT NULL DEFAULT 0
+            )", []
+        )?;
+        // Pomodoro work blocks and what was focused during each
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS pomodoros (
+                id INTEGER PRIMARY KEY,
+                device_id TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                planned_end INTEGER NOT NULL,
+                ended_at INTEGER,
+                completed INTEGER NOT NULL DEFAULT 0
+            )", []
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS pomodoro_apps (
+                pomodoro_id INTEGER NOT NULL,
+                app_id TEXT NOT NULL,
+                seconds INTEGER NOT NULL,
+                PRIMARY KEY(pomodoro_id, app_id),
+                FOREIGN KEY(pomodoro_id) REFERENCES pomodoros(id)
+            )", []
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS focus_violations (
+                id INTEGER PRIMARY KEY,
+                focus_session_id INTEGER NOT NULL,
+                at INTEGER NOT NULL,
+                app_id TEXT NOT NULL,
+                action TEXT NOT NULL,
+                FOREIGN KEY(focus_session_id) REFERENCES focus_sessions(id)
             )", []
         )?;
         Ok(())
     }
 
-    pub fn log_usage(&self, wm_class: &str, _window_title: &str, seconds: u64) -> anyhow::Result<()> {
-        let today = Local::now().date_naive().to_string();
+    fn table_exists(&self, table: &str) -> Result<bool> {
+        self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![table],
+            |row| row.get(0),
+        )
+    }
+
+    fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |row| row.get(0),
+        )
+    }
+
+    /// Makes sure this install has a row in `devices` with an up to date hostname.
+    fn register_device(&mut self) -> anyhow::Result<()> {
+        self.device_id = self.instance_id()?;
+        self.conn.execute(
+            "INSERT INTO devices (id, hostname) VALUES (?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET hostname = ?2",
+            params![self.device_id, local_hostname()],
+        )?;
+        Ok(())
+    }
+
+    pub fn instance_id(&self) -> anyhow::Result<String> {
+        let id = self.conn.query_row(
+            "SELECT value FROM meta WHERE key = 'instance_id'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(id)
+    }
+
+    /// The stable id of this install. Every usage row carries the id of the
+    /// device it was recorded on.
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    pub(crate) fn meta_get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let value = self.conn.query_row(
+            "SELECT value FROM meta WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(value)
+    }
 
+    pub(crate) fn meta_set(&self, key: &str, value: &str) -> anyhow::Result<()> {
         self.conn.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = ?2",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn log_usage(
+        &self,
+        wm_class: &str,
+        window_title: &str,
+        seconds: u64,
+        context: &FocusContext,
+    ) -> anyhow::Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let canonical = self.canonical_app_id(wm_class)?;
+        let wm_class = canonical.as_str();
+
+        let inserted = self.conn.execute(
             "INSERT OR IGNORE INTO apps (app_id, display_name) VALUES (?1, ?2)",
-            params![wm_class, wm_class], 
+            params![wm_class, self.config_aliases.get(wm_class).map(String::as_str).unwrap_or(wm_class)], 
         )?;
 
         let app_ref_id: i64 = self.conn.query_row(
@@ -74,29 +596,115 @@ impl Db {
             |row| row.get(0),
         )?;
 
+        if inserted > 0 {
+            if let Some(resolver) = &self.resolver {
+                self.store_desktop_info(app_ref_id, resolver.resolve(wm_class))?;
+                self.refresh_display_names()?;
+            }
+        }
+
+        // A tick that spans the start of the day is shared between both days.
+        // Sessions stay whole; range queries clip them at the same boundary.
+        // Always recorded in the zone this machine is in right now
+        let today = Zone::Local.date_of(now, self.day_start);
+        let boundary = Zone::Local.day_start(today, self.day_start).clamp(now - seconds as i64, now);
+        let parts = [(today - chrono::Duration::days(1), boundary - (now - seconds as i64)), (today, now - boundary)];
+        for (date, seconds) in parts.into_iter().filter(|(_, s)| *s > 0) {
+            self.conn.execute(
+                "INSERT INTO usage_daily (app_ref_id, date, seconds_focused, device_id, updated_at) 
+                 VALUES (?1, ?2, ?3, ?4, CAST(strftime('%s', 'now') AS INTEGER))
+                 ON CONFLICT(app_ref_id, date, device_id) DO UPDATE SET
+                    seconds_focused = seconds_focused + ?3,
+                    updated_at = excluded.updated_at",
+                params![app_ref_id, date.to_string(), seconds, self.device_id],
+            )?;
+        }
+
+        self.log_session(app_ref_id, window_title, context, now, seconds as i64)?;
+        Ok(())
+    }
+
+    /// Extends the latest session if it is the same window (and process) and
+    /// the previous tick ended right before this one, otherwise starts a new session.
+    fn log_session(
+        &self,
+        app_ref_id: i64,
+        title: &str,
+        context: &FocusContext,
+        now: i64,
+        seconds: i64,
+    ) -> anyhow::Result<()> {
+        let process = context.process.as_ref();
+        let pid = process.map(|p| p.pid as i64);
+        // A DST change or a new time zone starts a new session, so each one
+        // has a single offset
+        let utc_offset = timezone::local_offset(now);
+        // A couple of seconds of slack for a sleep that ran long
+        let last: Option<(i64, bool)> = self.conn.query_row(
+            "SELECT id, app_ref_id = ?2 AND title IS ?3 AND pid IS ?4 AND domain IS ?5
+                    AND command IS ?6 AND cwd IS ?7 AND ended_at >= ?8 AND utc_offset = ?9
+             FROM sessions
+             WHERE device_id = ?1
+             ORDER BY id DESC LIMIT 1",
+            params![
+                self.device_id, app_ref_id, title, pid, context.domain,
+                context.command, context.cwd, now - seconds - 2, utc_offset
+            ],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?;
+
+        if let Some((id, true)) = last {
+            self.conn.execute(
+                "UPDATE sessions SET ended_at = ?2 WHERE id = ?1",
+                params![id, now],
+            )?;
+            return Ok(());
+        }
+
+        // NUL-separated, like /proc/<pid>/cmdline itself
+        let cmdline = process.map(|p| p.cmdline.join("\0"));
+        // A terminal's directory says more than its title
+        let project = match (&context.cwd, &self.project_extractor) {
+            (Some(cwd), _) => project_from_path(cwd),
+            (None, Some(extractor)) => {
+                let app_id: String = self.conn.query_row(
+                    "SELECT app_id FROM apps WHERE id = ?1",
+                    params![app_ref_id],
+                    |row| row.get(0),
+                )?;
+                extractor.extract(&app_id, title)
+            }
+            (None, None) => None,
+        };
         self.conn.execute(
-            "INSERT INTO usage_daily (app_ref_id, date, seconds_focused) 
-             VALUES (?1, ?2, ?3)
-             ON CONFLICT(app_ref_id, date) DO UPDATE SET seconds_focused = seconds_focused + ?3",
-            params![app_ref_id, today, seconds],
+            "INSERT INTO sessions (app_ref_id, device_id, started_at, ended_at, title, pid, exe, cmdline, cgroup,
+                                   project, domain, command, cwd, utc_offset)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                app_ref_id, self.device_id, now - seconds, now, title,
+                pid, process.and_then(|p| p.exe.clone()), cmdline, process.and_then(|p| p.cgroup.clone()),
+                project, context.domain, context.command, context.cwd, utc_offset
+            ],
         )?;
-
         Ok(())
     }
 
     pub fn export_json(&self) -> anyhow::Result<Vec<ExportEntry>> {
         let mut stmt = self.conn.prepare(
-            "SELECT u.date, a.display_name, u.seconds_focused
+            "SELECT u.date, a.display_name, u.seconds_focused, COALESCE(d.hostname, u.device_id)
              FROM usage_daily u
              JOIN apps a ON u.app_ref_id = a.id
+             LEFT JOIN devices d ON u.device_id = d.id
+             WHERE (?1 IS NULL OR u.device_id = ?1)
              ORDER BY u.date DESC, u.seconds_focused DESC"
         )?;
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(params![self.device_filter], |row| {
             Ok(ExportEntry {
                 date: row.get(0)?,
                 app: row.get(1)?,
                 seconds: row.get(2)?,
+                device: row.get(3)?,
             })
         })?;
 
@@ -110,16 +718,28 @@ impl Db {
     // === NEW QUERY LOGIC ===
 
     /// 1. Get total screen time PER DAY for a range (for Charts)
+    ///
     /// Returns: Map<"2023-12-14", 12304>
     pub fn get_daily_totals(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<HashMap<String, i64>> {
+        if self.zone != Zone::Recorded {
+            let mut map = HashMap::new();
+            for date in start.iter_days().take_while(|d| *d <= end) {
+                let total: i64 = self.session_usage_by("a.display_name", date, date)?.iter().map(|(_, s)| s).sum();
+                if total > 0 {
+                    map.insert(date.to_string(), total);
+                }
+            }
+            return Ok(map);
+        }
         let mut stmt = self.conn.prepare(
             "SELECT u.date, SUM(u.seconds_focused) 
              FROM usage_daily u
              WHERE u.date BETWEEN ?1 AND ?2
+               AND (?3 IS NULL OR u.device_id = ?3)
              GROUP BY u.date"
         )?;
 
-        let rows = stmt.query_map(params![start.to_string(), end.to_string()], |row| {
+        let rows = stmt.query_map(params![start.to_string(), end.to_string(), self.device_filter], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
         })?;
 
@@ -133,16 +753,22 @@ impl Db {
 
     /// 2. Get total time PER APP for a range (for List)
     pub fn get_app_usage_range(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<Vec<(String, i64)>> {
+        // Daily totals are kept by the day they were recorded on; anything
+        // else has to be worked out from the sessions
+        if self.zone != Zone::Recorded {
+            return self.session_usage_by("a.display_name", start, end);
+        }
         let mut stmt = self.conn.prepare(
             "SELECT a.display_name, SUM(u.seconds_focused) as total
              FROM usage_daily u
              JOIN apps a ON u.app_ref_id = a.id
              WHERE u.date BETWEEN ?1 AND ?2
+               AND (?3 IS NULL OR u.device_id = ?3)
              GROUP BY a.display_name
              ORDER BY total DESC"
         )?;
 
-        let rows = stmt.query_map(params![start.to_string(), end.to_string()], |row| {
+        let rows = stmt.query_map(params![start.to_string(), end.to_string(), self.device_filter], |row| {
             Ok((row.get(0)?, row.get(1)?))
         })?;
 
@@ -153,10 +779,1428 @@ impl Db {
         Ok(result)
     }
     
+    /// Seconds per local hour of the day over a range, from the raw sessions
+    pub fn get_hourly_totals(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<[i64; 24]> {
+        let mut hours = [0; 24];
+        self.for_each_hour(start, end, &UsageFilter::default(), |_, hour, seconds| hours[hour as usize] += seconds)?;
+        Ok(hours)
+    }
+
+    /// Seconds per weekday (Monday first) and local hour over a range
+    pub fn get_weekday_hour_totals(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<[[i64; 24]; 7]> {
+        let mut matrix = [[0; 24]; 7];
+        self.for_each_hour(start, end, &UsageFilter::default(), |date, hour, seconds| {
+            matrix[date.weekday().num_days_from_monday() as usize][hour as usize] += seconds
+        })?;
+        Ok(matrix)
+    }
+
+    /// Splits the sessions in a range that match `filter` at wall-clock hours
+    /// and calls `f(day, hour, seconds)` for each piece
+    fn for_each_hour(&self, start: NaiveDate, end: NaiveDate, filter: &UsageFilter, mut f: impl FnMut(NaiveDate, u32, i64)) -> anyhow::Result<()> {
+        let (from, to, shift) = self.session_bounds(start, end);
+        let mut stmt = self.conn.prepare(
+            "SELECT MAX(s.started_at, ?1 - ?4 * s.utc_offset), MIN(s.ended_at, ?2 - ?4 * s.utc_offset), s.utc_offset
+             FROM sessions s
+             JOIN apps a ON s.app_ref_id = a.id
+             WHERE s.ended_at > ?1 - ?4 * s.utc_offset AND s.started_at < ?2 - ?4 * s.utc_offset
+               AND (?3 IS NULL OR s.device_id = ?3)
+               AND (?5 IS NULL OR lower(a.app_id) = lower(?5) OR lower(a.display_name) = lower(?5))
+               AND (?6 IS NULL OR lower(a.category) = lower(?6))
+               AND (?7 IS NULL OR lower(s.project) = lower(?7))"
+        )?;
+        let rows = stmt.query_map(params![from, to, self.device_filter, shift, filter.app, filter.category, filter.project], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i32>(2)?))
+        })?;
+
+        for r in rows {
+            let (start, end, offset) = r?;
+            self.zone.resolve(Some(offset)).split_hours(start, end, self.day_start, &mut f);
+        }
+        Ok(())
+    }
+
+    /// The sessions of one day in order. Back-to-back sessions of the same app
+    /// (title changes, ticks) are joined into one entry.
+    pub fn get_timeline(&self, date: NaiveDate) -> anyhow::Result<Vec<TimelineEntry>> {
+        let mut timeline: Vec<TimelineEntry> = self.stretches(date, date)?.into_iter().map(|s| s.entry).collect();
+        // Devices were walked one after the other
+        timeline.sort_by_key(|e| e.started_at);
+        Ok(timeline)
+    }
+
+    /// Focus switches and stretch lengths over a whole range. A change of app
+    /// after more than `idle_gap` seconds without focus isn't a switch.
+    pub fn get_focus_metrics(&self, start: NaiveDate, end: NaiveDate, deep_work: i64, idle_gap: i64) -> anyhow::Result<FocusMetrics> {
+        let stretches = self.stretches(start, end)?;
+        Ok(FocusMetrics::from_stretches(stretches.iter(), deep_work, idle_gap))
+    }
+
+    /// Focus metrics for each day of a range, stretches cut at the start of the day
+    pub fn get_daily_focus_metrics(&self, start: NaiveDate, end: NaiveDate, deep_work: i64, idle_gap: i64) -> anyhow::Result<Vec<(NaiveDate, FocusMetrics)>> {
+        let mut days = Vec::new();
+        for date in start.iter_days().take_while(|d| *d <= end) {
+            let stretches = self.stretches(date, date)?;
+            days.push((date, FocusMetrics::from_stretches(stretches.iter(), deep_work, idle_gap)));
+        }
+        Ok(days)
+    }
+
+    /// Focus metrics per app (display name), most used first. `switches`
+    /// counts how often focus moved to the app from another one.
+    pub fn get_app_focus_metrics(&self, start: NaiveDate, end: NaiveDate, deep_work: i64, idle_gap: i64) -> anyhow::Result<Vec<(String, FocusMetrics)>> {
+        let stretches = self.stretches(start, end)?;
+        let mut apps: Vec<String> = stretches.iter().map(|s| s.entry.app.clone()).collect();
+        apps.sort();
+        apps.dedup();
+
+        let mut result: Vec<(String, FocusMetrics)> = apps.into_iter().map(|app| {
+            let metrics = FocusMetrics::from_stretches(stretches.iter().filter(|s| s.entry.app == app), deep_work, idle_gap);
+            (app, metrics)
+        }).collect();
+        result.sort_by_key(|(_, m)| std::cmp::Reverse(m.total));
+        Ok(result)
+    }
+
+    /// Uninterrupted stretches in one app, device by device. Sessions of the
+    /// same app with at most a tick or so between them are one stretch.
+    fn stretches(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<Vec<Stretch>> {
+        let (from, to, shift) = self.session_bounds(start, end);
+        let mut stmt = self.conn.prepare(
+            "SELECT s.device_id, a.display_name, s.title, MAX(s.started_at, ?1 - ?4 * s.utc_offset),
+                    MIN(s.ended_at, ?2 - ?4 * s.utc_offset), s.utc_offset
+             FROM sessions s
+             JOIN apps a ON s.app_ref_id = a.id
+             WHERE s.ended_at > ?1 - ?4 * s.utc_offset AND s.started_at < ?2 - ?4 * s.utc_offset
+               AND (?3 IS NULL OR s.device_id = ?3)
+             ORDER BY s.device_id, s.started_at"
+        )?;
+        let rows = stmt.query_map(params![from, to, self.device_filter, shift], |row| {
+            let started_at = row.get(3)?;
+            Ok((row.get::<_, String>(0)?, TimelineEntry {
+                app: row.get(1)?,
+                title: row.get(2)?,
+                started_at,
+                ended_at: row.get(4)?,
+                offset: self.zone.resolve(Some(row.get(5)?)).offset_at(started_at),
+            }))
+        })?;
+
+        let mut stretches: Vec<Stretch> = Vec::new();
+        for r in rows {
+            let (device, entry) = r?;
+            match stretches.last_mut() {
+                Some(last) if last.device == device && last.entry.app == entry.app && entry.started_at <= last.entry.ended_at + 60 => {
+                    last.entry.ended_at = last.entry.ended_at.max(entry.ended_at);
+                }
+                last => {
+                    // Coming back to the same app after a break isn't a switch
+                    let switched_after = last
+                        .filter(|l| l.device == device && l.entry.app != entry.app)
+                        .map(|l| (entry.started_at - l.entry.ended_at).max(0));
+                    stretches.push(Stretch { device, entry, switched_after });
+                }
+            }
+        }
+        Ok(stretches)
+    }
+
+    /// Total time per .desktop category for a range; apps without one are "Other"
+    pub fn get_category_usage_range(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<Vec<(String, i64)>> {
+        if self.zone != Zone::Recorded {
+            return self.session_usage_by("COALESCE(a.category, 'Other')", start, end);
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT COALESCE(a.category, 'Other'), SUM(u.seconds_focused) as total
+             FROM usage_daily u
+             JOIN apps a ON u.app_ref_id = a.id
+             WHERE u.date BETWEEN ?1 AND ?2
+               AND (?3 IS NULL OR u.device_id = ?3)
+             GROUP BY 1
+             ORDER BY total DESC"
+        )?;
+        let rows = stmt.query_map(params![start.to_string(), end.to_string(), self.device_filter], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        Ok(rows.collect::<Result<_>>()?)
+    }
+
+    /// The app in the most recent session on this device if it ended after
+    /// `since`, with the time focus moved to it (back-to-back sessions of the
+    /// same app, e.g. across title changes, count as one)
+    pub fn get_current_session(&self, since: i64) -> anyhow::Result<Option<CurrentSession>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.app_ref_id, a.display_name, s.title, s.started_at, s.ended_at
+             FROM sessions s
+             JOIN apps a ON s.app_ref_id = a.id
+             WHERE s.device_id = ?1
+             ORDER BY s.ended_at DESC
+             LIMIT 200"
+        )?;
+        let mut rows = stmt.query_map(params![self.device_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, i64>(3)?, row.get::<_, i64>(4)?))
+        })?;
+
+        let Some((app_ref, app, title, mut started_at, ended_at)) = rows.next().transpose()? else { return Ok(None) };
+        if ended_at < since {
+            return Ok(None);
+        }
+        for r in rows {
+            let (other_ref, _, _, other_start, other_end) = r?;
+            // Allow for a tick or so between sessions
+            if other_ref != app_ref || other_end < started_at - 60 {
+                break;
+            }
+            started_at = started_at.min(other_start);
+        }
+        Ok(Some(CurrentSession { app, title, started_at, ended_at }))
+    }
+
     // Legacy support for CLI (wraps the new logic)
     pub fn get_usage_since(&self, days_ago: i64) -> anyhow::Result<Vec<(String, i64)>> {
-        let end = Local::now().date_naive();
+        let end = self.today();
         let start = end - chrono::Duration::days(days_ago);
         self.get_app_usage_range(start, end)
     }
-}
\ No newline at end of file
+
+    // === DISPLAY NAMES ===
+
+    /// Loads the [alias] table from config.toml and re-resolves every
+    /// `apps.display_name`, so all readers see the same names.
+    pub fn set_config_aliases(&mut self, aliases: &HashMap<String, String>) -> anyhow::Result<()> {
+        self.config_aliases = aliases.clone();
+        self.refresh_display_names()
+    }
+
+    /// Matches every recorded app against installed .desktop files, stores
+    /// name, icon and category, and keeps the resolver for apps seen later.
+    /// Only the recording process needs this; everyone else reads what it stored.
+    pub fn set_app_resolver(&mut self, resolver: AppResolver) -> anyhow::Result<()> {
+        let apps: Vec<(i64, String)> = {
+            let mut stmt = self.conn.prepare("SELECT id, app_id FROM apps")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+        let tx = self.conn.unchecked_transaction()?;
+        for (id, app_id) in apps {
+            self.store_desktop_info(id, resolver.resolve(&app_id))?;
+        }
+        tx.commit()?;
+
+        self.resolver = Some(resolver);
+        self.refresh_display_names()
+    }
+
+    fn store_desktop_info(&self, app_ref_id: i64, resolved: Option<ResolvedApp>) -> anyhow::Result<()> {
+        let (name, icon, category, categories) = match &resolved {
+            Some(r) => (
+                Some(r.name.as_str()),
+                r.icon.as_ref().map(|p| p.to_string_lossy().to_string()),
+                r.main_category(),
+                Some(r.categories.join(";")),
+            ),
+            None => (None, None, None, None),
+        };
+        self.conn.execute(
+            "UPDATE apps SET desktop_name = ?2, icon = ?3, category = ?4, categories = ?5 WHERE id = ?1",
+            params![app_ref_id, name, icon, category, categories],
+        )?;
+        Ok(())
+    }
+
+    /// display_name = config alias, else database alias, else the .desktop
+    /// name, else the raw id
+    fn refresh_display_names(&self) -> anyhow::Result<()> {
+        // One pass that only touches rows whose name changes, so opening the
+        // database with an unchanged config writes nothing
+        self.conn.execute(
+            "UPDATE apps SET display_name = n.name
+             FROM (
+                 SELECT a.id, COALESCE(c.value, a.alias, a.desktop_name, a.app_id) AS name
+                 FROM apps a LEFT JOIN json_each(?1) c ON c.key = a.app_id
+             ) AS n
+             WHERE n.id = apps.id AND apps.display_name IS NOT n.name",
+            params![serde_json::to_string(&self.config_aliases)?],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_alias(&self, app_id: &str, name: &str) -> anyhow::Result<()> {
+        let changed = self.conn.execute(
+            "UPDATE apps SET alias = ?2 WHERE app_id = ?1",
+            params![app_id, name],
+        )?;
+        if changed == 0 {
+            anyhow::bail!("No recorded app with id '{}'", app_id);
+        }
+        self.refresh_display_names()
+    }
+
+    pub fn unset_alias(&self, app_id: &str) -> anyhow::Result<()> {
+        let changed = self.conn.execute(
+            "UPDATE apps SET alias = NULL WHERE app_id = ?1",
+            params![app_id],
+        )?;
+        if changed == 0 {
+            anyhow::bail!("No recorded app with id '{}'", app_id);
+        }
+        self.refresh_display_names()
+    }
+
+    pub fn list_apps(&self) -> anyhow::Result<Vec<AppInfo>> {
+        self.query_apps(None)
+    }
+
+    /// Names and category of one app, after [merge] rules
+    pub fn get_app(&self, app_id: &str) -> anyhow::Result<Option<AppInfo>> {
+        let app_id = self.canonical_app_id(app_id)?;
+        Ok(self.query_apps(Some(&app_id))?.pop())
+    }
+
+    fn query_apps(&self, app_id: Option<&str>) -> anyhow::Result<Vec<AppInfo>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT app_id, COALESCE(display_name, app_id), alias, desktop_name, icon, category
+             FROM apps
+             WHERE (?1 IS NULL OR app_id = ?1)
+             ORDER BY app_id"
+        )?;
+        let rows = stmt.query_map(params![app_id], |row| {
+            let app_id: String = row.get(0)?;
+            Ok(AppInfo {
+                config_alias: self.config_aliases.get(&app_id).cloned(),
+                app_id,
+                display_name: row.get(1)?,
+                alias: row.get(2)?,
+                desktop_name: row.get(3)?,
+                icon: row.get(4)?,
+                category: row.get(5)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for r in rows {
+            result.push(r?);
+        }
+        Ok(result)
+    }
+
+    // === APP MERGES ===
+
+    /// The id an app is stored under after config rules and `apps merge`
+    pub fn canonical_app_id(&self, app_id: &str) -> anyhow::Result<String> {
+        let mut current = app_id.to_string();
+        // Bounded, in case someone writes a cycle into [merge]
+        for _ in 0..8 {
+            let next = match self.merge_rules.get(&current) {
+                Some(into) => Some(into.clone()),
+                None => self.conn.query_row(
+                    "SELECT into_app_id FROM app_merges WHERE from_app_id = ?1",
+                    params![current],
+                    |row| row.get(0),
+                ).optional()?,
+            };
+            match next {
+                Some(next) if next != current => current = next,
+                _ => break,
+            }
+        }
+        Ok(current)
+    }
+
+    /// Loads [merge] rules from config.toml and folds any history recorded
+    /// under the old ids into their targets.
+    pub fn set_merge_rules(&mut self, rules: &HashMap<String, String>) -> anyhow::Result<()> {
+        self.merge_rules = rules.clone();
+        for (from, into) in rules {
+            let exists: bool = self.conn.query_row(
+                "SELECT COUNT(*) > 0 FROM apps WHERE app_id = ?1",
+                params![from],
+                |row| row.get(0),
+            )?;
+            if exists && from != into {
+                self.merge_apps(from, into)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves all history of `from` onto `into` (adding up daily totals, keeping
+    /// sessions), removes `from` from `apps` and maps it to `into` from now on.
+    pub fn merge_apps(&self, from: &str, into: &str) -> anyhow::Result<AppMergeReport> {
+        let into = self.canonical_app_id(into)?;
+        if from == into {
+            anyhow::bail!("'{}' already is '{}'", from, into);
+        }
+        let from_ref: i64 = self.conn.query_row(
+            "SELECT id FROM apps WHERE app_id = ?1",
+            params![from],
+            |row| row.get(0),
+        ).optional()?.ok_or_else(|| anyhow::anyhow!("No recorded app with id '{}'", from))?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        let into_ref = Self::ensure_app(&tx, &into, None)?;
+        let mut report = AppMergeReport::default();
+
+        let rows: Vec<(String, String, i64)> = {
+            let mut stmt = tx.prepare(
+                "SELECT date, device_id, seconds_focused FROM usage_daily WHERE app_ref_id = ?1"
+            )?;
+            let rows = stmt.query_map(params![from_ref], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+        for (date, device_id, seconds) in &rows {
+            // Another device's time that sync can't attribute yet belongs to
+            // `from`; remember it, or the next entry for `from` adds it again
+            if *device_id != self.device_id {
+                let unaccounted = self.unaccounted_seconds(&tx, device_id, from_ref, from, date)?;
+                if unaccounted > 0 {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO sync_rows (device_id, app_id, date, seconds) VALUES (?1, ?2, ?3, ?4)",
+                        params![device_id, from, date, unaccounted],
+                    )?;
+                }
+            }
+            tx.execute(
+                "INSERT INTO usage_daily (app_ref_id, date, seconds_focused, device_id, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, CAST(strftime('%s', 'now') AS INTEGER))
+                 ON CONFLICT(app_ref_id, date, device_id) DO UPDATE SET
+                    seconds_focused = seconds_focused + ?3,
+                    updated_at = excluded.updated_at",
+                params![into_ref, date, seconds, device_id],
+            )?;
+            Self::audit(&tx, "merge", from_ref, date, device_id, *seconds, 0)?;
+            if *device_id == self.device_id {
+                Self::tombstone(&tx, from_ref, date)?;
+            }
+            report.seconds += seconds;
+        }
+        report.rows = tx.execute("DELETE FROM usage_daily WHERE app_ref_id = ?1", params![from_ref])?;
+        report.sessions = tx.execute(
+            "UPDATE sessions SET app_ref_id = ?2 WHERE app_ref_id = ?1",
+            params![from_ref, into_ref],
+        )?;
+        tx.execute("DELETE FROM apps WHERE id = ?1", params![from_ref])?;
+
+        // Earlier merges into `from` now point straight at `into`
+        tx.execute(
+            "UPDATE app_merges SET into_app_id = ?2 WHERE into_app_id = ?1",
+            params![from, into],
+        )?;
+        tx.execute(
+            "INSERT INTO app_merges (from_app_id, into_app_id) VALUES (?1, ?2)
+             ON CONFLICT(from_app_id) DO UPDATE SET into_app_id = ?2",
+            params![from, into],
+        )?;
+        tx.commit()?;
+
+        if let Some(resolver) = &self.resolver {
+            self.store_desktop_info(into_ref, resolver.resolve(&into))?;
+        }
+        self.refresh_display_names()?;
+        Ok(report)
+    }
+
+    /// (from, into) pairs recorded by `merge_apps`
+    pub fn list_app_merges(&self) -> anyhow::Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT from_app_id, into_app_id FROM app_merges ORDER BY into_app_id, from_app_id"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<Result<_>>()?)
+    }
+
+    // === PROJECTS, SITES & COMMANDS ===
+
+    /// Enables project detection for sessions logged from now on
+    pub fn set_project_extractor(&mut self, extractor: ProjectExtractor) {
+        self.project_extractor = Some(extractor);
+    }
+
+    /// Total time PER PROJECT for a range, from sessions whose title named a project.
+    pub fn get_project_usage_range(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<Vec<(String, i64)>> {
+        self.session_usage_by("s.project", start, end)
+    }
+
+    /// Total browser time PER SITE for a range, as reported by the native-messaging host.
+    pub fn get_domain_usage_range(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<Vec<(String, i64)>> {
+        self.session_usage_by("s.domain", start, end)
+    }
+
+    /// Terminal time PER FOREGROUND COMMAND, from the shell hook or tmux.
+    pub fn get_command_usage_range(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<Vec<(String, i64)>> {
+        self.session_usage_by("s.command", start, end)
+    }
+
+    /// Terminal time PER WORKING DIRECTORY, from the shell hook or tmux.
+    pub fn get_directory_usage_range(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<Vec<(String, i64)>> {
+        self.session_usage_by("s.cwd", start, end)
+    }
+
+    /// Sums session time grouped by an expression over `sessions s` and
+    /// `apps a`, skipping NULLs. Sessions crossing the range edges only count
+    /// the part inside.
+    fn session_usage_by(&self, column: &str, start: NaiveDate, end: NaiveDate) -> anyhow::Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {column}, SUM(MIN(s.ended_at, ?2 - ?4 * s.utc_offset) - MAX(s.started_at, ?1 - ?4 * s.utc_offset)) as total
+             FROM sessions s
+             JOIN apps a ON s.app_ref_id = a.id
+             WHERE {column} IS NOT NULL
+               AND s.ended_at > ?1 - ?4 * s.utc_offset AND s.started_at < ?2 - ?4 * s.utc_offset
+               AND (?3 IS NULL OR s.device_id = ?3)
+             GROUP BY {column}
+             ORDER BY total DESC"
+        ))?;
+
+        let (from, to, shift) = self.session_bounds(start, end);
+        let rows = stmt.query_map(params![from, to, self.device_filter, shift], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+
+        let mut result = Vec::new();
+        for r in rows {
+            result.push(r?);
+        }
+        Ok(result)
+    }
+
+    // === LIMITS ===
+
+    /// Time spent on an app (by id or display name) and/or a category
+    /// between two dates, on all devices: a budget is per person, not per machine.
+    pub fn get_limit_usage(
+        &self,
+        app: Option<&str>,
+        category: Option<&str>,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> anyhow::Result<i64> {
+        let total = self.conn.query_row(
+            "SELECT COALESCE(SUM(u.seconds_focused), 0)
+             FROM usage_daily u
+             JOIN apps a ON a.id = u.app_ref_id
+             WHERE u.date >= ?1 AND u.date <= ?2
+               AND (?3 IS NULL OR lower(a.app_id) = lower(?3) OR lower(a.display_name) = lower(?3))
+               AND (?4 IS NULL OR lower(a.category) = lower(?4))",
+            params![start.to_string(), end.to_string(), app, category],
+            |row| row.get(0),
+        )?;
+        Ok(total)
+    }
+
+    // === GOALS ===
+
+    /// The first day anything was recorded
+    pub fn get_first_day(&self) -> anyhow::Result<Option<NaiveDate>> {
+        let first: Option<String> = self.conn.query_row(
+            "SELECT MIN(date) FROM usage_daily WHERE (?1 IS NULL OR device_id = ?1)",
+            params![self.device_filter],
+            |row| row.get(0),
+        )?;
+        Ok(first.and_then(|d| d.parse().ok()))
+    }
+
+    /// First day `get_daily_usage_for` has everything for with `filter`.
+    /// Daily totals reach back to the first recording, sessions only as far
+    /// as retention keeps them; the day of the oldest kept session is left out
+    /// when older ones were pruned, as it may have lost some.
+    pub fn get_first_day_for(&self, filter: &UsageFilter) -> anyhow::Result<Option<NaiveDate>> {
+        let first = self.get_first_day()?;
+        if filter.project.is_none() && self.zone == Zone::Recorded {
+            return Ok(first);
+        }
+        let oldest: Option<(i64, i32)> = self.conn.query_row(
+            "SELECT started_at, utc_offset FROM sessions
+             WHERE (?1 IS NULL OR device_id = ?1)
+             ORDER BY started_at LIMIT 1",
+            params![self.device_filter],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?;
+        let Some((started, offset)) = oldest else { return Ok(None) };
+        let day = self.zone.resolve(Some(offset)).date_of(started, self.day_start);
+        Ok(Some(match first {
+            Some(first) if first < day => day + chrono::Duration::days(1),
+            _ => day,
+        }))
+    }
+
+    /// Seconds per day matching `filter` in a range; days without any are
+    /// left out. Projects and `--tz` need the raw sessions, so those only
+    /// reach back as far as sessions are kept.
+    pub fn get_daily_usage_for(&self, filter: &UsageFilter, start: NaiveDate, end: NaiveDate) -> anyhow::Result<HashMap<NaiveDate, i64>> {
+        let mut days = HashMap::new();
+        if filter.project.is_some() || self.zone != Zone::Recorded {
+            self.for_each_hour(start, end, filter, |date, _, seconds| *days.entry(date).or_default() += seconds)?;
+            return Ok(days);
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT u.date, SUM(u.seconds_focused)
+             FROM usage_daily u
+             JOIN apps a ON a.id = u.app_ref_id
+             WHERE u.date BETWEEN ?1 AND ?2
+               AND (?3 IS NULL OR u.device_id = ?3)
+               AND (?4 IS NULL OR lower(a.app_id) = lower(?4) OR lower(a.display_name) = lower(?4))
+               AND (?5 IS NULL OR lower(a.category) = lower(?5))
+             GROUP BY u.date"
+        )?;
+        let rows = stmt.query_map(
+            params![start.to_string(), end.to_string(), self.device_filter, filter.app, filter.category],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+        for r in rows {
+            let (date, seconds) = r?;
+            if let Ok(date) = date.parse() {
+                days.insert(date, seconds);
+            }
+        }
+        Ok(days)
+    }
+
+    // === FOCUS MODE ===
+
+    /// Records the start of a focus session and returns its id
+    pub fn start_focus_session(&self, source: &str, started_at: i64, planned_end: i64) -> anyhow::Result<i64> {
+        self.conn.execute(
+            "INSERT INTO focus_sessions (device_id, source, started_at, planned_end) VALUES (?1, ?2, ?3, ?4)",
+            params![self.device_id, source, started_at, planned_end],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn end_focus_session(&self, id: i64, ended_at: i64) -> anyhow::Result<()> {
+        self.conn.execute(
+            "UPDATE focus_sessions SET ended_at = ?2 WHERE id = ?1 AND ended_at IS NULL",
+            params![id, ended_at],
+        )?;
+        Ok(())
+    }
+
+    /// Sessions a previous daemon didn't get to end (crash, shutdown) are taken
+    /// to have run as planned, but not past `now`.
+    pub fn close_stale_focus_sessions(&self, now: i64) -> anyhow::Result<()> {
+        self.conn.execute(
+            "UPDATE focus_sessions SET ended_at = MIN(planned_end, ?2)
+             WHERE device_id = ?1 AND ended_at IS NULL",
+            params![self.device_id, now],
+        )?;
+        Ok(())
+    }
+
+    pub fn log_focus_violation(&self, focus_session_id: i64, app_id: &str, action: &str) -> anyhow::Result<()> {
+        self.conn.execute(
+            "INSERT INTO focus_violations (focus_session_id, at, app_id, action)
+             VALUES (?1, CAST(strftime('%s', 'now') AS INTEGER), ?2, ?3)",
+            params![focus_session_id, app_id, action],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent focus sessions first, honoring the device filter
+    pub fn list_focus_sessions(&self, limit: usize) -> anyhow::Result<Vec<FocusSessionEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT f.id, f.source, f.started_at, f.planned_end, f.ended_at,
+                    (SELECT COUNT(*) FROM focus_violations v WHERE v.focus_session_id = f.id)
+             FROM focus_sessions f
+             WHERE (?1 IS NULL OR f.device_id = ?1)
+             ORDER BY f.started_at DESC
+             LIMIT ?2"
+        )?;
+        let rows = stmt.query_map(params![self.device_filter, limit as i64], |row| {
+            Ok(FocusSessionEntry {
+                id: row.get(0)?,
+                source: row.get(1)?,
+                started_at: row.get(2)?,
+                planned_end: row.get(3)?,
+                ended_at: row.get(4)?,
+                violations: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_>>()?)
+    }
+
+    /// Blocked apps focused during one focus session, oldest first
+    pub fn get_focus_violations(&self, focus_session_id: i64) -> anyhow::Result<Vec<(i64, String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT at, app_id, action FROM focus_violations WHERE focus_session_id = ?1 ORDER BY at"
+        )?;
+        let rows = stmt.query_map(params![focus_session_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        Ok(rows.collect::<Result<_>>()?)
+    }
+
+    // === POMODORO ===
+
+    /// Records the start of a work block and returns its id
+    pub fn start_pomodoro(&self, started_at: i64, planned_end: i64) -> anyhow::Result<i64> {
+        self.conn.execute(
+            "INSERT INTO pomodoros (device_id, started_at, planned_end) VALUES (?1, ?2, ?3)",
+            params![self.device_id, started_at, planned_end],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// `completed` is false when the block was stopped early
+    pub fn end_pomodoro(&self, id: i64, ended_at: i64, completed: bool) -> anyhow::Result<()> {
+        self.conn.execute(
+            "UPDATE pomodoros SET ended_at = ?2, completed = ?3 WHERE id = ?1 AND ended_at IS NULL",
+            params![id, ended_at, completed],
+        )?;
+        Ok(())
+    }
+
+    /// Blocks a previous daemon didn't get to end count as interrupted
+    pub fn close_stale_pomodoros(&self, now: i64) -> anyhow::Result<()> {
+        self.conn.execute(
+            "UPDATE pomodoros SET ended_at = MIN(planned_end, ?2), completed = 0
+             WHERE device_id = ?1 AND ended_at IS NULL",
+            params![self.device_id, now],
+        )?;
+        Ok(())
+    }
+
+    /// Adds focused time of an app to a work block
+    pub fn add_pomodoro_time(&self, id: i64, app_id: &str, seconds: u64) -> anyhow::Result<()> {
+        let app_id = self.canonical_app_id(app_id)?;
+        self.conn.execute(
+            "INSERT INTO pomodoro_apps (pomodoro_id, app_id, seconds) VALUES (?1, ?2, ?3)
+             ON CONFLICT(pomodoro_id, app_id) DO UPDATE SET seconds = seconds + ?3",
+            params![id, app_id, seconds],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent work blocks first, each with its apps by time spent
+    pub fn list_pomodoros(&self, limit: usize) -> anyhow::Result<Vec<PomodoroEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, started_at, planned_end, ended_at, completed
+             FROM pomodoros
+             WHERE (?1 IS NULL OR device_id = ?1)
+             ORDER BY started_at DESC
+             LIMIT ?2"
+        )?;
+        let rows = stmt.query_map(params![self.device_filter, limit as i64], |row| {
+            Ok(PomodoroEntry {
+                id: row.get(0)?,
+                started_at: row.get(1)?,
+                planned_end: row.get(2)?,
+                ended_at: row.get(3)?,
+                completed: row.get(4)?,
+                apps: Vec::new(),
+            })
+        })?;
+        let mut entries: Vec<PomodoroEntry> = rows.collect::<Result<_>>()?;
+
+        let mut apps = self.conn.prepare(
+            "SELECT COALESCE(a.display_name, p.app_id), p.seconds
+             FROM pomodoro_apps p
+             LEFT JOIN apps a ON a.app_id = p.app_id
+             WHERE p.pomodoro_id = ?1
+             ORDER BY p.seconds DESC"
+        )?;
+        for entry in &mut entries {
+            let rows = apps.query_map(params![entry.id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            entry.apps = rows.collect::<Result<_>>()?;
+        }
+        Ok(entries)
+    }
+
+    // === BREAKS ===
+
+    /// Records a break reminder being shown and returns its id
+    pub fn log_break_reminder(&self, name: &str, reminded_at: i64) -> anyhow::Result<i64> {
+        self.conn.execute(
+            "INSERT INTO breaks (device_id, name, reminded_at) VALUES (?1, ?2, ?3)",
+            params![self.device_id, name, reminded_at],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn mark_break_taken(&self, id: i64, taken_at: i64) -> anyhow::Result<()> {
+        self.conn.execute(
+            "UPDATE breaks SET taken_at = ?2 WHERE id = ?1 AND taken_at IS NULL",
+            params![id, taken_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn add_break_snooze(&self, id: i64) -> anyhow::Result<()> {
+        self.conn.execute("UPDATE breaks SET snoozes = snoozes + 1 WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Per reminder: (name, times reminded, breaks taken, snoozes) in a date range
+    pub fn get_break_compliance(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<Vec<(String, i64, i64, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, COUNT(*), COUNT(taken_at), SUM(snoozes)
+             FROM breaks
+             WHERE reminded_at >= ?1 AND reminded_at < ?2
+               AND (?3 IS NULL OR device_id = ?3)
+             GROUP BY name
+             ORDER BY name"
+        )?;
+        let rows = stmt.query_map(
+            params![self.day_start_ts(start), self.day_start_ts(end + chrono::Duration::days(1)), self.device_filter],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )?;
+        Ok(rows.collect::<Result<_>>()?)
+    }
+
+    // === DEVICES ===
+
+    pub fn list_devices(&self) -> anyhow::Result<Vec<Device>> {
+        let mut stmt = self.conn.prepare("SELECT id, hostname FROM devices ORDER BY hostname")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Device { id: row.get(0)?, hostname: row.get(1)? })
+        })?;
+
+        let mut result = Vec::new();
+        for r in rows {
+            result.push(r?);
+        }
+        Ok(result)
+    }
+
+    /// Sets the local time at which a day begins (`day_start` in config.toml)
+    pub fn set_day_start(&mut self, time: NaiveTime) {
+        self.day_start = time;
+    }
+
+    /// Sets the time zone reports are computed in (`--tz`)
+    pub fn set_zone(&mut self, zone: Zone) {
+        self.zone = zone;
+    }
+
+    /// The current day in the report zone, taking `day_start` into account
+    pub fn today(&self) -> NaiveDate {
+        self.zone.resolve(None).date_of(chrono::Utc::now().timestamp(), self.day_start)
+    }
+
+    /// Wall-clock time of `ts` in the report zone, for rows stored without an offset
+    pub fn wall_clock(&self, ts: i64) -> NaiveDateTime {
+        self.zone.resolve(None).wall_clock(ts)
+    }
+
+    /// Unix timestamp at which `date` begins, for rows stored without an offset
+    fn day_start_ts(&self, date: NaiveDate) -> i64 {
+        self.zone.resolve(None).day_start(date, self.day_start)
+    }
+
+    /// Bounds of a range of days for session queries: `(from, to, shift)`.
+    /// With the recorded zone they are wall-clock seconds and each session's
+    /// own offset comes off them (`?from - shift * utc_offset`); otherwise
+    /// they are Unix timestamps and `shift` is 0.
+    fn session_bounds(&self, start: NaiveDate, end: NaiveDate) -> (i64, i64, i64) {
+        let end = end + chrono::Duration::days(1);
+        match self.zone {
+            Zone::Recorded => {
+                let wall = |date: NaiveDate| date.and_time(self.day_start).and_utc().timestamp();
+                (wall(start), wall(end), 1)
+            }
+            zone => (zone.day_start(start, self.day_start), zone.day_start(end, self.day_start), 0),
+        }
+    }
+
+    /// Restricts report queries to one device. Accepts the hostname, the full id
+    /// or an unambiguous id prefix.
+    pub fn set_device_filter(&mut self, name: &str) -> anyhow::Result<()> {
+        let matches: Vec<Device> = self.list_devices()?
+            .into_iter()
+            .filter(|d| d.hostname == name || d.id.starts_with(name))
+            .collect();
+
+        match matches.as_slice() {
+            [device] => {
+                self.device_filter = Some(device.id.clone());
+                Ok(())
+            }
+            [] => anyhow::bail!("Unknown device '{}'", name),
+            _ => anyhow::bail!("'{}' matches more than one device, use the device id", name),
+        }
+    }
+
+    /// Total time PER DEVICE for a range. Ignores the device filter.
+    pub fn get_device_usage_range(&self, start: NaiveDate, end: NaiveDate) -> anyhow::Result<Vec<(Device, i64)>> {
+        if self.zone != Zone::Recorded {
+            let devices = self.list_devices()?;
+            return Ok(self.session_usage_by("s.device_id", start, end)?.into_iter().map(|(id, total)| {
+                let hostname = devices.iter().find(|d| d.id == id).map_or_else(|| id.clone(), |d| d.hostname.clone());
+                (Device { id, hostname }, total)
+            }).collect());
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT u.device_id, COALESCE(d.hostname, u.device_id), SUM(u.seconds_focused) as total
+             FROM usage_daily u
+             LEFT JOIN devices d ON u.device_id = d.id
+             WHERE u.date BETWEEN ?1 AND ?2
+             GROUP BY u.device_id
+             ORDER BY total DESC"
+        )?;
+
+        let rows = stmt.query_map(params![start.to_string(), end.to_string()], |row| {
+            Ok((Device { id: row.get(0)?, hostname: row.get(1)? }, row.get(2)?))
+        })?;
+
+        let mut result = Vec::new();
+        for r in rows {
+            result.push(r?);
+        }
+        Ok(result)
+    }
+
+    // === SYNC ===
+
+    /// Rows recorded on this device that changed at or after `since` (unix
+    /// seconds), and rows deleted since then as a total of zero.
+    pub(crate) fn local_changes_since(&self, since: i64) -> anyhow::Result<Vec<SyncEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.app_id, a.display_name, u.date, u.seconds_focused
+             FROM usage_daily u
+             JOIN apps a ON u.app_ref_id = a.id
+             WHERE u.device_id = ?1 AND u.updated_at >= ?2
+             UNION ALL
+             SELECT t.app_id, NULL, t.date, 0
+             FROM sync_tombstones t
+             WHERE t.deleted_at >= ?2 AND NOT EXISTS (
+                 SELECT 1 FROM usage_daily u JOIN apps a ON u.app_ref_id = a.id
+                 WHERE a.app_id = t.app_id AND u.date = t.date AND u.device_id = ?1
+             )
+             ORDER BY 3, 1"
+        )?;
+
+        let rows = stmt.query_map(params![self.device_id, since], |row| {
+            Ok(SyncEntry {
+                seq: 0,
+                app_id: row.get(0)?,
+                display_name: row.get(1)?,
+                date: row.get(2)?,
+                seconds: row.get(3)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for r in rows {
+            result.push(r?);
+        }
+        Ok(result)
+    }
+
+    /// Last imported seq of a device, and the generation and byte offset of
+    /// its log where reading stopped
+    pub(crate) fn sync_position(&self, device_id: &str) -> anyhow::Result<(u64, u64, u64)> {
+        let position: Option<(i64, i64, i64)> = self.conn.query_row(
+            "SELECT last_seq, log_generation, log_offset FROM sync_state WHERE device_id = ?1",
+            params![device_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).optional()?;
+        let (seq, generation, offset) = position.unwrap_or_default();
+        Ok((seq as u64, generation as u64, offset as u64))
+    }
+
+    pub(crate) fn set_sync_log_position(&self, device_id: &str, generation: u64, offset: u64) -> anyhow::Result<()> {
+        self.conn.execute(
+            "INSERT INTO sync_state (device_id, last_seq, log_generation, log_offset) VALUES (?1, 0, ?2, ?3)
+             ON CONFLICT(device_id) DO UPDATE SET log_generation = ?2, log_offset = ?3",
+            params![device_id, generation as i64, offset as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Applies another device's change-log entries. Entries carry absolute
+    /// totals, so applying the same entry twice changes nothing.
+    pub(crate) fn apply_sync_entries(&self, device: &Device, entries: &[SyncEntry]) -> anyhow::Result<()> {
+        let Some(last) = entries.iter().map(|e| e.seq).max() else { return Ok(()) };
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO devices (id, hostname) VALUES (?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET hostname = ?2",
+            params![device.id, device.hostname],
+        )?;
+        for e in entries {
+            let canonical = self.canonical_app_id(&e.app_id)?;
+            let name = if canonical == e.app_id { e.display_name.as_deref() } else { None };
+            let app_ref_id = Self::ensure_app(&tx, &canonical, name)?;
+
+            let prev: Option<i64> = tx.query_row(
+                "SELECT seconds FROM sync_rows WHERE device_id = ?1 AND app_id = ?2 AND date = ?3",
+                params![device.id, e.app_id, e.date],
+                |row| row.get(0),
+            ).optional()?;
+
+            // The first entry of an id takes over the time in its row that no
+            // synced id accounts for: imported before sync_rows existed, or by
+            // `db merge`
+            let prev = match prev {
+                Some(prev) => prev,
+                None => self.unaccounted_seconds(&tx, &device.id, app_ref_id, &canonical, &e.date)?,
+            };
+            // Merged app ids share a row, so only add what changed for this id
+            let delta = e.seconds - prev;
+            tx.execute(
+                "INSERT INTO usage_daily (app_ref_id, date, seconds_focused, device_id, updated_at)
+                 VALUES (?1, ?2, MAX(?3, 0), ?4, CAST(strftime('%s', 'now') AS INTEGER))
+                 ON CONFLICT(app_ref_id, date, device_id) DO UPDATE SET
+                    seconds_focused = MAX(seconds_focused + ?3, 0),
+                    updated_at = excluded.updated_at",
+                params![app_ref_id, e.date, delta, device.id],
+            )?;
+            tx.execute(
+                "INSERT INTO sync_rows (device_id, app_id, date, seconds) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(device_id, app_id, date) DO UPDATE SET seconds = ?4",
+                params![device.id, e.app_id, e.date, e.seconds],
+            )?;
+        }
+        // Rows the other device deleted
+        tx.execute("DELETE FROM usage_daily WHERE device_id = ?1 AND seconds_focused <= 0", params![device.id])?;
+        tx.execute(
+            "INSERT INTO sync_state (device_id, last_seq) VALUES (?1, ?2)
+             ON CONFLICT(device_id) DO UPDATE SET last_seq = MAX(last_seq, ?2)",
+            params![device.id, last as i64],
+        )?;
+        tx.commit()?;
+        self.refresh_display_names()
+    }
+
+    /// Seconds of another device's row for `canonical` on `date` that aren't
+    /// covered by the sync_rows of the ids mapping to it
+    fn unaccounted_seconds(&self, conn: &Connection, device_id: &str, app_ref_id: i64, canonical: &str, date: &str) -> anyhow::Result<i64> {
+        let total: i64 = conn.query_row(
+            "SELECT seconds_focused FROM usage_daily WHERE app_ref_id = ?1 AND date = ?2 AND device_id = ?3",
+            params![app_ref_id, date, device_id],
+            |row| row.get(0),
+        ).optional()?.unwrap_or(0);
+        let synced: Vec<(String, i64)> = {
+            let mut stmt = conn.prepare("SELECT app_id, seconds FROM sync_rows WHERE device_id = ?1 AND date = ?2")?;
+            let rows = stmt.query_map(params![device_id, date], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_>>()?
+        };
+        let mut accounted = 0;
+        for (app_id, seconds) in synced {
+            if self.canonical_app_id(&app_id)? == canonical {
+                accounted += seconds;
+            }
+        }
+        Ok((total - accounted).max(0))
+    }
+
+    /// Returns the row id of an app, creating it if needed. A name coming from
+    /// another device is kept as the alias of a new app.
+    fn ensure_app(conn: &Connection, app_id: &str, display_name: Option<&str>) -> Result<i64> {
+        let alias = display_name.filter(|name| *name != app_id);
+        conn.execute(
+            "INSERT OR IGNORE INTO apps (app_id, display_name, alias) VALUES (?1, ?2, ?3)",
+            params![app_id, display_name.unwrap_or(app_id), alias],
+        )?;
+        conn.query_row(
+            "SELECT id FROM apps WHERE app_id = ?1",
+            params![app_id],
+            |row| row.get(0),
+        )
+    }
+
+    // === RETENTION ===
+
+    /// Deletes sessions older than `sessions_days` and strips window titles (and
+    /// command lines and terminal directories, which often hold file names too)
+    /// from sessions older than `titles_days`. Daily totals are never touched.
+    /// With `dry_run` nothing is changed and the report says what would go.
+    pub fn prune(&self, policy: &Retention, dry_run: bool) -> anyhow::Result<PruneReport> {
+        let now = chrono::Utc::now().timestamp();
+        let cutoff = |days: u64| (days > 0).then(|| now - days as i64 * 86400);
+        let session_cutoff = cutoff(policy.sessions_days);
+        let title_cutoff = cutoff(policy.titles_days);
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut report = PruneReport::default();
+
+        if let Some(before) = session_cutoff {
+            report.sessions = tx.query_row(
+                "SELECT COUNT(*) FROM sessions WHERE ended_at < ?1",
+                params![before],
+                |row| row.get(0),
+            )?;
+            if !dry_run {
+                tx.execute("DELETE FROM sessions WHERE ended_at < ?1", params![before])?;
+            }
+        }
+
+        if let Some(before) = title_cutoff {
+            // Sessions deleted above are already gone (or counted) in a dry run
+            report.titles = tx.query_row(
+                "SELECT COUNT(*) FROM sessions
+                 WHERE ended_at < ?1 AND (title IS NOT NULL OR cmdline IS NOT NULL OR cwd IS NOT NULL)
+                   AND (?2 IS NULL OR ended_at >= ?2)",
+                params![before, session_cutoff],
+                |row| row.get(0),
+            )?;
+            if !dry_run {
+                tx.execute(
+                    "UPDATE sessions SET title = NULL, cmdline = NULL, cwd = NULL
+                     WHERE ended_at < ?1 AND (title IS NOT NULL OR cmdline IS NOT NULL OR cwd IS NOT NULL)",
+                    params![before],
+                )?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Rebuilds the file to reclaim space and refreshes the query planner stats.
+    pub fn vacuum(&self) -> anyhow::Result<()> {
+        self.conn.execute_batch("VACUUM; ANALYZE;")?;
+        Ok(())
+    }
+
+    // === CORRECTIONS ===
+
+    /// Deletes recorded history for an app (matched by raw id or display name),
+    /// a date range, or both, in one transaction. Respects the device filter.
+    /// Every removed daily total is written to the audit log.
+    pub fn forget(&self, app: Option<&str>, from: Option<NaiveDate>, to: Option<NaiveDate>) -> anyhow::Result<ForgetReport> {
+        if app.is_none() && from.is_none() && to.is_none() {
+            anyhow::bail!("Refusing to forget everything, give an app or a date range");
+        }
+        // Ids come from our own table, so formatting them into the SQL is safe
+        let app_match = match app {
+            Some(app) => {
+                let ids = self.find_app_refs(app)?;
+                if ids.is_empty() {
+                    anyhow::bail!("No recorded app matches '{}'", app);
+                }
+                let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                format!("app_ref_id IN ({})", ids.join(","))
+            }
+            None => "1 = 1".to_string(),
+        };
+
+        // Open ends of the range cover all recorded history
+        let from_str = from.map(|d| d.to_string()).unwrap_or_else(|| "0000-01-01".to_string());
+        let to_str = to.map(|d| d.to_string()).unwrap_or_else(|| "9999-12-31".to_string());
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut report = ForgetReport::default();
+
+        let removed: Vec<(i64, String, String, i64)> = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT app_ref_id, date, device_id, seconds_focused FROM usage_daily
+                 WHERE {} AND date BETWEEN ?1 AND ?2 AND (?3 IS NULL OR device_id = ?3)",
+                app_match
+            ))?;
+            let rows = stmt.query_map(
+                params![from_str, to_str, self.device_filter],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )?;
+            rows.collect::<Result<_>>()?
+        };
+
+        for (app_ref_id, date, device_id, seconds) in &removed {
+            Self::audit(&tx, "forget", *app_ref_id, date, device_id, *seconds, 0)?;
+            if *device_id == self.device_id {
+                Self::tombstone(&tx, *app_ref_id, date)?;
+            }
+            report.seconds += seconds;
+        }
+        report.rows = tx.execute(
+            &format!(
+                "DELETE FROM usage_daily
+                 WHERE {} AND date BETWEEN ?1 AND ?2 AND (?3 IS NULL OR device_id = ?3)",
+                app_match
+            ),
+            params![from_str, to_str, self.device_filter],
+        )?;
+
+        // Same days as the daily totals above: the ones the sessions were recorded on
+        let wall = |date: NaiveDate| date.and_time(self.day_start).and_utc().timestamp();
+        let start_ts = from.map(wall).unwrap_or(i64::MIN);
+        let end_ts = to.map(|d| wall(d + chrono::Duration::days(1))).unwrap_or(i64::MAX);
+        report.sessions = tx.execute(
+            &format!(
+                "DELETE FROM sessions
+                 WHERE {} AND started_at + utc_offset >= ?1 AND started_at + utc_offset < ?2
+                   AND (?3 IS NULL OR device_id = ?3)",
+                app_match
+            ),
+            params![start_ts, end_ts, self.device_filter],
+        )?;
+
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Overwrites the daily total of one app on one day. The change goes to the
+    /// filtered device, or this device if there is no filter. An id that was
+    /// merged away edits the app it went into; an app never recorded is an
+    /// error rather than a new one. Returns the old value.
+    pub fn set_usage(&self, app: &str, date: NaiveDate, seconds: i64) -> anyhow::Result<i64> {
+        let device_id = self.device_filter.clone().unwrap_or_else(|| self.device_id.clone());
+        let canonical = self.canonical_app_id(app)?;
+        let app = canonical.as_str();
+
+        let tx = self.conn.unchecked_transaction()?;
+        let app_ref_id = match self.find_app_refs(app)?.as_slice() {
+            [id] => *id,
+            [] => anyhow::bail!("No recorded app matches '{}'", app),
+            _ => anyhow::bail!("'{}' matches more than one app, use the raw app id", app),
+        };
+
+        let old: i64 = tx.query_row(
+            "SELECT seconds_focused FROM usage_daily
+             WHERE app_ref_id = ?1 AND date = ?2 AND device_id = ?3",
+            params![app_ref_id, date.to_string(), device_id],
+            |row| row.get(0),
+        ).optional()?.unwrap_or(0);
+
+        tx.execute(
+            "INSERT INTO usage_daily (app_ref_id, date, seconds_focused, device_id, updated_at)
+             VALUES (?1, ?2, ?3, ?4, CAST(strftime('%s', 'now') AS INTEGER))
+             ON CONFLICT(app_ref_id, date, device_id) DO UPDATE SET
+                seconds_focused = ?3,
+                updated_at = excluded.updated_at",
+            params![app_ref_id, date.to_string(), seconds, device_id],
+        )?;
+        Self::audit(&tx, "edit", app_ref_id, &date.to_string(), &device_id, old, seconds)?;
+
+        tx.commit()?;
+        Ok(old)
+    }
+
+    pub fn get_audit_log(&self, limit: usize) -> anyhow::Result<Vec<AuditEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT l.at, l.action, l.app_id, l.date, COALESCE(d.hostname, l.device_id),
+                    l.old_seconds, l.new_seconds
+             FROM audit_log l
+             LEFT JOIN devices d ON l.device_id = d.id
+             ORDER BY l.id DESC LIMIT ?1"
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(AuditEntry {
+                at: row.get(0)?,
+                action: row.get(1)?,
+                app_id: row.get(2)?,
+                date: row.get(3)?,
+                device: row.get(4)?,
+                old_seconds: row.get(5)?,
+                new_seconds: row.get(6)?,
+            })
+        })?;
+
+        let mut result = Vec::new();
+        for r in rows {
+            result.push(r?);
+        }
+        Ok(result)
+    }
+
+    /// Apps matching a raw id exactly, or else every app with that display name
+    fn find_app_refs(&self, app: &str) -> anyhow::Result<Vec<i64>> {
+        let exact: Option<i64> = self.conn.query_row(
+            "SELECT id FROM apps WHERE app_id = ?1",
+            params![app],
+            |row| row.get(0),
+        ).optional()?;
+        if let Some(id) = exact {
+            return Ok(vec![id]);
+        }
+
+        let mut stmt = self.conn.prepare("SELECT id FROM apps WHERE display_name = ?1")?;
+        let ids = stmt.query_map(params![app], |row| row.get(0))?
+            .collect::<Result<_>>()?;
+        Ok(ids)
+    }
+
+    /// Records that this device's row of an app on `date` is gone
+    fn tombstone(conn: &Connection, app_ref_id: i64, date: &str) -> Result<()> {
+        conn.execute(
+            "INSERT INTO sync_tombstones (app_id, date, deleted_at)
+             SELECT app_id, ?2, CAST(strftime('%s', 'now') AS INTEGER) FROM apps WHERE id = ?1
+             ON CONFLICT(app_id, date) DO UPDATE SET deleted_at = excluded.deleted_at",
+            params![app_ref_id, date],
+        )?;
+        Ok(())
+    }
+
+    fn audit(conn: &Connection, action: &str, app_ref_id: i64, date: &str, device_id: &str, old: i64, new: i64) -> Result<()> {
+        conn.execute(
+            "INSERT INTO audit_log (at, action, app_id, date, device_id, old_seconds, new_seconds)
+             SELECT CAST(strftime('%s', 'now') AS INTEGER), ?1, app_id, ?3, ?4, ?5, ?6
+             FROM apps WHERE id = ?2",
+            params![action, app_ref_id, date, device_id, old, new],
+        )?;
+        Ok(())
+    }
+
+    // === BACKUP / RESTORE / MERGE ===
+
+    /// Writes a consistent snapshot to `dest` using the SQLite online backup API.
+    /// Safe to run while the daemon is writing.
+    pub fn backup_to<P: AsRef<Path>>(&self, dest: P) -> anyhow::Result<()> {
+        let dest = dest.as_ref();
+        if dest.exists() {
+            anyhow::bail!("{} already exists, refusing to overwrite", dest.display());
+        }
+        self.conn.backup(DatabaseName::Main, dest, None)?;
+        Ok(())
+    }
+
+    /// Replaces the contents of this database with the snapshot at `src`.
+    pub fn restore_from<P: AsRef<Path>>(&mut self, src: P) -> anyhow::Result<()> {
+        let src = src.as_ref();
+        Self::check_focusd_db(&Self::open_read_only(src)?)?;
+
+        self.conn.restore(DatabaseName::Main, src, None::<fn(rusqlite::backup::Progress)>)?;
+        // Snapshots from older versions may miss newer tables
+        self.create_tables()?;
+        self.register_device()?;
+        Ok(())
+    }
+
+    /// Folds `apps` and `usage_daily` from another focusd database into this one.
+    /// Rows keep the device they were recorded on, and a device's total for a day
+    /// only ever grows through a merge, so merging the same file again (or a
+    /// newer copy of it) never double-counts. Returns the number of seconds added.
+    pub fn merge_from<P: AsRef<Path>>(&self, other: P) -> anyhow::Result<i64> {
+        let other_path = other.as_ref();
+        let src = Self::open_read_only(other_path)?;
+        Self::check_focusd_db(&src)?;
+
+        // Databases from before the meta table existed are identified by their path
+        let has_meta: bool = src.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'meta'",
+            [],
+            |row| row.get(0),
+        )?;
+        let source_id: String = if has_meta {
+            src.query_row("SELECT value FROM meta WHERE key = 'instance_id'", [], |row| row.get(0))
+                .optional()?
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        let source_id = if source_id.is_empty() {
+            fs::canonicalize(other_path)?.to_string_lossy().to_string()
+        } else {
+            source_id
+        };
+
+        if source_id == self.device_id {
+            anyhow::bail!("Refusing to merge a database into itself");
+        }
+
+        let has_devices: bool = src.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('usage_daily') WHERE name = 'device_id'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        let mut devices = vec![Device {
+            id: source_id.clone(),
+            hostname: other_path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+        }];
+        let device_col = if has_devices {
+            let mut stmt = src.prepare("SELECT id, hostname FROM devices")?;
+            let rows = stmt.query_map([], |row| Ok(Device { id: row.get(0)?, hostname: row.get(1)? }))?;
+            for r in rows {
+                devices.push(r?);
+            }
+            "u.device_id"
+        } else {
+            "?1"
+        };
+
+        let mut stmt = src.prepare(&format!(
+            "SELECT a.app_id, a.display_name, u.date, u.seconds_focused, {}
+             FROM usage_daily u
+             JOIN apps a ON u.app_ref_id = a.id",
+            device_col
+        ))?;
+        let mut rows = if has_devices { stmt.query([])? } else { stmt.query(params![source_id])? };
+
+        let tx = self.conn.unchecked_transaction()?;
+        for d in &devices {
+            // Our own rows can come back from a machine we synced with earlier
+            if d.id == self.device_id { continue; }
+            tx.execute(
+                "INSERT INTO devices (id, hostname) VALUES (?1, ?2)
+                 ON CONFLICT(id) DO UPDATE SET hostname = ?2",
+                params![d.id, d.hostname],
+            )?;
+        }
+
+        // Source ids that map to the same app here add up to one row
+        let mut merged: BTreeMap<(String, String, String), (i64, Option<String>)> = BTreeMap::new();
+        while let Some(row) = rows.next()? {
+            let app_id: String = row.get(0)?;
+            let display_name: Option<String> = row.get(1)?;
+            let date: String = row.get(2)?;
+            let seconds: i64 = row.get(3)?;
+            let device_id: String = row.get(4)?;
+            if device_id == self.device_id { continue; }
+
+            let canonical = self.canonical_app_id(&app_id)?;
+            let entry = merged.entry((canonical.clone(), date, device_id)).or_default();
+            entry.0 += seconds;
+            if canonical == app_id {
+                entry.1 = display_name;
+            }
+        }
+
+        let mut added = 0;
+        for ((canonical, date, device_id), (seconds, name)) in merged {
+            let app_ref_id = Self::ensure_app(&tx, &canonical, name.as_deref())?;
+            let existing: i64 = tx.query_row(
+                "SELECT seconds_focused FROM usage_daily
+                 WHERE app_ref_id = ?1 AND date = ?2 AND device_id = ?3",
+                params![app_ref_id, date, device_id],
+                |row| row.get(0),
+            ).optional()?.unwrap_or(0);
+            if seconds <= existing { continue; }
+
+            tx.execute(
+                "INSERT INTO usage_daily (app_ref_id, date, seconds_focused, device_id, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, CAST(strftime('%s', 'now') AS INTEGER))
+                 ON CONFLICT(app_ref_id, date, device_id) DO UPDATE SET
+                    seconds_focused = ?3,
+                    updated_at = excluded.updated_at",
+                params![app_ref_id, date, seconds, device_id],
+            )?;
+            added += seconds - existing;
+        }
+        drop(rows);
+        tx.commit()?;
+        self.refresh_display_names()?;
+        Ok(added)
+    }
+
+    fn open_read_only(path: &Path) -> anyhow::Result<Connection> {
+        if !path.exists() {
+            anyhow::bail!("{} does not exist", path.display());
+        }
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(conn)
+    }
+
+    fn check_focusd_db(conn: &Connection) -> anyhow::Result<()> {
+        let tables: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('apps', 'usage_daily')",
+            [],
+            |row| row.get(0),
+        )?;
+        if tables != 2 {
+            anyhow::bail!("Not a focusd database (missing apps/usage_daily tables)");
+        }
+        Ok(())
+    }
+}
+
+fn local_hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|h| h.trim().to_string())
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
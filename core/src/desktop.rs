@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The parts of a `.desktop` file we care about
+#[derive(Debug, Clone)]
+pub struct DesktopEntry {
+    /// Desktop file id, e.g. `org.kde.dolphin` for `org.kde.dolphin.desktop`
+    pub id: String,
+    /// `Name=`, localized for the current locale when the file has a translation
+    pub name: String,
+    pub startup_wm_class: Option<String>,
+    /// Basename of the program in `Exec=`, e.g. `code` for `/usr/bin/code --unity-launch %F`
+    pub exec: Option<String>,
+    /// Raw `Icon=` value, either a theme icon name or an absolute path
+    pub icon: Option<String>,
+    pub categories: Vec<String>,
+}
+
+/// What the resolver knows about a raw app id
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResolvedApp {
+    pub desktop_id: String,
+    pub name: String,
+    pub icon: Option<PathBuf>,
+    pub categories: Vec<String>,
+}
+
+impl ResolvedApp {
+    /// The first of the freedesktop main categories, used to group apps in reports
+    pub fn main_category(&self) -> Option<&str> {
+        const MAIN: [&str; 13] = [
+            "Development", "Office", "Graphics", "AudioVideo", "Audio", "Video", "Game",
+            "Education", "Science", "Network", "Settings", "System", "Utility",
+        ];
+        MAIN.iter()
+            .find(|main| self.categories.iter().any(|c| c == *main))
+            .copied()
+    }
+}
+
+/// Maps raw app ids (WM_CLASS / Hyprland class) to installed applications.
+pub struct AppResolver {
+    entries: Vec<DesktopEntry>,
+}
+
+impl AppResolver {
+    /// Scans every `applications` directory once
+    pub fn load() -> Self {
+        AppResolver { entries: scan() }
+    }
+
+    pub fn entries(&self) -> &[DesktopEntry] {
+        &self.entries
+    }
+
+    /// Returns `None` when nothing matches; callers fall back to the raw id.
+    pub fn resolve(&self, app_id: &str) -> Option<ResolvedApp> {
+        let entry = find(&self.entries, app_id)?;
+        Some(ResolvedApp {
+            desktop_id: entry.id.clone(),
+            name: entry.name.clone(),
+            icon: entry.icon.as_deref().and_then(find_icon),
+            categories: entry.categories.clone(),
+        })
+    }
+}
+
+/// XDG data directories, most important first
+fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|h| h.join(".local/share")));
+    if let Some(home) = data_home {
+        dirs.push(home);
+    }
+
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    for dir in data_dirs.split(':').filter(|d| !d.is_empty()) {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs
+}
+
+/// Every `applications` directory from the XDG base dirs, most important first
+pub fn application_dirs() -> Vec<PathBuf> {
+    data_dirs().into_iter().map(|d| d.join("applications")).collect()
+}
+
+/// Reads all desktop entries. When two directories define the same id, the
+/// first one wins, as the spec says.
+pub fn scan() -> Vec<DesktopEntry> {
+    let locales = locale_keys();
+    let mut seen = HashMap::new();
+    for dir in application_dirs() {
+        collect(&dir, &dir, &locales, &mut seen);
+    }
+    let mut entries: Vec<DesktopEntry> = seen.into_values().collect();
+    // Stable order so matching doesn't depend on hash order
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    entries
+}
+
+fn collect(root: &Path, dir: &Path, locales: &[String], seen: &mut HashMap<String, DesktopEntry>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect(root, &path, locales, seen);
+            continue;
+        }
+        if path.extension().and_then(|e| e.to_str()) != Some("desktop") { continue; }
+
+        // Files in subdirectories get ids like `kde4-dolphin`
+        let Ok(rel) = path.strip_prefix(root) else { continue };
+        let id = rel.with_extension("").to_string_lossy().replace('/', "-");
+        if seen.contains_key(&id) { continue; }
+
+        if let Some(parsed) = parse(&path, &id, locales) {
+            seen.insert(id, parsed);
+        }
+    }
+}
+
+/// `Name[..]` keys to try for the current locale, best first.
+/// `de_DE.UTF-8@euro` gives `de_DE@euro`, `de_DE`, `de@euro`, `de`.
+fn locale_keys() -> Vec<String> {
+    let raw = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|v| env::var(v).ok())
+        .find(|v| !v.is_empty())
+        .unwrap_or_default();
+
+    let (rest, modifier) = match raw.split_once('@') {
+        Some((r, m)) => (r, Some(m)),
+        None => (raw.as_str(), None),
+    };
+    let rest = rest.split('.').next().unwrap_or("");
+    if rest.is_empty() || rest == "C" || rest == "POSIX" {
+        return Vec::new();
+    }
+    let (lang, country) = match rest.split_once('_') {
+        Some((l, c)) => (l, Some(c)),
+        None => (rest, None),
+    };
+
+    let mut keys = Vec::new();
+    if let (Some(c), Some(m)) = (country, modifier) { keys.push(format!("{}_{}@{}", lang, c, m)); }
+    if let Some(c) = country { keys.push(format!("{}_{}", lang, c)); }
+    if let Some(m) = modifier { keys.push(format!("{}@{}", lang, m)); }
+    keys.push(lang.to_string());
+    keys
+}
+
+fn parse(path: &Path, id: &str, locales: &[String]) -> Option<DesktopEntry> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut in_main = false;
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut wm_class = None;
+    let mut exec = None;
+    let mut icon = None;
+    let mut categories = Vec::new();
+    let mut hidden = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_main = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_main { continue; }
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let (key, value) = (key.trim(), value.trim());
+
+        if let Some(locale) = key.strip_prefix("Name[").and_then(|k| k.strip_suffix(']')) {
+            names.insert(locale.to_string(), value.to_string());
+            continue;
+        }
+        match key {
+            "Name" => { names.insert(String::new(), value.to_string()); }
+            "StartupWMClass" => wm_class = Some(value.to_string()),
+            "Exec" => exec = exec_basename(value),
+            "Icon" if !value.is_empty() => icon = Some(value.to_string()),
+            "Categories" => {
+                categories = value.split(';').filter(|c| !c.is_empty()).map(String::from).collect();
+            }
+            "Hidden" | "NoDisplay" => hidden |= value == "true",
+            _ => {}
+        }
+    }
+
+    // Hidden entries are usually helpers (url handlers etc.), not real apps
+    if hidden { return None; }
+
+    let name = locales.iter()
+        .find_map(|l| names.get(l))
+        .or_else(|| names.get(""))?
+        .clone();
+
+    Some(DesktopEntry { id: id.to_string(), name, startup_wm_class: wm_class, exec, icon, categories })
+}
+
+/// The program an `Exec=` line starts, skipping `env VAR=x` prefixes
+fn exec_basename(exec: &str) -> Option<String> {
+    let program = exec.split_whitespace()
+        .find(|w| *w != "env" && !w.contains('='))?
+        .trim_matches('"');
+    let base = Path::new(program).file_name()?.to_string_lossy().to_string();
+    Some(base)
+}
+
+/// Finds the entry for a raw app id, comparing case-insensitively against
+/// `StartupWMClass`, then the desktop id, then the `Exec` program.
+pub fn find<'a>(entries: &'a [DesktopEntry], app_id: &str) -> Option<&'a DesktopEntry> {
+    let app_id = app_id.to_lowercase();
+    let eq = |s: Option<&str>| s.map(str::to_lowercase).as_deref() == Some(app_id.as_str());
+
+    entries.iter()
+        .find(|e| eq(e.startup_wm_class.as_deref()))
+        .or_else(|| entries.iter().find(|e| eq(Some(&e.id))))
+        // `code` -> `com.visualstudio.code`
+        .or_else(|| entries.iter().find(|e| eq(e.id.rsplit('.').next())))
+        .or_else(|| entries.iter().find(|e| eq(e.exec.as_deref())))
+}
+
+/// Looks an icon name up in the hicolor theme and pixmaps. Absolute paths are
+/// returned as they are if the file exists.
+pub fn find_icon(icon: &str) -> Option<PathBuf> {
+    let path = Path::new(icon);
+    if path.is_absolute() {
+        return path.exists().then(|| path.to_path_buf());
+    }
+
+    const SIZES: [&str; 9] = [
+        "scalable", "512x512", "256x256", "128x128", "96x96", "64x64", "48x48", "32x32", "16x16",
+    ];
+    let mut bases: Vec<PathBuf> = dirs::home_dir().map(|h| h.join(".icons")).into_iter().collect();
+    bases.extend(data_dirs().into_iter().map(|d| d.join("icons")));
+
+    for base in &bases {
+        for size in SIZES {
+            for ext in ["svg", "png"] {
+                let candidate = base.join("hicolor").join(size).join("apps").join(format!("{}.{}", icon, ext));
+                if candidate.exists() {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+    for ext in ["svg", "png", "xpm"] {
+        let candidate = Path::new("/usr/share/pixmaps").join(format!("{}.{}", icon, ext));
+        if candidate.exists() {
+            return Some(candidate);
+        }
+    }
+    None
+}
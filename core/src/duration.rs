@@ -0,0 +1,57 @@
+/// Parses human durations like `1h20m`, `50m`, `90s` or `2h` into seconds.
+/// A bare number is taken as minutes. Anything that doesn't fit an i64 of
+/// seconds is an error.
+pub fn parse_duration(input: &str) -> anyhow::Result<u64> {
+    let input = input.trim();
+    if input.is_empty() {
+        anyhow::bail!("Empty duration");
+    }
+    let too_long = || anyhow::anyhow!("Duration '{}' is too long", input);
+    if let Ok(minutes) = input.parse::<u64>() {
+        return minutes.checked_mul(60).filter(|s| *s <= i64::MAX as u64).ok_or_else(too_long);
+    }
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            ' ' => continue,
+            _ => anyhow::bail!("Invalid duration '{}' (use e.g. 1h20m, 45m, 30s)", input),
+        };
+        if number.is_empty() {
+            anyhow::bail!("Invalid duration '{}' (use e.g. 1h20m, 45m, 30s)", input);
+        }
+        total = number.parse::<u64>().ok()
+            .and_then(|value| value.checked_mul(unit))
+            .and_then(|seconds| total.checked_add(seconds))
+            .filter(|s| *s <= i64::MAX as u64)
+            .ok_or_else(too_long)?;
+        number.clear();
+    }
+    if !number.is_empty() {
+        anyhow::bail!("Invalid duration '{}': missing unit after {}", input, number);
+    }
+    Ok(total)
+}
+
+/// Formats seconds as `1h 20m` (or `45s` below a minute).
+pub fn format_duration(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let seconds = seconds.unsigned_abs();
+    let h = seconds / 3600;
+    let m = (seconds % 3600) / 60;
+    if h > 0 {
+        format!("{}{}h {}m", sign, h, m)
+    } else if m > 0 {
+        format!("{}{}m", sign, m)
+    } else {
+        format!("{}{}s", sign, seconds)
+    }
+}
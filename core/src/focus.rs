@@ -0,0 +1,71 @@
+use crate::config::{FocusAction, FocusSchedule};
+use crate::db::AppInfo;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Weekday};
+
+// Focus mode rules that don't depend on the window system: which apps are
+// blocked and when the schedule says focus mode should be on.
+
+impl FocusAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FocusAction::Notify => "notify",
+            FocusAction::Minimize => "minimize",
+            FocusAction::Workspace => "workspace",
+            FocusAction::Kill => "kill",
+        }
+    }
+}
+
+/// True when the app's id, display name or category is on the blocklist
+pub fn is_blocked(block: &[String], app_id: &str, app: Option<&AppInfo>) -> bool {
+    block.iter().any(|b| {
+        b.eq_ignore_ascii_case(app_id)
+            || app.is_some_and(|a| {
+                b.eq_ignore_ascii_case(&a.display_name)
+                    || a.category.as_deref().is_some_and(|c| b.eq_ignore_ascii_case(c))
+            })
+    })
+}
+
+/// Warns about schedule entries that will never match
+pub fn check_schedules(schedules: &[FocusSchedule]) {
+    for s in schedules {
+        let times_ok = [&s.start, &s.end].iter().all(|t| NaiveTime::parse_from_str(t, "%H:%M").is_ok());
+        if !times_ok {
+            eprintln!("Warning: [[focus.schedule]] times must be HH:MM, got '{}'-'{}'", s.start, s.end);
+        }
+        for day in s.days.iter().filter(|d| d.parse::<Weekday>().is_err()) {
+            eprintln!("Warning: [[focus.schedule]] unknown day '{}'", day);
+        }
+    }
+}
+
+/// If a scheduled window covers `now`, the unix time it ends
+pub fn scheduled_until(schedules: &[FocusSchedule], now: DateTime<Local>) -> Option<i64> {
+    schedules.iter().filter_map(|s| window_end(s, now)).max()
+}
+
+fn window_end(schedule: &FocusSchedule, now: DateTime<Local>) -> Option<i64> {
+    let start = NaiveTime::parse_from_str(&schedule.start, "%H:%M").ok()?;
+    let end = NaiveTime::parse_from_str(&schedule.end, "%H:%M").ok()?;
+    let days: Vec<Weekday> = schedule.days.iter().filter_map(|d| d.parse().ok()).collect();
+    let runs_on = |date: chrono::NaiveDate| schedule.days.is_empty() || days.contains(&date.weekday());
+
+    let today = now.date_naive();
+    let time = now.time();
+    // Windows past midnight belong to the day they started on
+    let (day, ends_on) = if end > start {
+        if time < start || time >= end { return None; }
+        (today, today)
+    } else if time >= start {
+        (today, today + Duration::days(1))
+    } else if time < end {
+        (today - Duration::days(1), today)
+    } else {
+        return None;
+    };
+    if !runs_on(day) {
+        return None;
+    }
+    Local.from_local_datetime(&ends_on.and_time(end)).earliest().map(|t| t.timestamp())
+}
@@ -0,0 +1,178 @@
+use crate::config::{GoalRule, LimitPeriod};
+use crate::db::{Db, UsageFilter};
+use crate::duration::parse_duration;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+// [[goal]] targets and their streaks.
+//
+// Nothing is tracked by the daemon: progress and streaks are worked out from
+// the recorded history whenever they're asked for. Streaks start at the first
+// day anything was recorded, so a "max" goal doesn't get credit for the time
+// before focusd was installed. Goals that need sessions (projects, `--tz`)
+// start where the oldest kept session is, for the same reason.
+
+/// Whether a goal asks for at least or at most its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalKind {
+    Min,
+    Max,
+}
+
+pub struct Goal {
+    rule: GoalRule,
+    kind: GoalKind,
+    target: i64,
+    // Empty means every day
+    days: Vec<Weekday>,
+}
+
+/// Where a goal stands, for `focusd goals`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GoalStatus {
+    pub name: String,
+    pub kind: GoalKind,
+    pub period: LimitPeriod,
+    pub target: i64,
+    /// Time so far today or this week
+    pub used: i64,
+    /// False on days a daily goal doesn't apply
+    pub scheduled: bool,
+    /// Days or weeks in a row the goal was met. The current one only counts
+    /// once it's met for good: a min reached, or a max period that's over.
+    pub current_streak: u32,
+    pub longest_streak: u32,
+}
+
+impl GoalStatus {
+    /// Whether `used` meets the goal as things stand
+    pub fn met(&self) -> bool {
+        met(self.kind, self.target, self.used)
+    }
+}
+
+fn met(kind: GoalKind, target: i64, used: i64) -> bool {
+    match kind {
+        GoalKind::Min => used >= target,
+        GoalKind::Max => used <= target,
+    }
+}
+
+impl Goal {
+    /// Rules without exactly one valid min or max are skipped with a warning,
+    /// unknown days are ignored with one
+    pub fn from_rules(rules: &[GoalRule]) -> Vec<Goal> {
+        rules.iter().filter_map(|rule| {
+            let (kind, amount) = match (&rule.min, &rule.max) {
+                (Some(min), None) => (GoalKind::Min, min),
+                (None, Some(max)) => (GoalKind::Max, max),
+                _ => {
+                    eprintln!("Warning: [[goal]] '{}' needs either min or max", name(rule));
+                    return None;
+                }
+            };
+            let target = match parse_duration(amount) {
+                Ok(seconds) => seconds as i64,
+                Err(_) => {
+                    eprintln!("Warning: invalid [[goal]] target '{}'", amount);
+                    return None;
+                }
+            };
+            let days = rule.days.iter().filter_map(|d| match d.parse::<Weekday>() {
+                Ok(day) => Some(day),
+                Err(_) => {
+                    eprintln!("Warning: [[goal]] unknown day '{}'", d);
+                    None
+                }
+            }).collect();
+            Some(Goal { rule: rule.clone(), kind, target, days })
+        }).collect()
+    }
+
+    pub fn period(&self) -> LimitPeriod {
+        self.rule.period
+    }
+
+    /// For a daily goal: (days met, days it applied on) in a range
+    pub fn days_met(&self, db: &Db, start: NaiveDate, end: NaiveDate) -> anyhow::Result<(usize, usize)> {
+        let usage = db.get_daily_usage_for(&self.filter(), start, end)?;
+        let days: Vec<NaiveDate> = start.iter_days().take_while(|d| *d <= end).filter(|d| self.applies_on(*d)).collect();
+        let met_on = days.iter().filter(|d| met(self.kind, self.target, usage.get(d).copied().unwrap_or(0))).count();
+        Ok((met_on, days.len()))
+    }
+
+    fn filter(&self) -> UsageFilter {
+        UsageFilter {
+            app: self.rule.app.clone(),
+            category: self.rule.category.clone(),
+            project: self.rule.project.clone(),
+        }
+    }
+
+    fn applies_on(&self, date: NaiveDate) -> bool {
+        self.rule.period == LimitPeriod::Week || self.days.is_empty() || self.days.contains(&date.weekday())
+    }
+
+    pub fn status(&self, db: &Db) -> anyhow::Result<GoalStatus> {
+        self.status_at(db, db.today())
+    }
+
+    /// The goal as it stood at the end of `today`, which may be in the past
+    pub fn status_at(&self, db: &Db, today: NaiveDate) -> anyhow::Result<GoalStatus> {
+        let first = db.get_first_day_for(&self.filter())?.unwrap_or(today).min(today);
+        let usage = db.get_daily_usage_for(&self.filter(), first, today)?;
+        let day_total = |date: NaiveDate| usage.get(&date).copied().unwrap_or(0);
+
+        // Oldest first; the last one is the period in progress
+        let periods: Vec<i64> = match self.rule.period {
+            LimitPeriod::Day => first.iter_days()
+                .take_while(|d| *d <= today)
+                .filter(|d| *d == today || self.applies_on(*d))
+                .map(day_total)
+                .collect(),
+            LimitPeriod::Week => {
+                let monday = |d: NaiveDate| d - Duration::days(d.weekday().num_days_from_monday() as i64);
+                monday(first).iter_days()
+                    .step_by(7)
+                    .take_while(|w| *w <= today)
+                    .map(|w| w.iter_days().take(7).map(day_total).sum())
+                    .collect()
+            }
+        };
+        let (used, past) = periods.split_last().map_or((0, &[][..]), |(u, p)| (*u, p));
+
+        let (mut current, mut longest) = (0, 0);
+        for &seconds in past {
+            current = if met(self.kind, self.target, seconds) { current + 1 } else { 0 };
+            longest = longest.max(current);
+        }
+        let scheduled = self.applies_on(today);
+        if scheduled && self.kind == GoalKind::Min && used >= self.target {
+            current += 1;
+            longest = longest.max(current);
+        }
+
+        Ok(GoalStatus {
+            name: name(&self.rule),
+            kind: self.kind,
+            period: self.rule.period,
+            target: self.target,
+            used,
+            scheduled,
+            current_streak: current,
+            longest_streak: longest,
+        })
+    }
+}
+
+fn name(rule: &GoalRule) -> String {
+    if let Some(name) = &rule.name {
+        return name.clone();
+    }
+    let parts: Vec<&str> = [&rule.app, &rule.category, &rule.project].into_iter().flatten().map(String::as_str).collect();
+    if parts.is_empty() {
+        "All apps".to_string()
+    } else {
+        parts.join(" / ")
+    }
+}
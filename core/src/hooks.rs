@@ -0,0 +1,133 @@
+use crate::config::{HookEvent, HookRule};
+use crate::duration::parse_duration;
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Runs [[hook]] commands. Every hook gets its own worker thread, so the daemon
+// only hands events over and never waits for a script. Event data is passed as
+// FOCUSD_* environment variables and as a JSON object on stdin.
+
+/// Events waiting for a busy hook; more than this are dropped
+const QUEUE: usize = 16;
+
+struct Hook {
+    event: HookEvent,
+    command: String,
+    sender: SyncSender<Map<String, Value>>,
+}
+
+pub struct HookRunner {
+    hooks: Vec<Hook>,
+}
+
+impl HookRunner {
+    /// Starts a worker per hook. A timeout that doesn't parse falls back to 10s.
+    pub fn new(rules: &[HookRule]) -> Self {
+        let hooks = rules.iter().map(|rule| {
+            let timeout = parse_duration(&rule.timeout).unwrap_or_else(|_| {
+                eprintln!("Warning: invalid [[hook]] timeout '{}', using 10s", rule.timeout);
+                10
+            });
+            let debounce = rule.debounce.as_deref().and_then(|d| match parse_duration(d) {
+                Ok(seconds) => Some(seconds),
+                Err(_) => {
+                    eprintln!("Warning: invalid [[hook]] debounce '{}'", d);
+                    None
+                }
+            });
+
+            let (sender, receiver) = mpsc::sync_channel(QUEUE);
+            let command = rule.command.clone();
+            thread::spawn(move || {
+                while let Ok(mut data) = receiver.recv() {
+                    // Wait for the events to settle and only run for the last one
+                    if let Some(debounce) = debounce.filter(|&d| d > 0) {
+                        loop {
+                            match receiver.recv_timeout(Duration::from_secs(debounce)) {
+                                Ok(newer) => data = newer,
+                                Err(RecvTimeoutError::Timeout) => break,
+                                Err(RecvTimeoutError::Disconnected) => return,
+                            }
+                        }
+                    }
+                    if let Err(e) = run(&command, &data, Duration::from_secs(timeout)) {
+                        eprintln!("Hook '{}' failed: {}", command, e);
+                    }
+                }
+            });
+            Hook { event: rule.event, command: rule.command.clone(), sender }
+        }).collect();
+
+        HookRunner { hooks }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Hands `data` (a JSON object) to every hook for `event` without waiting
+    pub fn fire(&self, event: HookEvent, data: Value) {
+        let mut object = match data {
+            Value::Object(object) => object,
+            _ => Map::new(),
+        };
+        object.insert("event".to_string(), serde_json::json!(event));
+        object.insert("at".to_string(), chrono::Utc::now().timestamp().into());
+
+        for hook in self.hooks.iter().filter(|h| h.event == event) {
+            match hook.sender.try_send(object.clone()) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => eprintln!("Warning: hook '{}' is falling behind, event dropped", hook.command),
+                Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+    }
+}
+
+fn run(command: &str, data: &Map<String, Value>, timeout: Duration) -> anyhow::Result<()> {
+    let mut cmd = Command::new("sh");
+    // Its own process group, so a timeout takes down whatever the script started
+    cmd.arg("-c").arg(command).stdin(Stdio::piped()).process_group(0);
+    for (key, value) in data {
+        let value = match value {
+            Value::Null => continue,
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        cmd.env(format!("FOCUSD_{}", key.to_uppercase()), value);
+    }
+
+    let mut child = cmd.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores stdin closes it early; that's fine
+        let _ = writeln!(stdin, "{}", Value::Object(data.clone()));
+    }
+    wait(&mut child, timeout)
+}
+
+fn wait(child: &mut Child, timeout: Duration) -> anyhow::Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            if !status.success() {
+                anyhow::bail!("exited with {}", status);
+            }
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            let group = format!("-{}", child.id());
+            let killed = Command::new("kill").args(["-KILL", "--", &group]).status().is_ok_and(|s| s.success());
+            if !killed {
+                child.kill()?;
+            }
+            child.wait()?;
+            anyhow::bail!("killed after {}s", timeout.as_secs());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::limits::LimitLevel;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, DirBuilder};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Control socket of the daemon: one JSON request per line, one JSON response
+// line back. Used by the browser host and other local helpers.
+
+/// `$XDG_RUNTIME_DIR/focusd.sock`, falling back to a private directory in
+/// the temp dir
+pub fn socket_path() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR").filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("focusd.sock"),
+        None => fallback_dir().join("focusd.sock"),
+    }
+}
+
+fn fallback_dir() -> PathBuf {
+    let user = env::var("USER").unwrap_or_else(|_| "user".to_string());
+    env::temp_dir().join(format!("focusd-{}", user))
+}
+
+/// Creates the directory `socket_path()` lives in when there's no
+/// XDG_RUNTIME_DIR. Anyone who can enter it can drive the daemon, so one
+/// that belongs to another user or is open to others is refused.
+pub fn create_socket_dir() -> anyhow::Result<()> {
+    if env::var_os("XDG_RUNTIME_DIR").is_some_and(|d| !d.is_empty()) {
+        return Ok(());
+    }
+    let dir = fallback_dir();
+    match DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e.into()),
+    }
+    let meta = fs::symlink_metadata(&dir)?;
+    // /proc/self belongs to whoever we run as
+    let uid = fs::metadata("/proc/self")?.uid();
+    if !meta.is_dir() || meta.uid() != uid || meta.mode() & 0o077 != 0 {
+        anyhow::bail!("{} must be a directory only this user can access", dir.display());
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    /// The site of the browser's active tab changed. `None` when it must not
+    /// be recorded (private window, excluded site, internal page).
+    BrowserTab {
+        #[serde(default)]
+        browser: Option<String>,
+        domain: Option<String>,
+    },
+    /// Sent by the shell hook before a command runs (`command` set) and when
+    /// the prompt returns (`command` empty).
+    ShellState {
+        pid: u32,
+        cwd: String,
+        #[serde(default)]
+        command: Option<String>,
+    },
+    /// Starts focus mode for `seconds`, replacing a running focus session
+    FocusStart { seconds: u64 },
+    FocusStop,
+    /// Answers with a `FocusStatus` in `data`
+    FocusStatus,
+    /// Starts a Pomodoro cycle; lengths in seconds override config.toml
+    PomodoroStart {
+        #[serde(default)]
+        work: Option<u64>,
+        #[serde(default)]
+        short_break: Option<u64>,
+    },
+    PomodoroStop,
+    /// Answers with a `PomodoroStatus` in `data`
+    PomodoroStatus,
+    /// Postpones the break reminders currently showing (or only `name`)
+    BreakSnooze {
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        seconds: Option<u64>,
+    },
+    /// Answers with a list of `BreakStatus` in `data`
+    BreakStatus,
+    /// Keeps the connection open and answers with a `BarStatus` after every
+    /// daemon tick
+    BarSubscribe,
+}
+
+/// What a status bar shows, pushed by the daemon
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BarStatus {
+    /// Display name of the focused app; none while idle
+    #[serde(default)]
+    pub app: Option<String>,
+    /// Seconds tracked today
+    pub today: i64,
+    /// Today's most used apps by display name
+    #[serde(default)]
+    pub top: Vec<(String, i64)>,
+    /// The [[limit]] closest to (or furthest past) its budget
+    #[serde(default)]
+    pub limit: LimitLevel,
+    #[serde(default)]
+    pub limit_percent: u8,
+    #[serde(default)]
+    pub idle: bool,
+    #[serde(default)]
+    pub focus: bool,
+}
+
+/// Where a break reminder stands
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BreakStatus {
+    pub name: String,
+    /// Active seconds since the last break
+    pub active: u64,
+    /// Active seconds until the next reminder
+    pub due_in: u64,
+    /// Reminded, but the break hasn't been taken yet
+    #[serde(default)]
+    pub pending: bool,
+}
+
+/// The Pomodoro timer as the daemon sees it
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PomodoroStatus {
+    pub active: bool,
+    /// `work`, `short_break` or `long_break`
+    #[serde(default)]
+    pub phase: Option<String>,
+    #[serde(default)]
+    pub phase_ends: Option<i64>,
+    /// Work blocks finished in this cycle
+    #[serde(default)]
+    pub completed: u32,
+}
+
+/// Focus mode as the daemon sees it
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FocusStatus {
+    pub active: bool,
+    /// `manual` or `schedule`
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub started_at: Option<i64>,
+    #[serde(default)]
+    pub until: Option<i64>,
+    #[serde(default)]
+    pub violations: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl Response {
+    pub fn ok() -> Self {
+        Response { ok: true, ..Default::default() }
+    }
+
+    pub fn with_data(data: serde_json::Value) -> Self {
+        Response { ok: true, data: Some(data), ..Default::default() }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Response { ok: false, error: Some(message.into()), ..Default::default() }
+    }
+}
+
+/// Sends one request to the running daemon and waits for its answer
+pub fn send(request: &Request) -> anyhow::Result<Response> {
+    let path = socket_path();
+    let stream = UnixStream::connect(&path)
+        .map_err(|e| anyhow::anyhow!("daemon not reachable at {}: {}", path.display(), e))?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    (&stream).write_all(line.as_bytes())?;
+
+    let mut answer = String::new();
+    BufReader::new(&stream).read_line(&mut answer)?;
+    if answer.trim().is_empty() {
+        anyhow::bail!("daemon closed the connection without answering");
+    }
+    Ok(serde_json::from_str(&answer)?)
+}
+
+/// Sends a streaming request and yields every response line until the daemon
+/// goes away
+pub fn subscribe(request: &Request) -> anyhow::Result<impl Iterator<Item = anyhow::Result<Response>>> {
+    let path = socket_path();
+    let stream = UnixStream::connect(&path)
+        .map_err(|e| anyhow::anyhow!("daemon not reachable at {}: {}", path.display(), e))?;
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    (&stream).write_all(line.as_bytes())?;
+
+    Ok(BufReader::new(stream).lines().map(|line| Ok(serde_json::from_str(&line?)?)))
+}
@@ -1,2 +1,19 @@
 pub mod db;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod sync;
+pub mod duration;
+pub mod desktop;
+pub mod process;
+pub mod project;
+pub mod browser;
+pub mod ipc;
+pub mod limits;
+pub mod notify;
+pub mod focus;
+pub mod breaks;
+pub mod pomodoro;
+pub mod hooks;
+pub mod timezone;
+pub mod goals;
+pub mod compare;
+pub mod timeline;
@@ -0,0 +1,223 @@
+use crate::config::{LimitPeriod, LimitRule};
+use crate::db::Db;
+use crate::duration::{format_duration, parse_duration};
+use chrono::{Datelike, NaiveDate};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+// Keeps running totals for the [[limit]] budgets.
+//
+// Totals are read from the database once a minute; in between, the seconds the
+// daemon logs are added in memory so a budget is noticed within one tick.
+
+/// How often totals are re-read from the database
+const REFRESH: Duration = Duration::from_secs(60);
+
+struct Limit {
+    rule: LimitRule,
+    budget: i64,
+    used: i64,
+}
+
+/// A threshold that has just been crossed
+#[derive(Debug, Clone)]
+pub struct Alert {
+    /// What the limit is on: the app or category as written in config.toml
+    pub target: String,
+    pub period: LimitPeriod,
+    /// The threshold crossed, in percent of the budget (`warn_at` or 100)
+    pub percent: u8,
+    pub used: i64,
+    pub budget: i64,
+}
+
+impl Alert {
+    pub fn summary(&self) -> String {
+        let period = match self.period {
+            LimitPeriod::Day => "daily",
+            LimitPeriod::Week => "weekly",
+        };
+        if self.percent >= 100 {
+            format!("{}: {} limit reached", self.target, period)
+        } else {
+            format!("{}: {}% of the {} limit used", self.target, self.percent, period)
+        }
+    }
+
+    pub fn body(&self) -> String {
+        let period = match self.period {
+            LimitPeriod::Day => "today",
+            LimitPeriod::Week => "this week",
+        };
+        format!("{} of {} {}", format_duration(self.used), format_duration(self.budget), period)
+    }
+}
+
+/// How close the limits are to their budgets, for status bars
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitLevel {
+    #[default]
+    Ok,
+    /// Past `warn_at`
+    Warning,
+    Reached,
+}
+
+/// Where a limit stands right now, for `focusd limits`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LimitStatus {
+    pub target: String,
+    pub period: LimitPeriod,
+    pub used: i64,
+    pub budget: i64,
+}
+
+pub struct LimitTracker {
+    limits: Vec<Limit>,
+    // app id -> (display name, category) for matching the in-flight app
+    apps: HashMap<String, (String, Option<String>)>,
+    refreshed: Option<(Instant, NaiveDate)>,
+    // (limit index, start of its period, threshold) already notified
+    notified: HashSet<(usize, NaiveDate, u8)>,
+}
+
+impl LimitTracker {
+    /// Rules with a budget that doesn't parse or no target are skipped with a warning
+    pub fn new(rules: &[LimitRule]) -> Self {
+        let limits = rules.iter().filter_map(|rule| {
+            if rule.app.is_none() && rule.category.is_none() {
+                eprintln!("Warning: [[limit]] with budget '{}' needs an app or a category", rule.budget);
+                return None;
+            }
+            match parse_duration(&rule.budget) {
+                Ok(budget) if budget > 0 => Some(Limit { rule: rule.clone(), budget: budget as i64, used: 0 }),
+                _ => {
+                    eprintln!("Warning: invalid [[limit]] budget '{}'", rule.budget);
+                    None
+                }
+            }
+        }).collect();
+
+        LimitTracker { limits, apps: HashMap::new(), refreshed: None, notified: HashSet::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    /// Adds `seconds` just logged for `app_id` to the limits it falls under and
+    /// returns the thresholds crossed since the last call. Call after logging.
+    pub fn record(&mut self, db: &Db, app_id: &str, seconds: u64) -> anyhow::Result<Vec<Alert>> {
+        let today = db.today();
+        if self.is_stale(today) {
+            // The database already holds what was just logged
+            self.refresh(db, today)?;
+        } else {
+            let app = self.apps.get(app_id).cloned();
+            for limit in &mut self.limits {
+                if matches(&limit.rule, app_id, app.as_ref()) {
+                    limit.used += seconds as i64;
+                }
+            }
+        }
+
+        let mut alerts = Vec::new();
+        for (i, limit) in self.limits.iter().enumerate() {
+            let start = period_start(limit.rule.period, today);
+            // Only the most severe threshold crossed is worth a notification
+            let mut crossed_now = None;
+            for percent in [limit.rule.warn_at.min(100), 100] {
+                let crossed = limit.used * 100 >= limit.budget * percent as i64;
+                if crossed && self.notified.insert((i, start, percent)) {
+                    crossed_now = Some(percent);
+                }
+            }
+            if let Some(percent) = crossed_now {
+                alerts.push(Alert {
+                    target: target(&limit.rule),
+                    period: limit.rule.period,
+                    percent,
+                    used: limit.used,
+                    budget: limit.budget,
+                });
+            }
+        }
+        Ok(alerts)
+    }
+
+    /// The most severe level across all limits and the highest percentage
+    /// of a budget used. Only reads the database when the totals are stale.
+    pub fn level(&mut self, db: &Db) -> anyhow::Result<(LimitLevel, u8)> {
+        let today = db.today();
+        if self.is_stale(today) {
+            self.refresh(db, today)?;
+        }
+        Ok(self.limits.iter().map(|l| {
+            let percent = l.used * 100 / l.budget;
+            let level = if percent >= 100 {
+                LimitLevel::Reached
+            } else if percent >= l.rule.warn_at as i64 {
+                LimitLevel::Warning
+            } else {
+                LimitLevel::Ok
+            };
+            (level, percent.min(100) as u8)
+        }).max().unwrap_or_default())
+    }
+
+    /// Current totals without touching the notification state
+    pub fn status(&mut self, db: &Db) -> anyhow::Result<Vec<LimitStatus>> {
+        self.refresh(db, db.today())?;
+        Ok(self.limits.iter().map(|l| LimitStatus {
+            target: target(&l.rule),
+            period: l.rule.period,
+            used: l.used,
+            budget: l.budget,
+        }).collect())
+    }
+
+    fn is_stale(&self, today: NaiveDate) -> bool {
+        self.refreshed.is_none_or(|(at, day)| at.elapsed() >= REFRESH || day != today)
+    }
+
+    fn refresh(&mut self, db: &Db, today: NaiveDate) -> anyhow::Result<()> {
+        for limit in &mut self.limits {
+            let start = period_start(limit.rule.period, today);
+            limit.used = db.get_limit_usage(limit.rule.app.as_deref(), limit.rule.category.as_deref(), start, today)?;
+        }
+        self.apps = db.list_apps()?
+            .into_iter()
+            .map(|a| (a.app_id, (a.display_name, a.category)))
+            .collect();
+        self.refreshed = Some((Instant::now(), today));
+        Ok(())
+    }
+}
+
+fn matches(rule: &LimitRule, app_id: &str, app: Option<&(String, Option<String>)>) -> bool {
+    let app_ok = rule.app.as_deref().is_none_or(|want| {
+        want.eq_ignore_ascii_case(app_id) || app.is_some_and(|(name, _)| want.eq_ignore_ascii_case(name))
+    });
+    let category_ok = rule.category.as_deref().is_none_or(|want| {
+        app.and_then(|(_, c)| c.as_deref()).is_some_and(|c| want.eq_ignore_ascii_case(c))
+    });
+    app_ok && category_ok
+}
+
+fn target(rule: &LimitRule) -> String {
+    match (&rule.app, &rule.category) {
+        (Some(app), Some(category)) => format!("{} ({})", app, category),
+        (Some(app), None) => app.clone(),
+        (None, Some(category)) => category.clone(),
+        (None, None) => String::new(),
+    }
+}
+
+/// First day of the period containing `today`; weeks start on Monday
+fn period_start(period: LimitPeriod, today: NaiveDate) -> NaiveDate {
+    match period {
+        LimitPeriod::Day => today,
+        LimitPeriod::Week => today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64),
+    }
+}
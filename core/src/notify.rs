@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use zbus::blocking::Connection;
+use zbus::zvariant::Value;
+
+// Desktop notifications through org.freedesktop.Notifications on the session
+// bus. Any connection works, so tests can talk to a stand-in server instead.
+
+const DESTINATION: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Low = 0,
+    Normal = 1,
+    Critical = 2,
+}
+
+pub struct Notifier {
+    conn: Connection,
+}
+
+impl Notifier {
+    /// Connects to the session bus from `DBUS_SESSION_BUS_ADDRESS`
+    pub fn session() -> anyhow::Result<Self> {
+        Ok(Notifier { conn: Connection::session()? })
+    }
+
+    pub fn with_connection(conn: Connection) -> Self {
+        Notifier { conn }
+    }
+
+    /// Shows a notification and returns the id the server gave it
+    pub fn notify(&self, summary: &str, body: &str, urgency: Urgency) -> anyhow::Result<u32> {
+        let mut hints: HashMap<&str, Value> = HashMap::new();
+        hints.insert("urgency", Value::U8(urgency as u8));
+        hints.insert("desktop-entry", Value::from("focusd"));
+
+        let reply = self.conn.call_method(
+            Some(DESTINATION),
+            PATH,
+            Some(DESTINATION),
+            "Notify",
+            &("focusd", 0u32, "", summary, body, Vec::<&str>::new(), hints, -1i32),
+        )?;
+        Ok(reply.body().deserialize()?)
+    }
+}
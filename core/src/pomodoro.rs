@@ -0,0 +1,205 @@
+use crate::duration::{format_duration, parse_duration};
+use crate::ipc::PomodoroStatus;
+use crate::{config, db};
+use chrono::Utc;
+
+// The Pomodoro timer the daemon runs, and the recorder that mirrors its work
+// blocks into the database.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Work => "work",
+            Phase::ShortBreak => "short_break",
+            Phase::LongBreak => "long_break",
+        }
+    }
+}
+
+/// Phase lengths in seconds
+#[derive(Clone, Copy)]
+struct Lengths {
+    work: u64,
+    short_break: u64,
+    long_break: u64,
+    long_break_every: u32,
+}
+
+/// A Pomodoro cycle in progress
+struct Cycle {
+    lengths: Lengths,
+    phase: Phase,
+    phase_started: i64,
+    phase_ends: i64,
+    // Work blocks finished so far
+    completed: u32,
+    // Identifies the work block in progress, unique across cycles
+    block: u64,
+}
+
+/// A phase that has just begun, for the notification
+pub struct PhaseChange {
+    pub phase: Phase,
+    pub length: u64,
+    pub completed: u32,
+}
+
+impl PhaseChange {
+    pub fn summary(&self) -> String {
+        match self.phase {
+            Phase::Work => "Back to work".to_string(),
+            Phase::ShortBreak => "Time for a short break".to_string(),
+            Phase::LongBreak => "Time for a long break".to_string(),
+        }
+    }
+
+    pub fn body(&self) -> String {
+        format!("{} {}, {} Pomodoro(s) done", format_duration(self.length as i64), self.phase.as_str().replace('_', " "), self.completed)
+    }
+}
+
+/// The Pomodoro timer. Lives in the daemon's shared state so the control
+/// socket can start and stop it.
+pub struct Pomodoro {
+    defaults: Lengths,
+    cycle: Option<Cycle>,
+    // Work blocks begun so far, for `Cycle::block`
+    blocks: u64,
+}
+
+impl Pomodoro {
+    /// Lengths that don't parse fall back to the built-in ones with a warning
+    pub fn new(config: &config::Pomodoro) -> Self {
+        let fallback = config::Pomodoro::default();
+        let length = |name: &str, value: &str, default: &str| match parse_duration(value) {
+            Ok(seconds) if seconds > 0 => seconds,
+            _ => {
+                eprintln!("Warning: invalid [pomodoro] {} '{}', using {}", name, value, default);
+                parse_duration(default).unwrap_or(60)
+            }
+        };
+        let defaults = Lengths {
+            work: length("work", &config.work, &fallback.work),
+            short_break: length("short_break", &config.short_break, &fallback.short_break),
+            long_break: length("long_break", &config.long_break, &fallback.long_break),
+            long_break_every: config.long_break_every.max(1),
+        };
+        Pomodoro { defaults, cycle: None, blocks: 0 }
+    }
+
+    /// Starts a new cycle with a work block; restarts one already running
+    pub fn start(&mut self, work: Option<u64>, short_break: Option<u64>) -> PomodoroStatus {
+        let mut lengths = self.defaults;
+        lengths.work = work.filter(|&s| s > 0).unwrap_or(lengths.work);
+        lengths.short_break = short_break.filter(|&s| s > 0).unwrap_or(lengths.short_break);
+
+        let now = Utc::now().timestamp();
+        self.blocks += 1;
+        self.cycle = Some(Cycle {
+            lengths,
+            phase: Phase::Work,
+            phase_started: now,
+            phase_ends: now + lengths.work as i64,
+            completed: 0,
+            block: self.blocks,
+        });
+        self.status()
+    }
+
+    pub fn stop(&mut self) -> anyhow::Result<()> {
+        self.cycle.take().ok_or_else(|| anyhow::anyhow!("no Pomodoro is running"))?;
+        Ok(())
+    }
+
+    pub fn status(&self) -> PomodoroStatus {
+        match &self.cycle {
+            Some(c) => PomodoroStatus {
+                active: true,
+                phase: Some(c.phase.as_str().to_string()),
+                phase_ends: Some(c.phase_ends),
+                completed: c.completed,
+            },
+            None => PomodoroStatus::default(),
+        }
+    }
+
+    /// Moves on to the next phase once the current one is over. After a
+    /// suspend the next phase starts now rather than catching up.
+    pub fn advance(&mut self, now: i64) -> Option<PhaseChange> {
+        let cycle = self.cycle.as_mut().filter(|c| c.phase_ends <= now)?;
+        let (phase, length) = match cycle.phase {
+            Phase::Work => {
+                cycle.completed += 1;
+                if cycle.completed % cycle.lengths.long_break_every == 0 {
+                    (Phase::LongBreak, cycle.lengths.long_break)
+                } else {
+                    (Phase::ShortBreak, cycle.lengths.short_break)
+                }
+            }
+            Phase::ShortBreak | Phase::LongBreak => {
+                self.blocks += 1;
+                cycle.block = self.blocks;
+                (Phase::Work, cycle.lengths.work)
+            }
+        };
+        cycle.phase = phase;
+        cycle.phase_started = now;
+        cycle.phase_ends = now + length as i64;
+        Some(PhaseChange { phase, length, completed: cycle.completed })
+    }
+
+    /// (block, started, planned end) of the work block in progress
+    fn work_block(&self) -> Option<(u64, i64, i64)> {
+        self.cycle.as_ref()
+            .filter(|c| c.phase == Phase::Work)
+            .map(|c| (c.block, c.phase_started, c.phase_ends))
+    }
+}
+
+/// The daemon-side half of the timer: records work blocks and the apps used
+/// during them.
+#[derive(Default)]
+pub struct Recorder {
+    // (row id, block, planned_end) of the work block being recorded. Blocks
+    // are told apart by id: a restart can begin in the same second.
+    recorded: Option<(i64, u64, i64)>,
+}
+
+impl Recorder {
+    /// Advances `timer` and brings the database in line with it. Call once
+    /// per tick; returns the phase that just began, if any.
+    pub fn sync(&mut self, db: &db::Db, timer: &mut Pomodoro) -> anyhow::Result<Option<PhaseChange>> {
+        let now = Utc::now().timestamp();
+        let change = timer.advance(now);
+        let current = timer.work_block();
+
+        if let Some((id, block, planned_end)) = self.recorded {
+            if current.map(|c| c.0) != Some(block) {
+                // A block that ran out is complete; anything else was stopped
+                let completed = planned_end <= now;
+                db.end_pomodoro(id, if completed { planned_end } else { now }, completed)?;
+                self.recorded = None;
+            }
+        }
+        if let (None, Some((block, started, planned_end))) = (self.recorded, current) {
+            let id = db.start_pomodoro(started, planned_end)?;
+            self.recorded = Some((id, block, planned_end));
+        }
+        Ok(change)
+    }
+
+    /// Adds time just logged for `app_id` to the running work block
+    pub fn record(&self, db: &db::Db, app_id: &str, seconds: u64) -> anyhow::Result<()> {
+        match self.recorded {
+            Some((id, ..)) => db.add_pomodoro_time(id, app_id, seconds),
+            None => Ok(()),
+        }
+    }
+}
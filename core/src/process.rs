@@ -0,0 +1,168 @@
+use crate::project::is_terminal;
+use std::fs;
+use std::path::Path;
+
+/// What /proc tells us about the process that owns the focused window
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// Target of `/proc/<pid>/exe`
+    pub exe: Option<String>,
+    pub cmdline: Vec<String>,
+    /// Last path component of the unified cgroup, e.g.
+    /// `app-flatpak-com.spotify.Client-12345.scope`
+    pub cgroup: Option<String>,
+}
+
+/// Window classes shared by many unrelated programs
+const GENERIC_CLASSES: [&str; 14] = [
+    "java", "python", "python3", "electron", "node", "wine", "wine64", "mono",
+    "ruby", "perl", "sun-awt-x11-xframepeer", "jetbrains-toolbox", "qemu", "love",
+];
+
+/// Interpreters whose first non-option argument names the real program
+const INTERPRETERS: [&str; 8] = ["java", "python", "python3", "node", "electron", "ruby", "perl", "mono"];
+
+/// Interpreter options whose value is the next argument rather than the program
+const TAKE_VALUE: [&str; 8] = ["-cp", "-classpath", "--class-path", "--module-path", "-X", "-W", "-r", "--require"];
+
+/// Options that run code given inline, which has no name
+const INLINE: [&str; 3] = ["-c", "-e", "--eval"];
+
+impl ProcessInfo {
+    pub fn read(pid: u32) -> Option<Self> {
+        let base = Path::new("/proc").join(pid.to_string());
+        if !base.exists() {
+            return None;
+        }
+
+        let exe = fs::read_link(base.join("exe")).ok().map(|p| p.to_string_lossy().to_string());
+        let cmdline = fs::read(base.join("cmdline"))
+            .map(|raw| {
+                raw.split(|b| *b == 0)
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| String::from_utf8_lossy(arg).to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let cgroup = fs::read_to_string(base.join("cgroup"))
+            .ok()
+            .and_then(|c| parse_cgroup(&c));
+
+        Some(ProcessInfo { pid, exe, cmdline, cgroup })
+    }
+
+    /// The application id systemd or flatpak put in the scope name, if any.
+    /// Follows the `app[-<launcher>]-<ApplicationID>[@<RANDOM>].service` and
+    /// `app[-<launcher>]-<ApplicationID>-<RANDOM>.scope` conventions.
+    pub fn cgroup_app_id(&self) -> Option<String> {
+        let unit = self.cgroup.as_deref()?;
+        let (name, kind) = unit.rsplit_once('.')?;
+        let name = name.strip_prefix("app-")?;
+        let name = match kind {
+            "service" => name.split('@').next()?,
+            "scope" => name.rsplit_once('-').map(|(n, _)| n)?,
+            _ => return None,
+        };
+        // Dashes inside the application id are escaped, so any left split off the launcher
+        let id = name.rsplit('-').next()?.replace("\\x2d", "-");
+        (!id.is_empty()).then_some(id)
+    }
+
+    /// A name for the program an interpreter runs: `java -jar Foo.jar` gives
+    /// `Foo`, `python3 /usr/bin/meld` gives `meld`, `python3 -m pip` gives `pip`.
+    pub fn script_name(&self) -> Option<String> {
+        let program = Path::new(self.cmdline.first()?).file_name()?.to_string_lossy().to_string();
+        if !INTERPRETERS.iter().any(|i| program.starts_with(i)) {
+            return None;
+        }
+        let mut args = self.cmdline.iter().skip(1).map(String::as_str);
+        let target = loop {
+            match args.next()? {
+                arg if INLINE.contains(&arg) => return None,
+                arg if TAKE_VALUE.contains(&arg) => {
+                    args.next();
+                }
+                "-jar" => break args.next()?,
+                // `python3 -m pip`, `java -m com.example.app/com.example.Main`
+                "-m" | "--module" => return args.next()?.split('/').next().map(String::from),
+                arg if !arg.starts_with('-') => break arg,
+                _ => {}
+            }
+        };
+        // `java -cp app.jar com.example.Main`: the class, not its package
+        let is_class = !target.contains('/') && Path::new(target).extension().is_some_and(|e| e != "jar" && e != "java");
+        if program.starts_with("java") && is_class {
+            return target.rsplit('.').next().map(String::from);
+        }
+        let stem = Path::new(target).file_stem()?.to_string_lossy().to_string();
+        // `electron /usr/lib/signal/app.asar`: the directory is the better name
+        if stem == "app" || stem == "main" || stem == "index" {
+            return Path::new(target).parent()?.file_name().map(|n| n.to_string_lossy().to_string());
+        }
+        Some(stem)
+    }
+}
+
+/// Parent of a process, from `/proc/<pid>/stat`
+pub fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name in parentheses may contain spaces; fields after it don't
+    let after = &stat[stat.rfind(')')? + 1..];
+    after.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// True if `pid` is `ancestor` or runs somewhere below it
+pub fn is_descendant(pid: u32, ancestor: u32) -> bool {
+    let mut current = pid;
+    // pid 1 and 0 end every chain
+    while current > 1 {
+        if current == ancestor {
+            return true;
+        }
+        match parent_pid(current) {
+            Some(parent) => current = parent,
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Processes below `ancestor` whose `comm` satisfies `matches`
+pub fn find_descendants(ancestor: u32, matches: impl Fn(&str) -> bool) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir("/proc") else { return Vec::new() };
+    entries
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| *pid != ancestor)
+        .filter(|pid| {
+            fs::read_to_string(format!("/proc/{}/comm", pid))
+                .is_ok_and(|comm| matches(comm.trim_end()))
+        })
+        .filter(|pid| is_descendant(*pid, ancestor))
+        .collect()
+}
+
+/// Last component of the cgroup path in the contents of `/proc/<pid>/cgroup`
+pub fn parse_cgroup(contents: &str) -> Option<String> {
+    // cgroup v2 has a single `0::/path` line; on v1 prefer the systemd hierarchy
+    let path = contents.lines()
+        .find_map(|l| l.strip_prefix("0::"))
+        .or_else(|| contents.lines().find_map(|l| l.split_once(":name=systemd:").map(|(_, p)| p)))?;
+    path.rsplit('/').next().filter(|s| !s.is_empty()).map(String::from)
+}
+
+/// Replaces generic window classes (`java`, `python3`, `electron`, ...) with
+/// the real application found through the process. Other ids pass through.
+///
+/// The command line comes first: a program started from a terminal runs in
+/// the terminal's scope, so its cgroup only names the terminal.
+pub fn attribute_app_id(app_id: &str, process: Option<&ProcessInfo>) -> String {
+    let generic = GENERIC_CLASSES.contains(&app_id.to_lowercase().as_str());
+    if !generic {
+        return app_id.to_string();
+    }
+    process
+        .and_then(|p| p.script_name().or_else(|| p.cgroup_app_id().filter(|id| !is_terminal(id))))
+        .unwrap_or_else(|| app_id.to_string())
+}
@@ -0,0 +1,172 @@
+use crate::config::ProjectRule;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+// Window titles -> project names.
+//
+// User rules from config.toml run first, then the built-in parsers for the
+// editors and terminals we know the title conventions of.
+
+/// App ids of terminal emulators, whose titles usually hold the shell's cwd
+const TERMINALS: [&str; 16] = [
+    "kitty", "alacritty", "foot", "footclient", "wezterm", "org.wezfurlong.wezterm",
+    "com.mitchellh.ghostty", "ghostty", "gnome-terminal-server", "org.gnome.terminal",
+    "konsole", "org.kde.konsole", "xterm", "terminator", "tilix", "st-256color",
+];
+
+pub fn is_terminal(app_id: &str) -> bool {
+    TERMINALS.contains(&app_id.to_lowercase().as_str())
+}
+
+struct CompiledRule {
+    app: Option<Regex>,
+    title: Regex,
+}
+
+pub struct ProjectExtractor {
+    rules: Vec<CompiledRule>,
+    neovim: Regex,
+    shell_prompt: Regex,
+}
+
+impl ProjectExtractor {
+    /// Compiles the user rules. A rule with an invalid regex is skipped with a warning.
+    pub fn new(rules: &[ProjectRule]) -> Self {
+        let compiled = rules.iter().filter_map(|r| {
+            let title = match Regex::new(&r.title) {
+                Ok(re) => re,
+                Err(e) => {
+                    eprintln!("Warning: invalid [[project]] title regex '{}': {}", r.title, e);
+                    return None;
+                }
+            };
+            if title.capture_names().flatten().all(|n| n != "project") {
+                eprintln!("Warning: [[project]] regex '{}' has no (?P<project>...) group", r.title);
+                return None;
+            }
+            let app = match r.app.as_deref().map(Regex::new) {
+                Some(Ok(re)) => Some(re),
+                Some(Err(e)) => {
+                    eprintln!("Warning: invalid [[project]] app regex: {}", e);
+                    return None;
+                }
+                None => None,
+            };
+            Some(CompiledRule { app, title })
+        }).collect();
+
+        ProjectExtractor {
+            rules: compiled,
+            // `main.rs (~/code/focusd) - NVIM`, `main.rs + (~/code/focusd) - Nvim`
+            neovim: Regex::new(r"(?i)^.*?\s[+-]?\s*\((?P<dir>[^)]+)\)\s+-\s+n?vim$").expect("valid regex"),
+            // `user@host:~/code/focusd` or `user@host: ~/code/focusd`
+            shell_prompt: Regex::new(r"^[\w.-]+@[\w.-]+:\s*(?P<dir>[~/].*)$").expect("valid regex"),
+        }
+    }
+
+    pub fn extract(&self, app_id: &str, title: &str) -> Option<String> {
+        let title = title.trim();
+        if title.is_empty() {
+            return None;
+        }
+
+        for rule in &self.rules {
+            if let Some(app) = &rule.app {
+                if !app.is_match(app_id) { continue; }
+            }
+            if let Some(project) = rule.title.captures(title).and_then(|c| c.name("project")) {
+                let project = project.as_str().trim();
+                if !project.is_empty() {
+                    return Some(project.to_string());
+                }
+            }
+        }
+
+        let app = app_id.to_lowercase();
+        if let Some(p) = self.neovim.captures(title).and_then(|c| c.name("dir")) {
+            return project_from_path(p.as_str());
+        }
+        if app.contains("code") || app.contains("codium") || app == "cursor" {
+            return vscode(title);
+        }
+        if app.starts_with("jetbrains-") {
+            return jetbrains(title);
+        }
+        if app.contains("zed") {
+            return zed(title);
+        }
+        if is_terminal(&app) {
+            return self.terminal(title);
+        }
+        None
+    }
+
+    fn terminal(&self, title: &str) -> Option<String> {
+        if let Some(dir) = self.shell_prompt.captures(title).and_then(|c| c.name("dir")) {
+            return project_from_path(dir.as_str());
+        }
+        // fish / zsh style: just the cwd, sometimes followed by the command
+        if title.starts_with('~') || title.starts_with('/') {
+            let dir = title.split_whitespace().next()?;
+            return project_from_path(dir);
+        }
+        None
+    }
+}
+
+/// `● main.rs - focusd - Visual Studio Code`; with no editor open the title is
+/// just `focusd - Visual Studio Code`, and with no folder open it's
+/// `main.rs - Visual Studio Code`, which names a file rather than a project.
+fn vscode(title: &str) -> Option<String> {
+    let parts: Vec<&str> = title.split(" - ").map(str::trim).collect();
+    match parts.len() {
+        0 | 1 => None,
+        2 if is_editor_tab(parts[0]) => None,
+        2 => Some(parts[0].to_string()),
+        n => Some(parts[n - 2].to_string()),
+    }
+    .filter(|p| !p.is_empty() && !p.starts_with("Welcome"))
+}
+
+/// A dirty marker, a file extension or an unsaved buffer. A folder with a
+/// dot in its name looks the same and is given up on.
+fn is_editor_tab(name: &str) -> bool {
+    name.starts_with('●')
+        || name.starts_with("Untitled-")
+        || Path::new(name).extension().is_some_and(|ext| !ext.is_empty() && !name.contains(char::is_whitespace))
+}
+
+/// `focusd – main.rs` or `focusd [~/code/focusd] – src/main.rs`
+fn jetbrains(title: &str) -> Option<String> {
+    let first = title.split(" – ").next()?.trim();
+    let project = first.split(" [").next()?.trim();
+    (!project.is_empty()).then(|| project.to_string())
+}
+
+/// `focusd — src/main.rs`
+fn zed(title: &str) -> Option<String> {
+    let project = title.split(" — ").next()?.trim();
+    (!project.is_empty() && project != "Zed").then(|| project.to_string())
+}
+
+/// The repository a directory belongs to if we can see it locally, otherwise
+/// the directory's own name. Home and the filesystem root aren't projects.
+pub fn project_from_path(dir: &str) -> Option<String> {
+    let dir = dir.trim().trim_end_matches('/');
+    let expanded: PathBuf = match (dir.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(dir),
+    };
+    if dir.is_empty() || dir == "~" || Some(expanded.as_path()) == dirs::home_dir().as_deref() {
+        return None;
+    }
+
+    let mut current: Option<&Path> = Some(&expanded);
+    while let Some(path) = current {
+        if path.join(".git").exists() {
+            return path.file_name().map(|n| n.to_string_lossy().to_string());
+        }
+        current = path.parent();
+    }
+    expanded.file_name().map(|n| n.to_string_lossy().to_string())
+}
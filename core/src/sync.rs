@@ -0,0 +1,198 @@
+use crate::db::{Db, Device};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::Path;
+
+// File-based sync between installs through a shared folder (Syncthing, Nextcloud, ...).
+//
+// Every device only ever appends to its own change log, `<device_id>.jsonl`, so
+// the sync tool never sees two machines writing the same file. Each line holds
+// the *absolute* total of one (app, date) row, which makes importing idempotent:
+// replaying a line just writes the same number again. A deleted row is sent as
+// a total of zero.
+//
+// Readers remember how far into a log they got and only read what was appended
+// since. Once a log holds many superseded lines its writer rewrites it with
+// only the latest line per row and bumps the generation in the header, which
+// tells readers to start over (seq numbers still skip what they have).
+
+/// Lines a change log may grow to before it's compacted
+const COMPACT_AFTER: u64 = 20_000;
+
+/// One line of a device change log
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SyncEntry {
+    pub seq: u64,
+    pub app_id: String,
+    pub display_name: Option<String>,
+    pub date: String,
+    pub seconds: i64,
+}
+
+/// First line of every change log, so other installs know who wrote it
+#[derive(Serialize, Deserialize)]
+struct LogHeader {
+    device_id: String,
+    hostname: String,
+    // Bumped whenever the log is rewritten rather than appended to
+    #[serde(default)]
+    generation: u64,
+}
+
+#[derive(Default, Debug)]
+pub struct SyncReport {
+    pub exported: usize,
+    pub imported: usize,
+    pub devices: usize,
+}
+
+pub fn sync_dir(db: &Db, dir: &Path) -> anyhow::Result<SyncReport> {
+    fs::create_dir_all(dir)?;
+    let mut report = SyncReport {
+        exported: export_changes(db, dir)?,
+        ..Default::default()
+    };
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") { continue; }
+
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+        if stem == db.device_id() { continue; }
+
+        report.imported += import_log(db, &path)?;
+        report.devices += 1;
+    }
+    Ok(report)
+}
+
+fn export_changes(db: &Db, dir: &Path) -> anyhow::Result<usize> {
+    let log_path = dir.join(format!("{}.jsonl", db.device_id()));
+    let meta = |key: &str| -> anyhow::Result<u64> { Ok(db.meta_get(key)?.and_then(|v| v.parse().ok()).unwrap_or(0)) };
+    let exported_at = meta("sync_exported_at")? as i64;
+    let mut seq = meta("sync_seq")?;
+    let mut generation = meta("sync_generation")?;
+    let mut lines = meta("sync_log_lines")?;
+
+    // New folder, or the log was deleted from it: write everything again.
+    // Sequence numbers keep counting so other devices don't skip the rewrite.
+    let fresh = !log_path.exists();
+    let since = if fresh { 0 } else { exported_at };
+
+    let now = chrono::Utc::now().timestamp();
+    let changes = db.local_changes_since(since)?;
+    if changes.is_empty() && !fresh {
+        return Ok(0);
+    }
+
+    let mut out = String::new();
+    if fresh {
+        generation += 1;
+        lines = 0;
+        out.push_str(&header_line(db, generation)?);
+    }
+    let count = changes.len();
+    for mut change in changes {
+        seq += 1;
+        change.seq = seq;
+        out.push_str(&serde_json::to_string(&change)?);
+        out.push('\n');
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(&log_path)?;
+    file.write_all(out.as_bytes())?;
+    file.sync_all()?;
+    lines += count as u64;
+
+    if lines > COMPACT_AFTER {
+        generation += 1;
+        lines = compact(db, &log_path, generation)?;
+    }
+
+    db.meta_set("sync_exported_at", &now.to_string())?;
+    db.meta_set("sync_seq", &seq.to_string())?;
+    db.meta_set("sync_generation", &generation.to_string())?;
+    db.meta_set("sync_log_lines", &lines.to_string())?;
+    Ok(count)
+}
+
+fn header_line(db: &Db, generation: u64) -> anyhow::Result<String> {
+    let hostname = db.list_devices()?
+        .into_iter()
+        .find(|d| d.id == db.device_id())
+        .map(|d| d.hostname)
+        .unwrap_or_default();
+    let header = LogHeader { device_id: db.device_id().to_string(), hostname, generation };
+    Ok(serde_json::to_string(&header)? + "\n")
+}
+
+/// Rewrites our own log with only the latest line per (app, date). Returns
+/// the number of lines kept.
+fn compact(db: &Db, log_path: &Path, generation: u64) -> anyhow::Result<u64> {
+    let contents = fs::read_to_string(log_path)?;
+    let mut latest: HashMap<(String, String), SyncEntry> = HashMap::new();
+    for line in contents.lines().skip(1) {
+        if let Ok(entry) = serde_json::from_str::<SyncEntry>(line) {
+            latest.insert((entry.app_id.clone(), entry.date.clone()), entry);
+        }
+    }
+    let mut entries: Vec<SyncEntry> = latest.into_values().collect();
+    entries.sort_by_key(|e| e.seq);
+
+    let mut out = header_line(db, generation)?;
+    for entry in &entries {
+        out.push_str(&serde_json::to_string(entry)?);
+        out.push('\n');
+    }
+    // Replaced in one step, so readers see either the old log or the new one
+    let tmp = log_path.with_extension("jsonl.tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(out.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, log_path)?;
+    Ok(entries.len() as u64)
+}
+
+fn import_log(db: &Db, path: &Path) -> anyhow::Result<usize> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut first = String::new();
+    reader.read_line(&mut first)?;
+    let Ok(header) = serde_json::from_str::<LogHeader>(&first) else {
+        eprintln!("Warning: skipping {}, missing header", path.display());
+        return Ok(0);
+    };
+    let (last_seq, generation, offset) = db.sync_position(&header.device_id)?;
+
+    // Carry on where the last sync stopped, unless the log was rewritten since
+    let length = reader.get_ref().metadata()?.len();
+    let mut position = first.len() as u64;
+    if generation == header.generation && offset > position && offset <= length {
+        reader.seek(SeekFrom::Start(offset))?;
+        position = offset;
+    }
+
+    let mut entries = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        // A half-synced file can end in a partial line; pick it up next time
+        if read == 0 || !line.ends_with('\n') {
+            break;
+        }
+        position += read as u64;
+        match serde_json::from_str::<SyncEntry>(&line) {
+            Ok(entry) if entry.seq > last_seq => entries.push(entry),
+            Ok(_) => {}
+            // A complete line won't change, so stopping here would stall the log
+            Err(e) => eprintln!("Warning: skipping a bad line in {}: {}", path.display(), e),
+        }
+    }
+
+    let device = Device { id: header.device_id, hostname: header.hostname };
+    db.apply_sync_entries(&device, &entries)?;
+    db.set_sync_log_position(&device.id, header.generation, position)?;
+    Ok(entries.len())
+}
@@ -0,0 +1,70 @@
+use crate::db::TimelineEntry;
+use std::collections::HashMap;
+
+// A day's timeline cut into equal slots for Gantt-style bars.
+//
+// The span runs from the whole local hour before the first entry to the one
+// after the last, so it's always whole hours long and divides evenly into
+// `width` slots as long as `width` divides 3600.
+
+/// One row of bars: an app, or every app past the busiest few
+pub struct Lane {
+    pub name: String,
+    pub total: i64,
+    /// Seconds of the lane's time falling into each slot
+    pub cells: Vec<i64>,
+}
+
+pub struct Bins {
+    pub start: i64,
+    pub end: i64,
+    /// Seconds per cell
+    pub slot: i64,
+    /// Busiest first, then "Other" if any apps didn't get a lane
+    pub lanes: Vec<Lane>,
+}
+
+/// Spreads `timeline` over `width` slots, one lane per app for the busiest
+/// `max_lanes`. Times are placed by the first entry's offset. `None` for an
+/// empty timeline or no width.
+pub fn bin(timeline: &[TimelineEntry], width: usize, max_lanes: usize) -> Option<Bins> {
+    if width == 0 {
+        return None;
+    }
+    let first = timeline.iter().min_by_key(|e| e.started_at)?;
+    let last = timeline.iter().map(|e| e.ended_at).max()?;
+    let offset = first.offset as i64;
+    let start = first.started_at - (first.started_at + offset).rem_euclid(3600);
+    let end = last + (3600 - (last + offset).rem_euclid(3600)) % 3600;
+    let slot = ((end - start) / width as i64).max(1);
+
+    let mut totals: HashMap<&str, i64> = HashMap::new();
+    for e in timeline {
+        *totals.entry(e.app.as_str()).or_default() += e.ended_at - e.started_at;
+    }
+    let mut ranked: Vec<(&str, i64)> = totals.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    let mut lanes: Vec<Lane> = ranked.iter().take(max_lanes)
+        .map(|(app, total)| Lane { name: app.to_string(), total: *total, cells: vec![0; width] })
+        .collect();
+    let others: i64 = ranked.iter().skip(max_lanes).map(|(_, s)| s).sum();
+    if others > 0 {
+        lanes.push(Lane { name: "Other".to_string(), total: others, cells: vec![0; width] });
+    }
+
+    for e in timeline {
+        let lane = ranked.iter().take(max_lanes).position(|(app, _)| *app == e.app).unwrap_or(lanes.len() - 1);
+        let mut t = e.started_at;
+        while t < e.ended_at {
+            let i = (t - start) / slot;
+            let slot_end = start + (i + 1) * slot;
+            let until = slot_end.min(e.ended_at);
+            // Only an uneven width leaves a remainder past the last cell
+            let cell = (i as usize).min(width - 1);
+            lanes[lane].cells[cell] += until - t;
+            t = until;
+        }
+    }
+    Some(Bins { start, end, slot, lanes })
+}
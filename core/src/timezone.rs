@@ -0,0 +1,164 @@
+use chrono::{DateTime, Duration, FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Timelike};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+// Time zones for turning stored UTC timestamps into days and hours.
+//
+// Sessions are stored in UTC together with the UTC offset in effect when they
+// were recorded. Reports either use that offset ("recorded", where you were at
+// the time) or reinterpret everything in one zone picked with `--tz`.
+
+/// The time zone reports are computed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Zone {
+    /// The offset each session was recorded with; this machine's zone for anything else
+    #[default]
+    Recorded,
+    /// This machine's time zone
+    Local,
+    /// A fixed offset such as `UTC` or `+05:30`
+    Fixed(FixedOffset),
+    /// An IANA zone such as `Europe/Berlin`, with its DST rules
+    Named(Tz),
+}
+
+impl FromStr for Zone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "recorded" => return Ok(Zone::Recorded),
+            "local" => return Ok(Zone::Local),
+            "utc" | "z" => return Ok(Zone::Fixed(FixedOffset::east_opt(0).expect("UTC is valid"))),
+            _ => {}
+        }
+        if let Some(offset) = parse_offset(s) {
+            return Ok(Zone::Fixed(offset));
+        }
+        s.parse::<Tz>().map(Zone::Named).map_err(|_| {
+            anyhow::anyhow!("Unknown time zone '{}' (use a name like Europe/Berlin, an offset like +05:30, 'local' or 'recorded')", s)
+        })
+    }
+}
+
+/// `+05:30`, `-0800` or `+02`
+fn parse_offset(s: &str) -> Option<FixedOffset> {
+    let sign = match s.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = s[1..].chars().filter(|c| *c != ':').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (digits[..2].parse::<i32>().ok()?, digits[2..].parse::<i32>().ok()?),
+        _ => return None,
+    };
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// This machine's offset from UTC at `ts`, in seconds east
+pub fn local_offset(ts: i64) -> i32 {
+    Local.offset_from_utc_datetime(&utc(ts)).local_minus_utc()
+}
+
+fn utc(ts: i64) -> NaiveDateTime {
+    DateTime::from_timestamp(ts, 0).unwrap_or_default().naive_utc()
+}
+
+impl Zone {
+    /// The zone a session recorded with `recorded` (seconds east of UTC) is
+    /// shown in. Never `Recorded`.
+    pub fn resolve(self, recorded: Option<i32>) -> Zone {
+        match (self, recorded.and_then(FixedOffset::east_opt)) {
+            (Zone::Recorded, Some(offset)) => Zone::Fixed(offset),
+            (Zone::Recorded, None) => Zone::Local,
+            (zone, _) => zone,
+        }
+    }
+
+    /// Offset from UTC at `ts`, in seconds east
+    pub fn offset_at(&self, ts: i64) -> i32 {
+        match self {
+            Zone::Recorded | Zone::Local => local_offset(ts),
+            Zone::Fixed(offset) => offset.local_minus_utc(),
+            Zone::Named(tz) => tz.offset_from_utc_datetime(&utc(ts)).fix().local_minus_utc(),
+        }
+    }
+
+    /// What a wall clock in this zone reads at `ts`
+    pub fn wall_clock(&self, ts: i64) -> NaiveDateTime {
+        utc(ts) + Duration::seconds(self.offset_at(ts) as i64)
+    }
+
+    /// The day `ts` belongs to when days begin at `day_start`
+    pub fn date_of(&self, ts: i64, day_start: NaiveTime) -> NaiveDate {
+        let guess = (self.wall_clock(ts) - day_start.signed_duration_since(NaiveTime::MIN)).date();
+        // The clock reads a day start twice when it's turned back over it;
+        // the day began the first time
+        if ts >= self.day_start(guess + Duration::days(1), day_start) {
+            guess + Duration::days(1)
+        } else if ts < self.day_start(guess, day_start) {
+            guess - Duration::days(1)
+        } else {
+            guess
+        }
+    }
+
+    /// Unix timestamp at which `date` begins. A start skipped by a DST jump
+    /// means the moment of the jump; one that happens twice means the first.
+    pub fn day_start(&self, date: NaiveDate, day_start: NaiveTime) -> i64 {
+        self.timestamp(date.and_time(day_start))
+    }
+
+    /// The first moment the wall clock reads `wall` or later
+    pub fn timestamp(&self, wall: NaiveDateTime) -> i64 {
+        let result = match self {
+            Zone::Recorded | Zone::Local => Local.from_local_datetime(&wall).map(|t| t.timestamp()),
+            Zone::Fixed(offset) => offset.from_local_datetime(&wall).map(|t| t.timestamp()),
+            Zone::Named(tz) => tz.from_local_datetime(&wall).map(|t| t.timestamp()),
+        };
+        match result {
+            LocalResult::Single(ts) | LocalResult::Ambiguous(ts, _) => ts,
+            LocalResult::None => {
+                // In a gap: bisect between the readings with the offsets from
+                // a day either side for where the clock jumps past `wall`
+                let naive = wall.and_utc().timestamp();
+                let before = self.offset_at(naive - 86400) as i64;
+                let after = self.offset_at(naive + 86400) as i64;
+                let (mut lo, mut hi) = ((naive - before).min(naive - after), (naive - before).max(naive - after));
+                while lo < hi {
+                    let mid = lo + (hi - lo) / 2;
+                    if self.wall_clock(mid) >= wall {
+                        hi = mid;
+                    } else {
+                        lo = mid + 1;
+                    }
+                }
+                hi
+            }
+        }
+    }
+
+    /// Splits `[start, end)` at wall-clock hours and day starts and calls
+    /// `f(day, hour, seconds)` for each piece. A day with a DST jump has 23
+    /// or 25 pieces, skipping or repeating an hour.
+    pub fn split_hours(&self, start: i64, end: i64, day_start: NaiveTime, mut f: impl FnMut(NaiveDate, u32, i64)) {
+        let mut ts = start;
+        while ts < end {
+            let wall = self.wall_clock(ts);
+            let date = self.date_of(ts, day_start);
+            let next_hour = ts + 3600 - (wall.minute() * 60 + wall.second()) as i64;
+            let next_day = self.day_start(date + Duration::days(1), day_start);
+            let piece_end = next_hour.min(next_day).min(end).max(ts + 1);
+            f(date, wall.hour(), piece_end - ts);
+            ts = piece_end;
+        }
+    }
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{record, scratch};
+use focusd_core::db::Db;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::fs;
+
+fn name_of(db: &Db, app_id: &str) -> String {
+    db.get_app(app_id).unwrap().unwrap().display_name
+}
+
+/// Bumped by SQLite whenever another connection commits a change
+fn data_version(conn: &Connection) -> i64 {
+    conn.query_row("PRAGMA data_version", [], |row| row.get(0)).unwrap()
+}
+
+#[test]
+fn config_aliases_win_and_unchanged_ones_write_nothing() {
+    let dir = scratch("aliases-config");
+    let mut db = Db::open(dir.join("focusd.db")).unwrap();
+    let day: NaiveDate = "2026-10-05".parse().unwrap();
+    for app in ["firefox", "kitty", "org.gnome.Nautilus"] {
+        record(&db, app, day, 60);
+    }
+    db.set_alias("kitty", "Terminal").unwrap();
+    db.set_alias("firefox", "Browser").unwrap();
+
+    let aliases = HashMap::from([("firefox".to_string(), "Firefox".to_string())]);
+    db.set_config_aliases(&aliases).unwrap();
+    assert_eq!(name_of(&db, "firefox"), "Firefox");
+    assert_eq!(name_of(&db, "kitty"), "Terminal");
+    assert_eq!(name_of(&db, "org.gnome.Nautilus"), "org.gnome.Nautilus");
+
+    let watcher = Connection::open(dir.join("focusd.db")).unwrap();
+    let before = data_version(&watcher);
+    db.set_config_aliases(&aliases).unwrap();
+    let mut reopened = Db::open(dir.join("focusd.db")).unwrap();
+    reopened.set_config_aliases(&aliases).unwrap();
+    assert_eq!(data_version(&watcher), before);
+
+    // Dropping the config alias falls back to the database one
+    reopened.set_config_aliases(&HashMap::new()).unwrap();
+    assert_eq!(name_of(&reopened, "firefox"), "Browser");
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,88 @@
+use focusd_core::breaks::{BreakEvent, BreakTracker};
+use focusd_core::config::{BreakReminder, Breaks};
+
+fn tracker(reminders: &[(&str, &str, &str)]) -> BreakTracker {
+    BreakTracker::new(&Breaks {
+        reset_after: "5m".to_string(),
+        snooze: "2m".to_string(),
+        reminders: reminders.iter().map(|(name, every, length)| BreakReminder {
+            name: name.to_string(),
+            every: every.to_string(),
+            length: length.to_string(),
+            message: None,
+        }).collect(),
+    })
+}
+
+/// Runs `ticks` ticks of one second and collects the events
+fn run(tracker: &mut BreakTracker, ticks: u64, idle: bool, input_idle: Option<u64>) -> Vec<BreakEvent> {
+    (0..ticks).flat_map(|_| tracker.tick(idle, input_idle, 1)).collect()
+}
+
+fn due(name: &str, message: &str, active: u64) -> BreakEvent {
+    BreakEvent::Due { name: name.to_string(), message: message.to_string(), active }
+}
+
+#[test]
+fn reminder_is_due_after_its_active_time_and_comes_back() {
+    let mut breaks = tracker(&[("Eyes", "20m", "20s")]);
+    assert_eq!(run(&mut breaks, 1199, false, None), []);
+    assert_eq!(run(&mut breaks, 1, false, None), [due("Eyes", "Take a 20s break", 1200)]);
+    assert!(breaks.status()[0].pending);
+
+    // Ignored, it comes back after another 20 minutes
+    assert_eq!(run(&mut breaks, 1199, false, None), []);
+    assert_eq!(run(&mut breaks, 1, false, None), [due("Eyes", "Take a 20s break", 2400)]);
+}
+
+#[test]
+fn snooze_postpones_pending_reminders_only() {
+    let mut breaks = tracker(&[("Eyes", "20m", "20s"), ("Stretch", "1h", "5m")]);
+    assert!(breaks.snooze(None, None).is_err());
+
+    run(&mut breaks, 1200, false, None);
+    assert_eq!(breaks.snooze(None, None).unwrap(), ["Eyes"]);
+    assert_eq!(breaks.tick(false, None, 1), [BreakEvent::Snoozed { name: "Eyes".to_string() }]);
+    assert_eq!(breaks.status()[0].due_in, 119);
+    assert_eq!(run(&mut breaks, 118, false, None), []);
+    assert_eq!(run(&mut breaks, 1, false, None), [due("Eyes", "Take a 20s break", 1320)]);
+
+    assert!(breaks.snooze(Some("stretch"), Some(60)).is_err());
+    assert_eq!(breaks.snooze(Some("eyes"), Some(60)).unwrap(), ["Eyes"]);
+    assert_eq!(breaks.status()[0].due_in, 60);
+}
+
+#[test]
+fn short_breaks_count_with_input_idle_time() {
+    let mut breaks = tracker(&[("Eyes", "20m", "20s")]);
+    run(&mut breaks, 1200, false, None);
+
+    // 19 seconds away aren't enough
+    for since in 1..20 {
+        assert_eq!(breaks.tick(false, Some(since), 1), []);
+    }
+    assert_eq!(breaks.tick(false, Some(0), 1), []);
+    assert!(breaks.status()[0].pending);
+
+    for since in 1..20 {
+        assert_eq!(breaks.tick(false, Some(since), 1), []);
+    }
+    assert_eq!(breaks.tick(false, Some(20), 1), [BreakEvent::Taken { name: "Eyes".to_string() }]);
+    let status = &breaks.status()[0];
+    assert!(!status.pending);
+    assert_eq!((status.active, status.due_in), (0, 1200));
+}
+
+#[test]
+fn idle_hint_counts_idle_ticks_and_reset_after_restarts_every_clock() {
+    let mut breaks = tracker(&[("Eyes", "20m", "20s"), ("Walk", "2h", "15m")]);
+    run(&mut breaks, 1200, false, None);
+
+    assert_eq!(run(&mut breaks, 19, true, None), []);
+    assert_eq!(run(&mut breaks, 1, true, None), [BreakEvent::Taken { name: "Eyes".to_string() }]);
+    assert_eq!(breaks.status()[1].active, 1200);
+
+    // Five minutes restart the walk clock too, without a reminder to take
+    assert_eq!(run(&mut breaks, 280, true, None), []);
+    assert_eq!(breaks.status()[1].active, 0);
+}
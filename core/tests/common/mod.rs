@@ -0,0 +1,41 @@
+// Fixtures shared by the integration tests. Each test binary uses a
+// different subset, hence the allow.
+#![allow(dead_code)]
+
+use chrono::NaiveDate;
+use focusd_core::db::{Db, FocusContext};
+use std::fs;
+use std::path::PathBuf;
+
+/// A fresh directory under the system temp dir. `name` has to be unique
+/// across all test files, so it starts with the file's own name.
+pub fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("focusd-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+pub fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+/// The Monday most tests record on
+pub fn day() -> NaiveDate {
+    date("2026-10-05")
+}
+
+/// Seconds `db` has for `app` on `day()`, all devices
+pub fn seconds(db: &Db, app: &str) -> i64 {
+    db.get_app_usage_range(day(), day()).unwrap()
+        .into_iter()
+        .find(|(name, _)| name == app)
+        .map_or(0, |(_, s)| s)
+}
+
+/// Sets `app`'s total on `date`. The app is made known first, as the daemon's
+/// first tick would: editing only works on apps that were recorded.
+pub fn record(db: &Db, app: &str, date: NaiveDate, seconds: i64) {
+    db.log_usage(app, "", 0, &FocusContext::default()).unwrap();
+    db.set_usage(app, date, seconds).unwrap();
+}
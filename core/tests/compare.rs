@@ -0,0 +1,75 @@
+mod common;
+
+use common::date;
+use focusd_core::compare::{deltas, DateRange};
+
+fn range(start: &str, end: &str) -> DateRange {
+    DateRange { start: date(start), end: date(end) }
+}
+
+#[test]
+fn parse_understands_every_form() {
+    // A Wednesday
+    let today = date("2026-10-14");
+    let cases = [
+        ("today", range("2026-10-14", "2026-10-14")),
+        ("Yesterday", range("2026-10-13", "2026-10-13")),
+        ("this-week", range("2026-10-12", "2026-10-14")),
+        ("last_week", range("2026-10-05", "2026-10-11")),
+        ("this month", range("2026-10-01", "2026-10-14")),
+        ("last-month", range("2026-09-01", "2026-09-30")),
+        ("7d", range("2026-10-08", "2026-10-14")),
+        ("1d", range("2026-10-14", "2026-10-14")),
+        ("2026-10-02", range("2026-10-02", "2026-10-02")),
+        ("2026-w41", range("2026-10-05", "2026-10-11")),
+        ("2026-02", range("2026-02-01", "2026-02-28")),
+        ("2026-10-01..2026-10-03", range("2026-10-01", "2026-10-03")),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(DateRange::parse(input, today).unwrap(), expected, "{}", input);
+    }
+}
+
+#[test]
+fn parse_rejects_what_it_cannot_use() {
+    let today = date("2026-10-14");
+    for input in ["0d", "-3d", "99999999999d", "9223372036854775807d", "2026-10-03..2026-10-01", "2026-13", "fortnight", "2026-10-01..soon"] {
+        assert!(DateRange::parse(input, today).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn running_range_is_compared_with_as_many_days() {
+    let today = date("2026-10-14");
+    let this_week = DateRange::parse("this-week", today).unwrap();
+    let last_week = DateRange::parse("last-week", today).unwrap();
+    assert_eq!(last_week.same_days_as(&this_week, today), range("2026-10-05", "2026-10-07"));
+
+    let last_month = DateRange::parse("last-month", today).unwrap();
+    let this_month = DateRange::parse("this-month", today).unwrap();
+    assert_eq!(last_month.same_days_as(&this_month, today), range("2026-09-01", "2026-09-14"));
+
+    // Finished ranges stay as they are
+    let w40 = DateRange::parse("2026-W40", today).unwrap();
+    assert_eq!(w40.same_days_as(&last_week, today), w40);
+    assert_eq!(this_week.same_days_as(&last_week, today), this_week);
+}
+
+#[test]
+fn deltas_cover_both_ranges_biggest_change_first() {
+    let current = vec![("firefox".to_string(), 3600), ("kitty".to_string(), 1200), ("steam".to_string(), 900)];
+    let previous = vec![("firefox".to_string(), 1800), ("kitty".to_string(), 1200), ("slack".to_string(), 2400)];
+    let result = deltas(current, previous);
+
+    let names: Vec<&str> = result.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, ["slack", "firefox", "steam", "kitty"]);
+
+    let [slack, firefox, steam, kitty] = &result[..] else { panic!() };
+    assert_eq!((slack.current, slack.previous, slack.change), (0, 2400, -2400));
+    assert!(slack.gone && !slack.new);
+    assert_eq!(slack.percent, Some(-100.0));
+    assert_eq!((firefox.change, firefox.percent), (1800, Some(100.0)));
+    assert!(steam.new && !steam.gone);
+    assert_eq!(steam.percent, None);
+    assert_eq!((kitty.change, kitty.percent), (0, Some(0.0)));
+}
@@ -0,0 +1,46 @@
+use focusd_core::duration::{format_duration, parse_duration};
+
+#[test]
+fn parse_duration_reads_units_and_bare_minutes() {
+    let cases = [
+        ("1h20m", 4800),
+        ("50m", 3000),
+        ("90s", 90),
+        ("2h", 7200),
+        (" 1h 30m ", 5400),
+        ("1h1m1s", 3661),
+        ("25", 1500),
+        ("0", 0),
+        ("0s", 0),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(parse_duration(input).unwrap(), expected, "{}", input);
+    }
+}
+
+#[test]
+fn parse_duration_rejects_garbage_and_overflow() {
+    for input in ["", "  ", "h", "1d", "1.5h", "-5m", "1h20", "m30", "18446744073709551615", "2562047788015216h", "99999999999999999999s", "9223372036854775807s1s"] {
+        assert!(parse_duration(input).is_err(), "{}", input);
+    }
+    assert_eq!(parse_duration("9223372036854775807s").unwrap(), i64::MAX as u64);
+}
+
+#[test]
+fn format_duration_shows_the_two_largest_units() {
+    let cases = [
+        (0, "0s"),
+        (45, "45s"),
+        (60, "1m"),
+        (3599, "59m"),
+        (3600, "1h 0m"),
+        (4830, "1h 20m"),
+        (90000, "25h 0m"),
+        (-90, "-1m"),
+        (-4800, "-1h 20m"),
+    ];
+    for (seconds, expected) in cases {
+        assert_eq!(format_duration(seconds), expected, "{}", seconds);
+    }
+    assert!(format_duration(i64::MIN).starts_with('-'));
+}
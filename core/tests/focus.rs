@@ -0,0 +1,59 @@
+use chrono::{DateTime, Local, TimeZone};
+use focusd_core::config::FocusSchedule;
+use focusd_core::focus::scheduled_until;
+
+fn schedule(days: &[&str], start: &str, end: &str) -> FocusSchedule {
+    FocusSchedule {
+        days: days.iter().map(|d| d.to_string()).collect(),
+        start: start.to_string(),
+        end: end.to_string(),
+    }
+}
+
+/// Local time on a day of the week of 2026-10-05 (a Monday)
+fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+    Local.with_ymd_and_hms(2026, 10, day, hour, minute, 0).unwrap()
+}
+
+#[test]
+fn daytime_window_covers_its_start_but_not_its_end() {
+    let schedules = [schedule(&["mon", "tue"], "09:00", "12:00")];
+    let ends = at(5, 12, 0).timestamp();
+    assert_eq!(scheduled_until(&schedules, at(5, 8, 59)), None);
+    assert_eq!(scheduled_until(&schedules, at(5, 9, 0)), Some(ends));
+    assert_eq!(scheduled_until(&schedules, at(5, 11, 59)), Some(ends));
+    assert_eq!(scheduled_until(&schedules, at(5, 12, 0)), None);
+    // Wednesday isn't on the list
+    assert_eq!(scheduled_until(&schedules, at(7, 10, 0)), None);
+}
+
+#[test]
+fn window_past_midnight_belongs_to_the_day_it_starts() {
+    let schedules = [schedule(&["fri"], "22:00", "02:00")];
+    let ends = at(10, 2, 0).timestamp();
+    assert_eq!(scheduled_until(&schedules, at(9, 23, 0)), Some(ends));
+    assert_eq!(scheduled_until(&schedules, at(10, 1, 30)), Some(ends));
+    assert_eq!(scheduled_until(&schedules, at(10, 2, 0)), None);
+    // Saturday night is not a Friday window, nor is early Friday morning
+    assert_eq!(scheduled_until(&schedules, at(10, 23, 0)), None);
+    assert_eq!(scheduled_until(&schedules, at(9, 1, 0)), None);
+}
+
+#[test]
+fn no_days_means_every_day_and_overlaps_run_to_the_latest_end() {
+    let schedules = [
+        schedule(&[], "09:00", "12:00"),
+        schedule(&["sat"], "11:00", "15:00"),
+    ];
+    assert_eq!(scheduled_until(&schedules, at(10, 11, 30)), Some(at(10, 15, 0).timestamp()));
+    assert_eq!(scheduled_until(&schedules, at(11, 11, 30)), Some(at(11, 12, 0).timestamp()));
+}
+
+#[test]
+fn malformed_entries_never_match() {
+    let schedules = [
+        schedule(&[], "9am", "12:00"),
+        schedule(&["someday"], "09:00", "12:00"),
+    ];
+    assert_eq!(scheduled_until(&schedules, at(5, 10, 0)), None);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use chrono::NaiveTime;
+use common::{date, record, scratch};
+use focusd_core::config::{GoalRule, LimitPeriod};
+use focusd_core::db::Db;
+use focusd_core::goals::Goal;
+use rusqlite::{params, Connection};
+use std::fs;
+
+fn goal(app: Option<&str>, project: Option<&str>, min: Option<&str>, max: Option<&str>, period: LimitPeriod, days: &[&str]) -> Goal {
+    let rule = GoalRule {
+        name: None,
+        app: app.map(String::from),
+        category: None,
+        project: project.map(String::from),
+        min: min.map(String::from),
+        max: max.map(String::from),
+        period,
+        days: days.iter().map(|d| d.to_string()).collect(),
+    };
+    Goal::from_rules(&[rule]).pop().unwrap()
+}
+
+#[test]
+fn daily_streak_skips_days_the_goal_does_not_apply_on() {
+    let dir = scratch("goals-daily");
+    let db = Db::open(dir.join("focusd.db")).unwrap();
+    // Mon 5th to Fri 16th; the Wednesday of the first week falls short
+    for (day, hours) in [("05", 2), ("06", 2), ("07", 0), ("08", 2), ("09", 2), ("12", 2), ("13", 1), ("14", 1), ("15", 1)] {
+        record(&db, "kitty", date(&format!("2026-10-{}", day)), hours * 3600);
+    }
+    record(&db, "kitty", date("2026-10-16"), 1800);
+
+    let weekdays = goal(Some("kitty"), None, Some("1h"), None, LimitPeriod::Day, &["mon", "tue", "wed", "thu", "fri"]);
+    let status = weekdays.status_at(&db, date("2026-10-16")).unwrap();
+    assert_eq!((status.used, status.scheduled, status.met()), (1800, true, false));
+    assert_eq!((status.current_streak, status.longest_streak), (6, 6));
+
+    // Today counts as soon as it's met
+    record(&db, "kitty", date("2026-10-16"), 3600);
+    let status = weekdays.status_at(&db, date("2026-10-16")).unwrap();
+    assert_eq!((status.current_streak, status.longest_streak), (7, 7));
+
+    // Every day: the empty weekend breaks it
+    let every_day = goal(Some("kitty"), None, Some("1h"), None, LimitPeriod::Day, &[]);
+    let status = every_day.status_at(&db, date("2026-10-16")).unwrap();
+    assert_eq!((status.current_streak, status.longest_streak), (5, 5));
+
+    // Looked at from a Saturday, the goal doesn't apply
+    let status = weekdays.status_at(&db, date("2026-10-17")).unwrap();
+    assert!(!status.scheduled);
+    assert_eq!(status.current_streak, 7);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn max_goal_counts_a_period_only_once_it_is_over() {
+    let dir = scratch("goals-weekly");
+    let db = Db::open(dir.join("focusd.db")).unwrap();
+    record(&db, "steam", date("2026-10-05"), 8 * 3600);
+    record(&db, "steam", date("2026-10-13"), 3 * 3600);
+
+    let weekly = goal(Some("steam"), None, None, Some("10h"), LimitPeriod::Week, &[]);
+    let status = weekly.status_at(&db, date("2026-10-14")).unwrap();
+    assert_eq!((status.used, status.met()), (3 * 3600, true));
+    assert_eq!((status.current_streak, status.longest_streak), (1, 1));
+
+    // Only days since the first recording count
+    let daily = goal(Some("steam"), None, None, Some("1h"), LimitPeriod::Day, &[]);
+    let status = daily.status_at(&db, date("2026-10-14")).unwrap();
+    assert_eq!((status.current_streak, status.longest_streak), (0, 7));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn project_goal_starts_at_the_oldest_kept_session() {
+    let dir = scratch("goals-sessions");
+    let mut db = Db::open(dir.join("focusd.db")).unwrap();
+    db.set_day_start(NaiveTime::MIN);
+    record(&db, "code", date("2026-10-05"), 3600);
+
+    // Sessions before the 12th were pruned; the 12th itself may be partial
+    let raw = Connection::open(dir.join("focusd.db")).unwrap();
+    let app: i64 = raw.query_row("SELECT id FROM apps WHERE app_id = 'code'", [], |r| r.get(0)).unwrap();
+    for (day, seconds) in [("12", 3600), ("13", 3600), ("14", 1800)] {
+        let start = date(&format!("2026-10-{}", day)).and_hms_opt(12, 0, 0).unwrap().and_utc().timestamp();
+        raw.execute(
+            "INSERT INTO sessions (app_ref_id, device_id, started_at, ended_at, project, utc_offset) VALUES (?1, ?2, ?3, ?4, 'focusd', 0)",
+            params![app, db.device_id(), start, start + seconds],
+        ).unwrap();
+    }
+    assert_eq!(db.get_first_day().unwrap(), Some(date("2026-10-05")));
+
+    let project = goal(None, Some("focusd"), None, Some("2h"), LimitPeriod::Day, &[]);
+    let status = project.status_at(&db, date("2026-10-14")).unwrap();
+    assert_eq!(status.used, 1800);
+    assert_eq!((status.current_streak, status.longest_streak), (1, 1));
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::scratch;
+use focusd_core::config::{HookEvent, HookRule};
+use focusd_core::hooks::HookRunner;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn rule(command: String, timeout: &str, debounce: Option<&str>) -> HookRule {
+    HookRule {
+        event: HookEvent::FocusChanged,
+        command,
+        timeout: timeout.to_string(),
+        debounce: debounce.map(String::from),
+    }
+}
+
+/// Contents of `path` once `done` accepts them, or after 5 seconds
+fn wait_for(path: &Path, done: impl Fn(&str) -> bool) -> String {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let contents = fs::read_to_string(path).unwrap_or_default();
+        if done(&contents) || Instant::now() >= deadline {
+            return contents;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn event_data_comes_as_environment_and_stdin() {
+    let dir = scratch("hooks-payload");
+    let out = dir.join("out");
+    let command = format!(
+        "printf '%s %s %s\\n' \"$FOCUSD_EVENT\" \"$FOCUSD_APP_ID\" \"$FOCUSD_SECONDS\" > '{0}.tmp'; cat >> '{0}.tmp'; mv '{0}.tmp' '{0}'",
+        out.display()
+    );
+    let runner = HookRunner::new(&[rule(command, "10s", None)]);
+    runner.fire(HookEvent::FocusChanged, json!({ "app_id": "kitty", "seconds": 42, "previous_app_id": null }));
+    runner.fire(HookEvent::IdleStart, json!({}));
+
+    let contents = wait_for(&out, |c| !c.is_empty());
+    let (env, stdin) = contents.split_once('\n').unwrap();
+    assert_eq!(env, "focus_changed kitty 42");
+    let payload: Value = serde_json::from_str(stdin.trim()).unwrap();
+    assert_eq!(payload["event"], "focus_changed");
+    assert_eq!(payload["app_id"], "kitty");
+    assert_eq!(payload["seconds"], 42);
+    assert!(payload["at"].is_i64());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn debounce_runs_once_for_the_latest_event() {
+    let dir = scratch("hooks-debounce");
+    let out = dir.join("out");
+    let command = format!("echo \"$FOCUSD_APP_ID\" >> '{}'", out.display());
+    let runner = HookRunner::new(&[rule(command, "10s", Some("1s"))]);
+    for app in ["firefox", "kitty", "code"] {
+        runner.fire(HookEvent::FocusChanged, json!({ "app_id": app }));
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    assert_eq!(wait_for(&out, |c| !c.is_empty()), "code\n");
+    // Nothing else turns up later
+    thread::sleep(Duration::from_millis(1500));
+    assert_eq!(fs::read_to_string(&out).unwrap(), "code\n");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn timeout_kills_what_the_script_started() {
+    let dir = scratch("hooks-timeout");
+    let pid_file = dir.join("pid");
+    let command = format!("sleep 30 & echo $! > '{}'; wait", pid_file.display());
+    let runner = HookRunner::new(&[rule(command, "1s", None)]);
+    runner.fire(HookEvent::FocusChanged, json!({}));
+
+    let pid = wait_for(&pid_file, |c| c.ends_with('\n'));
+    let stat = format!("/proc/{}/stat", pid.trim());
+    assert!(Path::new(&stat).exists());
+    // Gone, or a zombie left for init to reap
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let alive = || fs::read_to_string(&stat).is_ok_and(|s| !s.contains(") Z "));
+    while alive() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert!(!alive(), "sleep outlived the hook timeout");
+    let _ = fs::remove_dir_all(&dir);
+}
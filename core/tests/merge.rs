@@ -0,0 +1,116 @@
+mod common;
+
+use common::{day, record, scratch, seconds};
+use focusd_core::db::Db;
+use focusd_core::sync;
+use std::collections::HashMap;
+use std::fs;
+
+#[test]
+fn merge_from_adds_up_ids_that_map_to_one_app() {
+    let dir = scratch("merge-ids");
+    let laptop = Db::open(dir.join("laptop.db")).unwrap();
+    record(&laptop, "code", day(), 3600);
+    record(&laptop, "code-oss", day(), 1800);
+
+    let mut desktop = Db::open(dir.join("desktop.db")).unwrap();
+    desktop.set_merge_rules(&HashMap::from([("code-oss".to_string(), "code".to_string())])).unwrap();
+
+    assert_eq!(desktop.merge_from(dir.join("laptop.db")).unwrap(), 5400);
+    assert_eq!(seconds(&desktop, "code"), 5400);
+
+    // Merging the same file again adds nothing
+    assert_eq!(desktop.merge_from(dir.join("laptop.db")).unwrap(), 0);
+    assert_eq!(seconds(&desktop, "code"), 5400);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn sync_after_merging_apps_of_a_legacy_device_counts_once() {
+    let dir = scratch("merge-legacy");
+    let shared = dir.join("shared");
+    let laptop = Db::open(dir.join("laptop.db")).unwrap();
+    record(&laptop, "vscode", day(), 3600);
+    record(&laptop, "codium", day(), 1800);
+    sync::sync_dir(&laptop, &shared).unwrap();
+
+    let desktop = Db::open(dir.join("desktop.db")).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "vscode") + seconds(&desktop, "codium"), 5400);
+
+    // An install that imported the laptop before sync_rows existed
+    let raw = rusqlite::Connection::open(dir.join("desktop.db")).unwrap();
+    raw.execute("DELETE FROM sync_rows", []).unwrap();
+    drop(raw);
+
+    desktop.merge_apps("codium", "vscode").unwrap();
+    assert_eq!(seconds(&desktop, "vscode"), 5400);
+
+    // codium's first 30m are already in the row
+    record(&laptop, "codium", day(), 2700);
+    sync::sync_dir(&laptop, &shared).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "vscode"), 6300);
+
+    // vscode's own hour is taken over by its first entry, not added again
+    record(&laptop, "vscode", day(), 7200);
+    sync::sync_dir(&laptop, &shared).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "vscode"), 9900);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn sync_of_ids_merged_by_rule_adds_changes_only() {
+    let dir = scratch("merge-rule");
+    let shared = dir.join("shared");
+    let laptop = Db::open(dir.join("laptop.db")).unwrap();
+    record(&laptop, "code", day(), 3600);
+    record(&laptop, "code-oss", day(), 1800);
+    sync::sync_dir(&laptop, &shared).unwrap();
+
+    let mut desktop = Db::open(dir.join("desktop.db")).unwrap();
+    desktop.set_merge_rules(&HashMap::from([("code-oss".to_string(), "code".to_string())])).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "code"), 5400);
+
+    record(&laptop, "code-oss", day(), 2400);
+    sync::sync_dir(&laptop, &shared).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "code"), 6000);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn editing_a_merged_away_id_edits_the_app_it_went_into() {
+    let dir = scratch("merge-edit");
+    let mut db = Db::open(dir.join("focusd.db")).unwrap();
+    record(&db, "vscode", day(), 3600);
+    record(&db, "codium", day(), 1800);
+    db.merge_apps("codium", "vscode").unwrap();
+
+    assert_eq!(db.set_usage("codium", day(), 6000).unwrap(), 5400);
+    assert_eq!(seconds(&db, "vscode"), 6000);
+    let ids = |db: &Db| db.list_apps().unwrap().into_iter().map(|a| a.app_id).collect::<Vec<_>>();
+    assert_eq!(ids(&db), ["vscode"]);
+
+    // Same for a [merge] rule whose old id was never recorded here
+    db.set_merge_rules(&HashMap::from([("code-oss".to_string(), "vscode".to_string())])).unwrap();
+    db.set_usage("code-oss", day(), 4000).unwrap();
+    assert_eq!(seconds(&db, "vscode"), 4000);
+    assert_eq!(ids(&db), ["vscode"]);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn editing_an_app_never_recorded_is_an_error() {
+    let dir = scratch("merge-unknown");
+    let db = Db::open(dir.join("focusd.db")).unwrap();
+    record(&db, "firefox", day(), 3600);
+
+    let err = db.set_usage("fierfox", day(), 3600).unwrap_err();
+    assert_eq!(err.to_string(), "No recorded app matches 'fierfox'");
+    assert!(db.get_app("fierfox").unwrap().is_none());
+    assert!(db.get_audit_log(10).unwrap().iter().all(|e| e.app_id != "fierfox"));
+    let _ = fs::remove_dir_all(&dir);
+}
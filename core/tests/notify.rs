@@ -0,0 +1,62 @@
+use focusd_core::notify::{Notifier, Urgency};
+use std::collections::HashMap;
+use std::os::unix::net::UnixStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use zbus::blocking::connection::Builder;
+use zbus::zvariant::OwnedValue;
+
+/// Stands in for a notification daemon and records what it was asked to show
+struct StandIn {
+    received: Arc<Mutex<Vec<(String, String, u8)>>>,
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl StandIn {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        _app_name: String,
+        _replaces_id: u32,
+        _app_icon: String,
+        summary: String,
+        body: String,
+        _actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        _expire_timeout: i32,
+    ) -> u32 {
+        let urgency = hints.get("urgency").and_then(|v| u8::try_from(v).ok()).unwrap_or(1);
+        let mut received = self.received.lock().unwrap();
+        received.push((summary, body, urgency));
+        received.len() as u32
+    }
+}
+
+#[test]
+fn notify_reaches_the_notification_server() {
+    let (server_end, client_end) = UnixStream::pair().unwrap();
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let stand_in = StandIn { received: received.clone() };
+    let server = thread::spawn(move || {
+        Builder::async_io_unix_stream(server_end)
+            .server(zbus::Guid::generate())
+            .unwrap()
+            .p2p()
+            .serve_at("/org/freedesktop/Notifications", stand_in)
+            .unwrap()
+            .build()
+            .unwrap()
+    });
+    let client = Builder::async_io_unix_stream(client_end).p2p().build().unwrap();
+    let _server = server.join().unwrap();
+
+    let notifier = Notifier::with_connection(client);
+    let first = notifier.notify("Discord: 80% of the daily limit used", "48m of 1h today", Urgency::Normal).unwrap();
+    let second = notifier.notify("Discord: daily limit reached", "1h of 1h today", Urgency::Critical).unwrap();
+
+    assert_eq!((first, second), (1, 2));
+    let received = received.lock().unwrap();
+    assert_eq!(received[0], ("Discord: 80% of the daily limit used".into(), "48m of 1h today".into(), 1));
+    assert_eq!(received[1].2, 2);
+}
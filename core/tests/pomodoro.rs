@@ -0,0 +1,85 @@
+mod common;
+
+use common::scratch;
+use focusd_core::config;
+use focusd_core::db::Db;
+use focusd_core::pomodoro::{Phase, Pomodoro, Recorder};
+use std::fs;
+
+fn timer(long_break_every: u32) -> Pomodoro {
+    Pomodoro::new(&config::Pomodoro {
+        work: "25m".to_string(),
+        short_break: "5m".to_string(),
+        long_break: "15m".to_string(),
+        long_break_every,
+    })
+}
+
+/// Runs the timer to the end of its current phase
+fn finish_phase(timer: &mut Pomodoro) -> (Phase, u64, u32) {
+    let ends = timer.status().phase_ends.unwrap();
+    assert!(timer.advance(ends - 1).is_none());
+    let change = timer.advance(ends).unwrap();
+    (change.phase, change.length, change.completed)
+}
+
+#[test]
+fn phases_roll_over_with_every_nth_break_long() {
+    let mut timer = timer(3);
+    assert!(timer.advance(i64::MAX).is_none());
+
+    timer.start(None, None);
+    let mut phases = Vec::new();
+    for _ in 0..8 {
+        phases.push(finish_phase(&mut timer));
+    }
+    assert_eq!(phases, [
+        (Phase::ShortBreak, 300, 1),
+        (Phase::Work, 1500, 1),
+        (Phase::ShortBreak, 300, 2),
+        (Phase::Work, 1500, 2),
+        (Phase::LongBreak, 900, 3),
+        (Phase::Work, 1500, 3),
+        (Phase::ShortBreak, 300, 4),
+        (Phase::Work, 1500, 4),
+    ]);
+    assert_eq!(timer.status().completed, 4);
+}
+
+#[test]
+fn late_ticks_start_the_next_phase_then() {
+    let mut timer = timer(4);
+    timer.start(Some(600), Some(120));
+    let ends = timer.status().phase_ends.unwrap();
+
+    // Woken from suspend an hour late: the break runs from now
+    let change = timer.advance(ends + 3600).unwrap();
+    assert_eq!((change.phase, change.length), (Phase::ShortBreak, 120));
+    assert_eq!(timer.status().phase_ends, Some(ends + 3600 + 120));
+}
+
+#[test]
+fn stop_and_restart_in_the_same_second_records_two_blocks() {
+    let dir = scratch("pomodoro-restart");
+    let db = Db::open(dir.join("focusd.db")).unwrap();
+    let mut timer = timer(4);
+    let mut recorder = Recorder::default();
+
+    timer.start(None, None);
+    recorder.sync(&db, &mut timer).unwrap();
+    recorder.record(&db, "kitty", 60).unwrap();
+    timer.stop().unwrap();
+    assert!(timer.stop().is_err());
+    timer.start(None, None);
+    recorder.sync(&db, &mut timer).unwrap();
+    recorder.record(&db, "firefox", 30).unwrap();
+
+    let mut blocks = db.list_pomodoros(10).unwrap();
+    blocks.sort_by_key(|b| b.id);
+    let [first, second] = &blocks[..] else { panic!("{} blocks", blocks.len()) };
+    assert!(first.ended_at.is_some() && !first.completed);
+    assert_eq!(first.apps, [("kitty".to_string(), 60)]);
+    assert!(second.ended_at.is_none());
+    assert_eq!(second.apps, [("firefox".to_string(), 30)]);
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,83 @@
+use focusd_core::process::{attribute_app_id, parse_cgroup, ProcessInfo};
+
+fn process(cmdline: &[&str], cgroup: Option<&str>) -> ProcessInfo {
+    ProcessInfo {
+        pid: 1234,
+        exe: None,
+        cmdline: cmdline.iter().map(|a| a.to_string()).collect(),
+        cgroup: cgroup.map(String::from),
+    }
+}
+
+#[test]
+fn parse_cgroup_takes_the_unit_name() {
+    let cases = [
+        ("0::/user.slice/user-1000.slice/user@1000.service/app.slice/app-kitty-4242.scope\n", Some("app-kitty-4242.scope")),
+        (
+            "12:pids:/user.slice\n1:name=systemd:/user.slice/user-1000.slice/user@1000.service/app.slice/app-gnome-org.gnome.Nautilus-77.scope\n",
+            Some("app-gnome-org.gnome.Nautilus-77.scope"),
+        ),
+        ("0::/\n", None),
+        ("", None),
+    ];
+    for (contents, expected) in cases {
+        assert_eq!(parse_cgroup(contents).as_deref(), expected, "{:?}", contents);
+    }
+}
+
+#[test]
+fn cgroup_app_id_follows_the_systemd_conventions() {
+    let cases = [
+        ("app-flatpak-com.spotify.Client-12345.scope", Some("com.spotify.Client")),
+        ("app-gnome-org.gnome.Nautilus-4321.scope", Some("org.gnome.Nautilus")),
+        ("app-kitty-1234.scope", Some("kitty")),
+        ("app-org.kde.konsole@a1b2c3.service", Some("org.kde.konsole")),
+        ("app-gnome-org.example.My\\x2dTool-55.scope", Some("org.example.My-Tool")),
+        ("session-2.scope", None),
+        ("user@1000.service", None),
+        ("app.slice", None),
+    ];
+    for (unit, expected) in cases {
+        assert_eq!(process(&[], Some(unit)).cgroup_app_id().as_deref(), expected, "{}", unit);
+    }
+    assert_eq!(process(&[], None).cgroup_app_id(), None);
+}
+
+#[test]
+fn script_name_finds_what_an_interpreter_runs() {
+    let cases: [(&[&str], Option<&str>); 17] = [
+        (&["java", "-Xmx2g", "-jar", "/opt/foo/Foo.jar"], Some("Foo")),
+        (&["java", "-cp", "app.jar", "Main"], Some("Main")),
+        (&["java", "-classpath", "lib/*:app.jar", "com.example.Main"], Some("Main")),
+        (&["java", "--class-path", "app.jar", "-Dx=1", "com.example.Editor"], Some("Editor")),
+        (&["java", "-cp", "app.jar", "-jar", "/opt/bar/Bar.jar"], Some("Bar")),
+        (&["java", "--module-path", "mods", "-m", "com.example.app/com.example.Main"], Some("com.example.app")),
+        (&["python3", "-X", "opt", "script.py"], Some("script")),
+        (&["python3", "-W", "ignore", "-X", "utf8", "/usr/bin/meld"], Some("meld")),
+        (&["python3", "-m", "http.server", "8000"], Some("http.server")),
+        (&["python3", "-c", "print(1)"], None),
+        (&["node", "-r", "ts-node/register", "server.ts"], Some("server")),
+        (&["/usr/bin/python3", "/usr/bin/meld"], Some("meld")),
+        (&["python3.12", "-u", "tool.py"], Some("tool")),
+        (&["electron", "/usr/lib/signal/app.asar"], Some("signal")),
+        (&["node", "/srv/chat/index.js"], Some("chat")),
+        (&["/usr/bin/kitty"], None),
+        (&["java"], None),
+    ];
+    for (cmdline, expected) in cases {
+        assert_eq!(process(cmdline, None).script_name().as_deref(), expected, "{:?}", cmdline);
+    }
+    assert_eq!(process(&[], None).script_name(), None);
+}
+
+#[test]
+fn programs_started_from_a_terminal_are_not_the_terminal() {
+    let kitty = Some("app-kitty-1234.scope");
+    assert_eq!(attribute_app_id("java", Some(&process(&["java", "-jar", "Foo.jar"], kitty))), "Foo");
+    assert_eq!(attribute_app_id("java", Some(&process(&["java"], kitty))), "java");
+
+    let flatpak = Some("app-flatpak-com.example.Chat-99.scope");
+    assert_eq!(attribute_app_id("electron", Some(&process(&["electron"], flatpak))), "com.example.Chat");
+    assert_eq!(attribute_app_id("firefox", Some(&process(&["python3", "x.py"], kitty))), "firefox");
+    assert_eq!(attribute_app_id("python3", None), "python3");
+}
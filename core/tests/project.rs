@@ -0,0 +1,98 @@
+use focusd_core::config::ProjectRule;
+use focusd_core::project::ProjectExtractor;
+
+// Directories are absolute and don't exist, so the project is the last
+// path component rather than whatever repository the test runs in
+
+fn check(extractor: &ProjectExtractor, app_id: &str, cases: &[(&str, Option<&str>)]) {
+    for (title, expected) in cases {
+        assert_eq!(extractor.extract(app_id, title).as_deref(), *expected, "{}: {}", app_id, title);
+    }
+}
+
+#[test]
+fn vscode_titles() {
+    let extractor = ProjectExtractor::new(&[]);
+    let cases = [
+        ("● main.rs - focusd - Visual Studio Code", Some("focusd")),
+        ("main.rs - focusd - Visual Studio Code", Some("focusd")),
+        ("focusd - Visual Studio Code", Some("focusd")),
+        ("main.rs - Visual Studio Code", None),
+        ("● main.rs - Visual Studio Code", None),
+        ("Untitled-1 - Visual Studio Code", None),
+        ("Welcome - Visual Studio Code", None),
+        ("Visual Studio Code", None),
+        ("", None),
+    ];
+    for app in ["code", "code-oss", "VSCodium", "cursor"] {
+        check(&extractor, app, &cases);
+    }
+}
+
+#[test]
+fn jetbrains_titles() {
+    let extractor = ProjectExtractor::new(&[]);
+    check(&extractor, "jetbrains-idea", &[
+        ("focusd – main.rs", Some("focusd")),
+        ("focusd [~/code/focusd] – src/main.rs", Some("focusd")),
+        ("focusd", Some("focusd")),
+    ]);
+}
+
+#[test]
+fn zed_titles() {
+    let extractor = ProjectExtractor::new(&[]);
+    check(&extractor, "dev.zed.Zed", &[
+        ("focusd — src/main.rs", Some("focusd")),
+        ("focusd", Some("focusd")),
+        ("Zed", None),
+    ]);
+}
+
+#[test]
+fn neovim_titles_in_any_app() {
+    let extractor = ProjectExtractor::new(&[]);
+    let cases = [
+        ("main.rs (/srv/focusd-test/focusd) - NVIM", Some("focusd")),
+        ("main.rs + (/srv/focusd-test/focusd/src) - Nvim", Some("src")),
+        ("main.rs (/srv/focusd-test/api) - VIM", Some("api")),
+        ("main.rs (~) - NVIM", None),
+        ("main.rs - NVIM", None),
+    ];
+    for app in ["kitty", "neovide", "org.wezfurlong.wezterm"] {
+        check(&extractor, app, &cases);
+    }
+}
+
+#[test]
+fn terminal_titles() {
+    let extractor = ProjectExtractor::new(&[]);
+    let cases = [
+        ("me@laptop:/srv/focusd-test/focusd", Some("focusd")),
+        ("me@laptop: /srv/focusd-test/api/", Some("api")),
+        ("/srv/focusd-test/focusd cargo test", Some("focusd")),
+        ("me@laptop:~", None),
+        ("~", None),
+        ("/", None),
+        ("cargo test", None),
+    ];
+    for app in ["kitty", "Alacritty", "gnome-terminal-server"] {
+        check(&extractor, app, &cases);
+    }
+    // Not a terminal: a path in the title means nothing
+    check(&extractor, "firefox", &[("/srv/focusd-test/focusd", None)]);
+}
+
+#[test]
+fn user_rules_run_first() {
+    let rules = [
+        ProjectRule { app: Some("^code$".to_string()), title: r"\[(?P<project>[^\]]+)\]".to_string() },
+        ProjectRule { app: None, title: "no group".to_string() },
+    ];
+    let extractor = ProjectExtractor::new(&rules);
+    check(&extractor, "code", &[
+        ("main.rs - focusd [client] - Visual Studio Code", Some("client")),
+        ("main.rs - focusd - Visual Studio Code", Some("focusd")),
+    ]);
+    check(&extractor, "codium", &[("main.rs - focusd [client] - VSCodium", Some("focusd [client]"))]);
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use common::{day, record, scratch, seconds};
+use focusd_core::db::Db;
+use focusd_core::sync;
+use rusqlite::{params, Connection};
+use std::fs;
+use std::path::Path;
+
+/// A database as written before multi-device support
+fn old_schema(path: &Path, instance_id: &str, rows: &[(&str, i64)], ledger: &[(&str, &str, i64)]) {
+    let conn = Connection::open(path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE apps (id INTEGER PRIMARY KEY, app_id TEXT UNIQUE NOT NULL, display_name TEXT);
+         CREATE TABLE usage_daily (
+             id INTEGER PRIMARY KEY,
+             app_ref_id INTEGER NOT NULL,
+             date TEXT NOT NULL,
+             seconds_focused INTEGER DEFAULT 0,
+             UNIQUE(app_ref_id, date)
+         );
+         CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+         CREATE TABLE merge_ledger (
+             source_id TEXT NOT NULL,
+             app_id TEXT NOT NULL,
+             date TEXT NOT NULL,
+             seconds INTEGER NOT NULL,
+             PRIMARY KEY(source_id, app_id, date)
+         );"
+    ).unwrap();
+    conn.execute("INSERT INTO meta (key, value) VALUES ('instance_id', ?1)", params![instance_id]).unwrap();
+    for (app, seconds) in rows {
+        conn.execute("INSERT INTO apps (app_id, display_name) VALUES (?1, ?1)", params![app]).unwrap();
+        conn.execute(
+            "INSERT INTO usage_daily (app_ref_id, date, seconds_focused) VALUES (last_insert_rowid(), ?1, ?2)",
+            params![day().to_string(), seconds],
+        ).unwrap();
+    }
+    for (source, app, seconds) in ledger {
+        conn.execute(
+            "INSERT INTO merge_ledger (source_id, app_id, date, seconds) VALUES (?1, ?2, ?3, ?4)",
+            params![source, app, day().to_string(), seconds],
+        ).unwrap();
+    }
+}
+
+#[test]
+fn forgotten_rows_are_removed_on_other_devices() {
+    let dir = scratch("sync-forget");
+    let shared = dir.join("shared");
+    let laptop = Db::open(dir.join("laptop.db")).unwrap();
+    record(&laptop, "firefox", day(), 3600);
+    record(&laptop, "kitty", day(), 600);
+    sync::sync_dir(&laptop, &shared).unwrap();
+
+    let desktop = Db::open(dir.join("desktop.db")).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "firefox"), 3600);
+
+    laptop.forget(Some("firefox"), None, None).unwrap();
+    sync::sync_dir(&laptop, &shared).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "firefox"), 0);
+    assert_eq!(seconds(&desktop, "kitty"), 600);
+
+    // Recording the app again brings it back
+    record(&laptop, "firefox", day(), 300);
+    sync::sync_dir(&laptop, &shared).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "firefox"), 300);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn apps_merged_on_the_recording_device_sync_once() {
+    let dir = scratch("sync-apps-merge");
+    let shared = dir.join("shared");
+    let laptop = Db::open(dir.join("laptop.db")).unwrap();
+    record(&laptop, "vscode", day(), 3600);
+    record(&laptop, "codium", day(), 1800);
+    sync::sync_dir(&laptop, &shared).unwrap();
+
+    let desktop = Db::open(dir.join("desktop.db")).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+
+    laptop.merge_apps("codium", "vscode").unwrap();
+    sync::sync_dir(&laptop, &shared).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "vscode"), 5400);
+    assert_eq!(seconds(&desktop, "codium"), 0);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn upgrading_keeps_merged_time_off_this_device() {
+    let dir = scratch("sync-ledger");
+    let shared = dir.join("shared");
+    old_schema(&dir.join("other.db"), "other", &[("firefox", 1800)], &[]);
+    old_schema(&dir.join("main.db"), "main", &[("firefox", 5400)], &[("other", "firefox", 1800)]);
+
+    let main = Db::open(dir.join("main.db")).unwrap();
+    assert_eq!(seconds(&main, "firefox"), 5400);
+    assert_eq!(main.merge_from(dir.join("other.db")).unwrap(), 0);
+    assert_eq!(seconds(&main, "firefox"), 5400);
+
+    // Only this install's own hour goes out to other devices
+    sync::sync_dir(&main, &shared).unwrap();
+    let peer = Db::open(dir.join("peer.db")).unwrap();
+    sync::sync_dir(&peer, &shared).unwrap();
+    assert_eq!(seconds(&peer, "firefox"), 3600);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn logs_are_read_from_where_the_last_sync_stopped() {
+    let dir = scratch("sync-offset");
+    let shared = dir.join("shared");
+    let laptop = Db::open(dir.join("laptop.db")).unwrap();
+    record(&laptop, "firefox", day(), 600);
+    sync::sync_dir(&laptop, &shared).unwrap();
+
+    let desktop = Db::open(dir.join("desktop.db")).unwrap();
+    assert_eq!(sync::sync_dir(&desktop, &shared).unwrap().imported, 1);
+
+    // A line still being written by the file syncer is left for next time
+    record(&laptop, "firefox", day(), 1200);
+    sync::sync_dir(&laptop, &shared).unwrap();
+    let log = shared.join(format!("{}.jsonl", laptop.device_id()));
+    let contents = fs::read(&log).unwrap();
+    fs::write(&log, &contents[..contents.len() - 10]).unwrap();
+    assert_eq!(sync::sync_dir(&desktop, &shared).unwrap().imported, 0);
+
+    fs::write(&log, &contents).unwrap();
+    assert_eq!(sync::sync_dir(&desktop, &shared).unwrap().imported, 1);
+    assert_eq!(seconds(&desktop, "firefox"), 1200);
+    assert_eq!(sync::sync_dir(&desktop, &shared).unwrap().imported, 0);
+
+    // A rewritten log is read from the start again
+    fs::remove_file(&log).unwrap();
+    record(&laptop, "firefox", day(), 1500);
+    sync::sync_dir(&laptop, &shared).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "firefox"), 1500);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_bad_line_is_skipped_rather_than_stalling_the_log() {
+    let dir = scratch("sync-bad-line");
+    let shared = dir.join("shared");
+    let laptop = Db::open(dir.join("laptop.db")).unwrap();
+    record(&laptop, "firefox", day(), 600);
+    sync::sync_dir(&laptop, &shared).unwrap();
+    let log = shared.join(format!("{}.jsonl", laptop.device_id()));
+    let mut contents = fs::read_to_string(&log).unwrap();
+    contents.push_str("{\"not\": \"an entry\"\n");
+    fs::write(&log, &contents).unwrap();
+    record(&laptop, "kitty", day(), 300);
+    sync::sync_dir(&laptop, &shared).unwrap();
+
+    let desktop = Db::open(dir.join("desktop.db")).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!((seconds(&desktop, "firefox"), seconds(&desktop, "kitty")), (600, 300));
+
+    // Later entries keep coming in
+    record(&laptop, "kitty", day(), 900);
+    sync::sync_dir(&laptop, &shared).unwrap();
+    sync::sync_dir(&desktop, &shared).unwrap();
+    assert_eq!(seconds(&desktop, "kitty"), 900);
+    assert_eq!(sync::sync_dir(&desktop, &shared).unwrap().imported, 0);
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use chrono::NaiveTime;
+use common::{date, record, scratch};
+use focusd_core::config::Config;
+use focusd_core::db::{Db, FocusMetrics, TimelineEntry};
+use focusd_core::timeline;
+use rusqlite::{params, Connection};
+use std::fs;
+use std::path::PathBuf;
+
+/// `HH:MM[:SS]` on 2026-10-12, UTC
+fn at(time: &str) -> i64 {
+    let time = NaiveTime::parse_from_str(time, "%H:%M:%S").or_else(|_| NaiveTime::parse_from_str(time, "%H:%M")).unwrap();
+    date("2026-10-12").and_time(time).and_utc().timestamp()
+}
+
+/// A morning in kitty with a quick look at firefox, two hours away, then
+/// back in firefox; plus ten minutes of kitty on a second device
+fn morning(name: &str) -> (PathBuf, Db) {
+    let dir = scratch(name);
+    let mut db = Db::open(dir.join("focusd.db")).unwrap();
+    db.set_day_start(NaiveTime::MIN);
+    for app in ["kitty", "firefox"] {
+        record(&db, app, date("2026-10-12"), 60);
+    }
+    let raw = Connection::open(dir.join("focusd.db")).unwrap();
+    let sessions = [
+        ("kitty", db.device_id().to_string(), "09:00", "09:20", "vim"),
+        ("kitty", db.device_id().to_string(), "09:20:30", "09:40", "cargo"),
+        ("firefox", db.device_id().to_string(), "09:40", "09:45", "docs"),
+        ("kitty", db.device_id().to_string(), "09:45", "10:15", "vim"),
+        ("firefox", db.device_id().to_string(), "12:15", "12:25", "mail"),
+        ("kitty", db.device_id().to_string(), "12:25", "12:26", "vim"),
+        ("kitty", "laptop".to_string(), "09:50", "10:00", "ssh"),
+    ];
+    for (app, device, start, end, title) in sessions {
+        let app: i64 = raw.query_row("SELECT id FROM apps WHERE app_id = ?1", [app], |r| r.get(0)).unwrap();
+        raw.execute(
+            "INSERT INTO sessions (app_ref_id, device_id, started_at, ended_at, title, utc_offset) VALUES (?1, ?2, ?3, ?4, ?5, 0)",
+            params![app, device, at(start), at(end), title],
+        ).unwrap();
+    }
+    (dir, db)
+}
+
+#[test]
+fn stretches_join_same_app_sessions_per_device() {
+    let (dir, db) = morning("timeline-stretches");
+    let timeline = db.get_timeline(date("2026-10-12")).unwrap();
+    let stretches: Vec<(&str, i64, i64, Option<&str>)> = timeline.iter()
+        .map(|e| (e.app.as_str(), e.started_at, e.ended_at, e.title.as_deref()))
+        .collect();
+    assert_eq!(stretches, [
+        ("kitty", at("09:00"), at("09:40"), Some("vim")),
+        ("firefox", at("09:40"), at("09:45"), Some("docs")),
+        ("kitty", at("09:45"), at("10:15"), Some("vim")),
+        ("kitty", at("09:50"), at("10:00"), Some("ssh")),
+        ("firefox", at("12:15"), at("12:25"), Some("mail")),
+        ("kitty", at("12:25"), at("12:26"), Some("vim")),
+    ]);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn metrics_skip_switches_across_idle_gaps() {
+    let (dir, db) = morning("timeline-metrics");
+    let today = date("2026-10-12");
+
+    let overall = db.get_focus_metrics(today, today, 1500, 300).unwrap();
+    assert_eq!(overall, FocusMetrics {
+        total: 5760,
+        // Into firefox, back to kitty and kitty at 12:25; not firefox after two hours
+        switches: 3,
+        stretches: 6,
+        median: 600,
+        longest: 2400,
+        deep_work: 4200,
+    });
+    assert_eq!(db.get_focus_metrics(today, today, 1500, 3 * 3600).unwrap().switches, 4);
+    assert_eq!(db.get_daily_focus_metrics(today, today, 1500, 300).unwrap(), [(today, overall)]);
+
+    let apps = db.get_app_focus_metrics(today, today, 1500, 300).unwrap();
+    let [(kitty_name, kitty), (firefox_name, firefox)] = &apps[..] else { panic!("{:?}", apps) };
+    assert_eq!((kitty_name.as_str(), firefox_name.as_str()), ("kitty", "firefox"));
+    assert_eq!((kitty.total, kitty.switches, kitty.stretches, kitty.median, kitty.deep_work), (4860, 2, 4, 1200, 4200));
+    assert_eq!((firefox.total, firefox.switches, firefox.stretches, firefox.median, firefox.deep_work), (900, 1, 2, 450, 0));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn binning_fills_whole_hours_and_keeps_every_second() {
+    let (dir, db) = morning("timeline-bins");
+    let entries = db.get_timeline(date("2026-10-12")).unwrap();
+    let bins = timeline::bin(&entries, 72, 1).unwrap();
+    assert_eq!((bins.start, bins.end, bins.slot), (at("09:00"), at("13:00"), 200));
+
+    let names: Vec<&str> = bins.lanes.iter().map(|l| l.name.as_str()).collect();
+    assert_eq!(names, ["kitty", "Other"]);
+    let [kitty, other] = &bins.lanes[..] else { panic!() };
+    assert_eq!((kitty.total, other.total), (4860, 900));
+    for lane in &bins.lanes {
+        assert_eq!(lane.cells.len(), 72);
+        assert_eq!(lane.cells.iter().sum::<i64>(), lane.total, "{}", lane.name);
+    }
+    // 09:00-09:40 fills twelve cells, then firefox has the next one and a half
+    assert!(kitty.cells[..12].iter().all(|&s| s == 200));
+    assert_eq!((kitty.cells[12], other.cells[12], other.cells[13], kitty.cells[13]), (0, 200, 100, 100));
+
+    assert!(timeline::bin(&[], 72, 10).is_none());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn binning_follows_the_local_hour_and_uneven_widths() {
+    // 09:30 to 11:15 in India
+    let entry = |app: &str, start: &str, end: &str| TimelineEntry {
+        app: app.to_string(),
+        title: None,
+        started_at: at(start),
+        ended_at: at(end),
+        offset: 19800,
+    };
+    let entries = [entry("kitty", "04:00", "05:00"), entry("firefox", "05:00", "05:45")];
+
+    let bins = timeline::bin(&entries, 72, 10).unwrap();
+    assert_eq!((bins.start, bins.end, bins.slot), (at("03:30"), at("06:30"), 150));
+
+    let bins = timeline::bin(&entries, 7, 10).unwrap();
+    for lane in &bins.lanes {
+        assert_eq!(lane.cells.len(), 7);
+        assert_eq!(lane.cells.iter().sum::<i64>(), lane.total, "{}", lane.name);
+    }
+}
+
+#[test]
+fn idle_gap_is_its_own_setting() {
+    let config: Config = toml::from_str("[breaks]\nreset_after = \"3h\"\n").unwrap();
+    assert_eq!((config.idle_gap_seconds(), config.deep_work_seconds()), (300, 1500));
+    let config: Config = toml::from_str("idle_gap = \"30m\"\n").unwrap();
+    assert_eq!(config.idle_gap_seconds(), 1800);
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use common::date;
+use focusd_core::timezone::Zone;
+
+fn ts(s: &str) -> i64 {
+    DateTime::parse_from_rfc3339(s).unwrap().timestamp()
+}
+
+fn time(s: &str) -> NaiveTime {
+    NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+}
+
+/// (day, hour, seconds) pieces of a whole day, as reports see it
+fn hours_of(zone: Zone, day: NaiveDate, day_start: NaiveTime) -> Vec<(NaiveDate, u32, i64)> {
+    let start = zone.day_start(day, day_start);
+    let end = zone.day_start(day.succ_opt().unwrap(), day_start);
+    let mut pieces = Vec::new();
+    zone.split_hours(start, end, day_start, |d, h, s| pieces.push((d, h, s)));
+    pieces
+}
+
+#[test]
+fn spring_forward_day_has_23_hours() {
+    let berlin: Zone = "Europe/Berlin".parse().unwrap();
+    let day = date("2026-03-29");
+    let midnight = NaiveTime::MIN;
+
+    let length = berlin.day_start(day.succ_opt().unwrap(), midnight) - berlin.day_start(day, midnight);
+    assert_eq!(length, 23 * 3600);
+
+    let pieces = hours_of(berlin, day, midnight);
+    assert_eq!(pieces.len(), 23);
+    assert!(pieces.iter().all(|&(d, _, s)| d == day && s == 3600));
+    // 02:00 never happens
+    let hours: Vec<u32> = pieces.iter().map(|p| p.1).collect();
+    assert_eq!(hours, [0, 1].into_iter().chain(3..24).collect::<Vec<_>>());
+}
+
+#[test]
+fn fall_back_day_has_25_hours() {
+    let berlin: Zone = "Europe/Berlin".parse().unwrap();
+    let day = date("2026-10-25");
+    let midnight = NaiveTime::MIN;
+
+    let length = berlin.day_start(day.succ_opt().unwrap(), midnight) - berlin.day_start(day, midnight);
+    assert_eq!(length, 25 * 3600);
+
+    let pieces = hours_of(berlin, day, midnight);
+    assert_eq!(pieces.len(), 25);
+    assert!(pieces.iter().all(|&(d, _, s)| d == day && s == 3600));
+    // 02:00 happens twice
+    assert_eq!(pieces.iter().filter(|p| p.1 == 2).count(), 2);
+    assert_eq!(pieces.iter().map(|p| p.2).sum::<i64>(), 25 * 3600);
+}
+
+#[test]
+fn dst_days_in_another_hemisphere_and_zone() {
+    let new_york: Zone = "America/New_York".parse().unwrap();
+    let midnight = NaiveTime::MIN;
+    let length = |day: &str| {
+        let day = date(day);
+        new_york.day_start(day.succ_opt().unwrap(), midnight) - new_york.day_start(day, midnight)
+    };
+    assert_eq!(length("2026-03-08"), 23 * 3600);
+    assert_eq!(length("2026-11-01"), 25 * 3600);
+    assert_eq!(length("2026-07-01"), 24 * 3600);
+
+    // Sydney falls back in April and springs forward in October
+    let sydney: Zone = "Australia/Sydney".parse().unwrap();
+    let length = |day: &str| {
+        let day = date(day);
+        sydney.day_start(day.succ_opt().unwrap(), midnight) - sydney.day_start(day, midnight)
+    };
+    assert_eq!(length("2026-04-05"), 25 * 3600);
+    assert_eq!(length("2026-10-04"), 23 * 3600);
+}
+
+#[test]
+fn day_start_skipped_by_the_jump_begins_at_the_jump() {
+    let berlin: Zone = "Europe/Berlin".parse().unwrap();
+    let day_start = time("02:30");
+
+    // 02:00 CET jumps straight to 03:00 CEST at 01:00 UTC
+    assert_eq!(berlin.day_start(date("2026-03-29"), day_start), ts("2026-03-29T01:00:00Z"));
+    let length = berlin.day_start(date("2026-03-30"), day_start) - berlin.day_start(date("2026-03-29"), day_start);
+    assert_eq!(length, 23 * 3600 + 30 * 60);
+
+    // A few minutes before the jump still belongs to the day before
+    assert_eq!(berlin.date_of(ts("2026-03-29T00:55:00Z"), day_start), date("2026-03-28"));
+    assert_eq!(berlin.date_of(ts("2026-03-29T01:00:00Z"), day_start), date("2026-03-29"));
+}
+
+#[test]
+fn day_start_repeated_by_the_jump_begins_the_first_time() {
+    let berlin: Zone = "Europe/Berlin".parse().unwrap();
+    let day_start = time("02:30");
+
+    // 02:30 CEST comes before 02:30 CET
+    assert_eq!(berlin.day_start(date("2026-10-25"), day_start), ts("2026-10-25T00:30:00Z"));
+    let pieces = hours_of(berlin, date("2026-10-25"), day_start);
+    assert_eq!(pieces.iter().map(|p| p.2).sum::<i64>(), 25 * 3600);
+    assert!(pieces.iter().all(|p| p.0 == date("2026-10-25")));
+}
+
+#[test]
+fn recorded_offset_wins_unless_a_zone_is_chosen() {
+    // A session logged in Tokyo at 23:30 local time
+    let at = ts("2026-06-01T23:30:00+09:00");
+    let recorded = Zone::Recorded.resolve(Some(9 * 3600));
+    assert_eq!(recorded.date_of(at, NaiveTime::MIN), date("2026-06-01"));
+    assert_eq!(recorded.wall_clock(at).format("%H:%M").to_string(), "23:30");
+
+    // The same moment back home in Berlin was mid-afternoon
+    let berlin = "Europe/Berlin".parse::<Zone>().unwrap().resolve(Some(9 * 3600));
+    assert_eq!(berlin.date_of(at, NaiveTime::MIN), date("2026-06-01"));
+    assert_eq!(berlin.wall_clock(at).format("%H:%M").to_string(), "16:30");
+
+    // And already the next day in Auckland
+    let auckland = "+12:00".parse::<Zone>().unwrap();
+    assert_eq!(auckland.date_of(at, NaiveTime::MIN), date("2026-06-02"));
+}
+
+#[test]
+fn split_hours_cuts_at_day_start() {
+    let utc: Zone = "UTC".parse().unwrap();
+    let mut pieces = Vec::new();
+    utc.split_hours(ts("2026-06-01T03:15:00Z"), ts("2026-06-01T05:00:00Z"), time("04:00"), |d, h, s| pieces.push((d, h, s)));
+    assert_eq!(pieces, [
+        (date("2026-05-31"), 3, 45 * 60),
+        (date("2026-06-01"), 4, 3600),
+    ]);
+}
+
+#[test]
+fn parses_zones() {
+    assert_eq!("recorded".parse::<Zone>().unwrap(), Zone::Recorded);
+    assert_eq!("local".parse::<Zone>().unwrap(), Zone::Local);
+    assert_eq!("+05:30".parse::<Zone>().unwrap().offset_at(0), 5 * 3600 + 30 * 60);
+    assert_eq!("-0800".parse::<Zone>().unwrap().offset_at(0), -8 * 3600);
+    assert_eq!("+02".parse::<Zone>().unwrap().offset_at(0), 2 * 3600);
+    assert_eq!("utc".parse::<Zone>().unwrap().offset_at(0), 0);
+    assert!(matches!("Asia/Kolkata".parse::<Zone>().unwrap(), Zone::Named(_)));
+    assert!("Mars/Olympus".parse::<Zone>().is_err());
+    assert!("+25:00".parse::<Zone>().is_err());
+}
@@ -1,18 +1,23 @@
-use focusd_core::{db::Db, config::Config};
-use chrono::{Local, Duration, Datelike};
+use focusd_core::{db::{Db, Device, FocusMetrics, TimelineEntry}, config::Config, ipc, compare};
+use std::collections::HashMap;
+use chrono::{Duration, Datelike};
 
 #[derive(serde::Serialize)]
 struct DashboardData {
     total_seconds: i64,
     apps: Vec<(String, i64)>, // Name, Seconds
     chart: Vec<(String, i64)>, // Date Label (Mon/Tue), Seconds
+    icons: HashMap<String, String>, // Name -> icon file from the app's .desktop entry
+    focus: FocusMetrics, // Switches and stretch lengths over the whole range
+    focus_apps: Vec<(String, FocusMetrics)>, // Same, per app
+    focus_days: Vec<(String, FocusMetrics)>, // Same, per day (week view)
 }
 
 #[tauri::command]
-fn get_data(view: String) -> Result<DashboardData, String> {
-    let db = Db::init().map_err(|e| e.to_string())?;
+fn get_data(view: String, device: Option<String>) -> Result<DashboardData, String> {
     let config = Config::load();
-    let today = Local::now().date_naive();
+    let db = open_db(device)?;
+    let today = db.today();
 
     // 1. Determine Range
     let (start, end) = if view == "week" {
@@ -23,14 +28,8 @@ fn get_data(view: String) -> Result<DashboardData, String> {
         (today, today)
     };
 
-    // 2. Fetch Apps List (Summed over range)
-    let raw = db.get_app_usage_range(start, end).map_err(|e| e.to_string())?;
-    
-    // Apply aliases & aliases map
-    let apps: Vec<(String, i64)> = raw.into_iter().map(|(id, sec)| {
-        let name = config.alias.get(&id).unwrap_or(&id).to_string();
-        (name, sec)
-    }).collect();
+    // 2. Fetch Apps List (Summed over range, names already resolved by the DB)
+    let apps = db.get_app_usage_range(start, end).map_err(|e| e.to_string())?;
     
     let total = apps.iter().map(|(_, s)| s).sum();
 
@@ -46,14 +45,129 @@ fn get_data(view: String) -> Result<DashboardData, String> {
         }
     }
 
-    Ok(DashboardData { total_seconds: total, apps, chart })
+    let icons = db.list_apps().map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|a| Some((a.display_name, a.icon?)))
+        .collect();
+
+    // 4. Context switches & deep work
+    let deep_work = config.deep_work_seconds();
+    let idle_gap = config.idle_gap_seconds();
+    let focus = db.get_focus_metrics(start, end, deep_work, idle_gap).map_err(|e| e.to_string())?;
+    let focus_apps = db.get_app_focus_metrics(start, end, deep_work, idle_gap).map_err(|e| e.to_string())?;
+    let focus_days = if view == "week" {
+        db.get_daily_focus_metrics(start, end, deep_work, idle_gap).map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|(d, m)| (d.format("%a").to_string(), m))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(DashboardData { total_seconds: total, apps, chart, icons, focus, focus_apps, focus_days })
+}
+
+/// Opens the database with display names resolved, optionally for one device
+fn open_db(device: Option<String>) -> Result<Db, String> {
+    let config = Config::load();
+    let mut db = Db::init().map_err(|e| e.to_string())?;
+    db.set_config_aliases(&config.alias).map_err(|e| e.to_string())?;
+    db.set_day_start(config.day_start_time());
+    // No device means all devices combined
+    if let Some(device) = device {
+        db.set_device_filter(&device).map_err(|e| e.to_string())?;
+    }
+    Ok(db)
+}
+
+#[derive(serde::Serialize)]
+struct HeatmapData {
+    hours: [i64; 24],            // Seconds per hour of the day
+    weekdays: [[i64; 24]; 7],    // Monday first, then per hour
+}
+
+#[tauri::command]
+fn get_heatmap(days: i64, device: Option<String>) -> Result<HeatmapData, String> {
+    let db = open_db(device)?;
+    let end = db.today();
+    let start = end - Duration::days(days.max(1) - 1);
+    Ok(HeatmapData {
+        hours: db.get_hourly_totals(start, end).map_err(|e| e.to_string())?,
+        weekdays: db.get_weekday_hour_totals(start, end).map_err(|e| e.to_string())?,
+    })
+}
+
+/// `date` is YYYY-MM-DD; today when missing
+#[tauri::command]
+fn get_timeline(date: Option<String>, device: Option<String>) -> Result<Vec<TimelineEntry>, String> {
+    let db = open_db(device)?;
+    let date = match date {
+        Some(d) => d.parse().map_err(|e: chrono::ParseError| e.to_string())?,
+        None => db.today(),
+    };
+    db.get_timeline(date).map_err(|e| e.to_string())
+}
+
+/// Ranges as for `focusd compare`, e.g. "this-week" against "last-week"
+#[tauri::command]
+fn get_comparison(range: String, against: String, device: Option<String>) -> Result<compare::Comparison, String> {
+    let db = open_db(device)?;
+    let today = db.today();
+    let current = compare::DateRange::parse(&range, today).map_err(|e| e.to_string())?;
+    let previous = compare::DateRange::parse(&against, today).map_err(|e| e.to_string())?.same_days_as(&current, today);
+    compare::compare(&db, current, previous).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_devices() -> Result<Vec<Device>, String> {
+    let db = Db::init().map_err(|e| e.to_string())?;
+    db.list_devices().map_err(|e| e.to_string())
+}
+
+// The timer runs in the daemon; these just talk to its control socket
+
+fn send(request: ipc::Request) -> Result<ipc::Response, String> {
+    let response = ipc::send(&request).map_err(|e| e.to_string())?;
+    if !response.ok {
+        return Err(response.error.unwrap_or_default());
+    }
+    Ok(response)
+}
+
+#[tauri::command]
+fn pomodoro_start(work_minutes: Option<u64>, break_minutes: Option<u64>) -> Result<ipc::PomodoroStatus, String> {
+    let response = send(ipc::Request::PomodoroStart {
+        work: work_minutes.map(|m| m * 60),
+        short_break: break_minutes.map(|m| m * 60),
+    })?;
+    serde_json::from_value(response.data.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn pomodoro_stop() -> Result<(), String> {
+    send(ipc::Request::PomodoroStop).map(|_| ())
+}
+
+#[tauri::command]
+fn pomodoro_status() -> Result<ipc::PomodoroStatus, String> {
+    let response = send(ipc::Request::PomodoroStatus)?;
+    serde_json::from_value(response.data.unwrap_or_default()).map_err(|e| e.to_string())
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![get_data])
+        .invoke_handler(tauri::generate_handler![
+            get_data,
+            get_devices,
+            get_heatmap,
+            get_timeline,
+            get_comparison,
+            pomodoro_start,
+            pomodoro_stop,
+            pomodoro_status
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
\ No newline at end of file